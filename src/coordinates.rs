@@ -2,7 +2,7 @@
 use std::cmp;
 use std::fmt::Debug;
 use std::ops::{Add, Sub, Mul, Div, Neg};
-use super::nalgebra::{Cast, cast, Point2, Transpose, zero};
+use super::nalgebra::{ApproxEq, Cast, cast, Point2, Transpose, zero};
 use super::num::Zero;
 
 // TODO: use this for all the Size and Rect impls. I think it's needlessly complicated to have
@@ -188,6 +188,28 @@ impl<Nin: Copy, Nout: Copy + Cast<Nin>> Cast<Rect<Nin>> for Rect<Nout> {
     }
 }
 
+impl<N> ApproxEq<N> for Rect<N> where N: ApproxEq<N> {
+    fn approx_epsilon(_: Option<Self>) -> N {
+        N::approx_epsilon(None)
+    }
+
+    fn approx_eq_eps(&self, other: &Self, epsilon: &N) -> bool {
+        self.x.approx_eq_eps(&other.x, epsilon) &&
+        self.y.approx_eq_eps(&other.y, epsilon) &&
+        self.width.approx_eq_eps(&other.width, epsilon) &&
+        self.height.approx_eq_eps(&other.height, epsilon)
+    }
+    fn approx_ulps(_: Option<Self>) -> u32 {
+        N::approx_ulps(None)
+    }
+    fn approx_eq_ulps(&self, other: &Self, ulps: u32) -> bool {
+        self.x.approx_eq_ulps(&other.x, ulps) &&
+        self.y.approx_eq_ulps(&other.y, ulps) &&
+        self.width.approx_eq_ulps(&other.width, ulps) &&
+        self.height.approx_eq_ulps(&other.height, ulps)
+    }
+}
+
 impl<N> Rect<N> where N: Copy + Add<Output = N> {
     #[inline]
     pub fn right(&self) -> N {
@@ -428,6 +450,45 @@ impl<N> Sub for BorderSize2<N> where N: Copy + Sub<Output = N> {
     }
 }
 
+/// Corner radii store a radius for the top left, top right, bottom right, and bottom left corners
+/// of a rectangle
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CornerRadii2<N> {
+    pub top_left: N,
+    pub top_right: N,
+    pub bottom_right: N,
+    pub bottom_left: N,
+}
+
+impl<N> CornerRadii2<N> {
+    pub fn new(top_left: N, top_right: N, bottom_right: N, bottom_left: N) -> Self {
+        CornerRadii2 {
+            top_left: top_left,
+            top_right: top_right,
+            bottom_right: bottom_right,
+            bottom_left: bottom_left,
+        }
+    }
+}
+
+impl<N: Copy> CornerRadii2<N> {
+    /// Creates corner radii that are the same for every corner.
+    pub fn uniform(radius: N) -> Self {
+        CornerRadii2::new(radius, radius, radius, radius)
+    }
+}
+
+impl<Nin: Copy, Nout: Copy + Cast<Nin>> Cast<CornerRadii2<Nin>> for CornerRadii2<Nout> {
+    fn from(radii: CornerRadii2<Nin>) -> CornerRadii2<Nout> {
+        CornerRadii2 {
+            top_left: cast(radii.top_left),
+            top_right: cast(radii.top_right),
+            bottom_right: cast(radii.bottom_right),
+            bottom_left: cast(radii.bottom_left),
+        }
+    }
+}
+
 #[test]
 fn test_border_size() {
     assert_eq!(&(BorderSize2::new(3, 4, 6, 9) + BorderSize2::new(10, 20, 30, 40)),
@@ -435,3 +496,10 @@ fn test_border_size() {
     assert_eq!(&(BorderSize2::new(3, 4, 6, 9) - BorderSize2::new(10, 20, 30, 40)),
                &BorderSize2::new(-7, -16, -24, -31));
 }
+
+#[test]
+fn test_corner_radii() {
+    assert_eq!(CornerRadii2::uniform(5), CornerRadii2::new(5, 5, 5, 5));
+    let radii: CornerRadii2<i32> = cast(CornerRadii2::new(1.0, 2.0, 3.0, 4.0));
+    assert_eq!(radii, CornerRadii2::new(1, 2, 3, 4));
+}
@@ -3,8 +3,15 @@ use std::f32::consts::{FRAC_PI_2, PI};
 use std::fmt::Debug;
 use std::ops::{Add, Div, Mul, Sub};
 use super::{Point2, Rect, LargerFloat, QuadBezier};
-use super::nalgebra::{ApproxEq, BaseFloat, Cast, cast, Matrix2, Origin};
-use super::smallvec::SmallVec;
+use super::nalgebra::{ApproxEq, BaseFloat, Cast, cast, Dot, Matrix2, Norm, Origin, Vector2};
+use super::smallvec::{Array, SmallVec};
+
+// Limits how many times a curve is split in half when approximating it with quadratic Beziers, in
+// case the tolerance is zero.
+const MAX_QUAD_DEPTH: u32 = 10;
+
+// How many pieces a curve is divided into to measure how far a quadratic Bezier is from it
+const QUAD_ERROR_SAMPLES: u32 = 8;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum CurveType {
@@ -17,11 +24,12 @@ pub enum CurveType {
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct LoopPoints {
     // The first t value on the curve that intersects the second.
-    first: f32,
-    // The t value of the midpoint of the loop.
-    midpoint: f32,
+    pub first: f32,
+    // The t value of the midpoint of the loop, halfway between `first` and `second`. Splitting the
+    // curve there leaves two curves without loops.
+    pub midpoint: f32,
     // The second t value on the curve that intersects the first.
-    second: f32,
+    pub second: f32,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -187,14 +195,170 @@ impl<N, F> CubicBezier<N> where F: BaseFloat
         self.split_using_de_casteljau(t)
     }
 
-    // pub fn tangent_at(&self, t: f32) -> Vector2<N> {
-    // }
-    // pub fn project_point(&self, pt: Point2<N>) -> f32 {
-    // }
-    // pub fn point_at(&self, t: f32) -> Point2<N> {
-    // }
+    pub fn point_at(&self, t: f32) -> Point2<N> {
+        let _1_0: N::Float = cast(1.0);
+        let _3_0: N::Float = cast(3.0);
+        let t: N::Float = cast(t);
+        let (p0, p1, p2, p3) = (cast::<Point2<N>, Point2<N::Float>>(self.p0),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p1),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p2),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p3));
+
+        // Written so that t = 1 gives exactly the end point.
+        let one_m_t = _1_0 - t;
+        cast(p0 * (one_m_t * one_m_t * one_m_t)
+                + p1.to_vector() * (_3_0 * one_m_t * one_m_t * t)
+                + p2.to_vector() * (_3_0 * one_m_t * t * t)
+                + p3.to_vector() * (t * t * t))
+    }
+
+    // Returns the derivative of the curve with respect to t.
+    pub fn derivative_at(&self, t: f32) -> Vector2<N> {
+        let _1_0: N::Float = cast(1.0);
+        let _2_0: N::Float = cast(2.0);
+        let _3_0: N::Float = cast(3.0);
+        let t: N::Float = cast(t);
+        let (p0, p1, p2, p3) = (cast::<Point2<N>, Point2<N::Float>>(self.p0),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p1),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p2),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p3));
+
+        let one_m_t = _1_0 - t;
+        cast(((p1 - p0) * (one_m_t * one_m_t)
+                + (p2 - p1) * (_2_0 * one_m_t * t)
+                + (p3 - p2) * (t * t)) * _3_0)
+    }
+
+    pub fn second_derivative_at(&self, t: f32) -> Vector2<N> {
+        let _1_0: N::Float = cast(1.0);
+        let _6_0: N::Float = cast(6.0);
+        let t: N::Float = cast(t);
+        let (p0, p1, p2, p3) = (cast::<Point2<N>, Point2<N::Float>>(self.p0),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p1),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p2),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p3));
+
+        cast((((p2 - p1) - (p1 - p0)) * (_1_0 - t) + ((p3 - p2) - (p2 - p1)) * t) * _6_0)
+    }
+
+    // Returns a unit vector in the direction of the curve at t, or a zero vector if the curve is
+    // a single point.
+    pub fn tangent_at(&self, t: f32) -> Vector2<N> {
+        let _0_0: N::Float = cast(0.0);
+        let mut deriv = cast::<Vector2<N>, Vector2<N::Float>>(self.derivative_at(t));
+        if deriv.norm_squared() == _0_0 {
+            // The derivative is zero at a cusp or where a control point is on an end point, but
+            // the curve still has a direction there, which can be found from a point next to it.
+            deriv = cast(self.derivative_at(if t < 0.5 { t + 1e-3 } else { t - 1e-3 }));
+            if deriv.norm_squared() == _0_0 {
+                return cast(deriv);
+            }
+        }
+        cast(deriv.normalize())
+    }
+
+    // Returns the curvature at t, which is one over the radius of the circle that best fits the
+    // curve there. It's positive where the curve turns right (when y points down) and negative
+    // where it turns left. Where the derivative is zero, such as at a cusp, it isn't finite.
+    pub fn curvature_at(&self, t: f32) -> N::Float {
+        let deriv = cast::<Vector2<N>, Vector2<N::Float>>(self.derivative_at(t));
+        let second_deriv = cast::<Vector2<N>, Vector2<N::Float>>(self.second_derivative_at(t));
+        let speed = deriv.norm();
+        (deriv.x * second_deriv.y - deriv.y * second_deriv.x) / (speed * speed * speed)
+    }
+
+    // Returns the t values strictly between 0 and 1 where the x (`axis` = 0) or y (`axis` = 1)
+    // coordinate has a local minimum or maximum, in increasing order.
+    pub fn extrema(&self, axis: usize) -> SmallVec<[f32; 2]> {
+        let _2_0: N::Float = cast(2.0);
+        let _3_0: N::Float = cast(3.0);
+        let (p0, p1, p2, p3) = (cast::<Point2<N>, Point2<N::Float>>(self.p0),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p1),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p2),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p3));
+
+        // The derivative is a quadratic polynomial.
+        let a: f32 = cast(-p0[axis] + _3_0 * p1[axis] - _3_0 * p2[axis] + p3[axis]);
+        let b: f32 = cast(_2_0 * (p0[axis] - _2_0 * p1[axis] + p2[axis]));
+        let c: f32 = cast(p1[axis] - p0[axis]);
+        let mut ts = solve_quadratic(a, b, c);
+        ts.retain(|t| *t > 0.0 && *t < 1.0);
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        ts.dedup();
+        ts
+    }
+
+    // Splits the curve where it turns around in x or y, which makes pieces that each only go one
+    // way in x and in y. A piece's bounding box is the box around its end points.
+    pub fn split_at_extrema(&self) -> SmallVec<[CubicBezier<N>; 5]> {
+        let mut ts: SmallVec<[f32; 4]> = self.extrema(0).into_iter().chain(self.extrema(1))
+                                                                      .collect();
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        ts.dedup();
+        split_at_ts(*self, &ts, |bez, t| bez.split(t))
+    }
+
+    // Returns the point on the curve closest to `pt`, its t, and its distance from `pt`. Other
+    // than at the ends, the closest point is where the line to `pt` is perpendicular to the curve.
+    // For a cubic curve, that is a root of a fifth degree polynomial, so it's found numerically.
+    pub fn project_point(&self, pt: Point2<N>) -> (Point2<N>, f32, N::Float) {
+        let _2_0: N::Float = cast(2.0);
+        let _3_0: N::Float = cast(3.0);
+        let (p0, p1, p2, p3) = (cast::<Point2<N>, Point2<N::Float>>(self.p0),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p1),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p2),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p3));
+        let pt = cast::<Point2<N>, Point2<N::Float>>(pt);
+
+        // the curve as a polynomial, p0 + ct + bt^2 + at^3
+        let a = (p1 - p2) * _3_0 + (p3 - p0);
+        let b = ((p0 - p1) + (p2 - p1)) * _3_0;
+        let c = (p1 - p0) * _3_0;
+        let point_at = |t: N::Float| p0 + ((a * t + b) * t + c) * t;
+        // The derivative of half the squared distance is the dot product of the vector from `pt`
+        // and the derivative of the curve.
+        let t = find_minimum(32, |t| {
+            let t: N::Float = cast(t);
+            let v = point_at(t) - pt;
+            let deriv = (a * (_3_0 * t) + b * _2_0) * t + c;
+            (cast(v.norm_squared()), cast(v.dot(&deriv)))
+        });
+        let closest = point_at(cast(t));
+        (cast(closest), t, (closest - pt).norm())
+    }
+
     pub fn bounding_box(&self) -> Rect<N> {
-        unimplemented!()
+        // https://pomax.github.io/bezierinfo/#boundingbox
+        let _1_0: N::Float = cast(1.0);
+        let _2_0: N::Float = cast(2.0);
+        let _3_0: N::Float = cast(3.0);
+        let (p0, p1, p2, p3) = (cast::<Point2<N>, Point2<N::Float>>(self.p0),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p1),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p2),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p3));
+
+        let mut min = Point2::new(p0.x.min(p3.x), p0.y.min(p3.y));
+        let mut max = Point2::new(p0.x.max(p3.x), p0.y.max(p3.y));
+        // The curve can only go past its end points where the derivative is zero.
+        for axis in 0..2 {
+            let a: f32 = cast(-p0[axis] + _3_0 * p1[axis] - _3_0 * p2[axis] + p3[axis]);
+            let b: f32 = cast(_2_0 * (p0[axis] - _2_0 * p1[axis] + p2[axis]));
+            let c: f32 = cast(p1[axis] - p0[axis]);
+            for t in solve_quadratic(a, b, c) {
+                if t <= 0.0 || t >= 1.0 {
+                    continue;
+                }
+                let t: N::Float = cast(t);
+                let one_m_t = _1_0 - t;
+                let value = p0[axis] * one_m_t * one_m_t * one_m_t
+                          + _3_0 * p1[axis] * one_m_t * one_m_t * t
+                          + _3_0 * p2[axis] * one_m_t * t * t
+                          + p3[axis] * t * t * t;
+                min[axis] = min[axis].min(value);
+                max[axis] = max[axis].max(value);
+            }
+        }
+        Rect::new(cast(min.x), cast(min.y), cast(max.x - min.x), cast(max.y - min.y))
     }
 
     // Translates and rotates the curve so that the first point is at the origin (0, 0) and the
@@ -254,9 +418,13 @@ impl<N, F> CubicBezier<N> where F: BaseFloat
         let y = _18_0 * (_3_0 * a - b - _3_0 * c);
         let z = _18_0 * (c - a);
 
-        // can't divide by zero
+        // can't divide by zero, but then there's at most one inflection, where yt + z = 0
         if x == _0_0 {
-            return (None, None);
+            if y == _0_0 {
+                return (None, None);
+            }
+            let t: f32 = cast(-z / y);
+            return if t >= 0.0 && t <= 1.0 { (Some(t), None) } else { (None, None) };
         }
 
         // Quadratic formula
@@ -278,23 +446,167 @@ impl<N, F> CubicBezier<N> where F: BaseFloat
         }
     }
 
-    pub fn loop_points(&self) -> LoopPoints {
+    // Returns the t values where the curve crosses itself, or `None` if it doesn't have a loop
+    // between t = 0 and t = 1.
+    pub fn loop_points(&self) -> Option<LoopPoints> {
+        // https://comp.graphics.algorithms.narkive.com/tqLNEZqM/cubic-bezier-self-intersections
+        let _0_0: N::Float = cast(0.0);
+        let _1_0: N::Float = cast(1.0);
+        let _2_0: N::Float = cast(2.0);
+        let _3_0: N::Float = cast(3.0);
+        let _4_0: N::Float = cast(4.0);
+        let (p0, p1, p2, p3) = (cast::<Point2<N>, Point2<N::Float>>(self.p0),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p1),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p2),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p3));
 
-        unimplemented!()
+        // the curve as a polynomial, p0 + ct + bt^2 + at^3
+        let a = (p1 - p2) * _3_0 + (p3 - p0);
+        let b = ((p0 - p1) + (p2 - p1)) * _3_0;
+        let c = (p1 - p0) * _3_0;
+        // If the curve is at the same point at s and t, then dividing the difference of the
+        // polynomials by s - t gives a(s^2 + st + t^2) + b(s + t) + c = 0. With u = s + t and
+        // v = st, that's a(u^2 - v) + bu + c = 0, and taking the cross product with a gets rid
+        // of v, leaving (a x b)u + a x c = 0.
+        let cross = |v0: Vector2<F>, v1: Vector2<F>| v0.x * v1.y - v0.y * v1.x;
+        let a_cross_b = cross(a, b);
+        if a_cross_b == _0_0 {
+            return None;
+        }
+        let u = -cross(a, c) / a_cross_b;
+        let v = if a.x.abs() >= a.y.abs() {
+            u * u + (b.x * u + c.x) / a.x
+        } else {
+            u * u + (b.y * u + c.y) / a.y
+        };
+        // s and t are the roots of x^2 - ux + v = 0.
+        let discrim = u * u - _4_0 * v;
+        if !(discrim > _0_0) {
+            return None;
+        }
+        let first = (u - discrim.sqrt()) / _2_0;
+        let second = (u + discrim.sqrt()) / _2_0;
+        if first < _0_0 || second > _1_0 {
+            return None;
+        }
+        Some(LoopPoints {
+            first: cast(first),
+            midpoint: cast(u / _2_0),
+            second: cast(second),
+        })
     }
 
     // Returns a quadratic Bezier that approximates this curve. If this curve has an inflection or
-    // loop, it must be split before a quadratic Bezier can approximate it.
+    // loop, it must be split before a quadratic Bezier can approximate it, so `None` is returned.
+    // The quadratic Bezier has the same end points and, where it can, goes the same direction at
+    // them, so that the ones approximating neighboring pieces of a curve join smoothly.
     pub fn to_quad_bezier(&self) -> Option<QuadBezier<N>> {
-        unimplemented!()
+        // An inflection right at an end is where the curve was split at one.
+        let is_inside = |t: Option<f32>| t.map_or(false, |t| t > 1e-3 && t < 1.0 - 1e-3);
+        let (t0, t1) = self.inflection_points();
+        if is_inside(t0) || is_inside(t1) || self.loop_points().is_some() {
+            return None;
+        }
+        Some(self.quad_approximation())
+    }
+
+    fn quad_approximation(&self) -> QuadBezier<N> {
+        let _0_0: N::Float = cast(0.0);
+        let _3_0: N::Float = cast(3.0);
+        let _0_25: N::Float = cast(0.25);
+        let (p0, p1, p2, p3) = (cast::<Point2<N>, Point2<N::Float>>(self.p0),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p1),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p2),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p3));
+
+        // The directions at the ends, which come from the next control point when a control point
+        // is on an end point
+        let dir0 = if p1 != p0 { p1 - p0 } else { p2 - p0 };
+        let dir1 = if p3 != p2 { p3 - p2 } else { p3 - p1 };
+        // Where the lines through the ends in those directions cross, if that's in front of both
+        let cross = |v0: Vector2<F>, v1: Vector2<F>| v0.x * v1.y - v0.y * v1.x;
+        let denom = cross(dir0, dir1);
+        let s = cross(p3 - p0, dir1) / denom;
+        let v = cross(dir0, p3 - p0) / denom;
+        let control_pt = if denom != _0_0 && s > _0_0 && v > _0_0 {
+            p0 + dir0 * s
+        } else {
+            // This is the quadratic Bezier closest to the curve at its middle.
+            // https://caffeineowl.com/graphics/2d/vectorial/cubic2quad01.html
+            (((p1.to_vector() + p2.to_vector()) * _3_0 - p0.to_vector() - p3.to_vector()) * _0_25)
+                .to_point()
+        };
+        cast(QuadBezier::new(p0, control_pt, p3))
+    }
+
+    // Returns about how far the quadratic Bezier gets from this curve, measured from points along
+    // each of them to the other one.
+    fn quad_error(&self, quad: &QuadBezier<N>) -> f32 {
+        let _2_0: N::Float = cast(2.0);
+        let _3_0: N::Float = cast(3.0);
+        let (p0, p1, p2, p3) = (cast::<Point2<N>, Point2<N::Float>>(self.p0),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p1),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p2),
+                                cast::<Point2<N>, Point2<N::Float>>(self.p3));
+        let mut ts: SmallVec<[f32; 16]> = (1..QUAD_ERROR_SAMPLES).map(|i| {
+            i as f32 / QUAD_ERROR_SAMPLES as f32
+        }).collect();
+        // Where the curve turns around in x or y is often where it's farthest from the
+        // approximation, such as at the tip of a curve that nearly doubles back on itself.
+        for axis in 0..2 {
+            let a: f32 = cast(-p0[axis] + _3_0 * p1[axis] - _3_0 * p2[axis] + p3[axis]);
+            let b: f32 = cast(_2_0 * (p0[axis] - _2_0 * p1[axis] + p2[axis]));
+            let c: f32 = cast(p1[axis] - p0[axis]);
+            ts.extend(solve_quadratic(a, b, c).into_iter().filter(|&t| t > 0.0 && t < 1.0));
+        }
+        let mut error = 0.0f32;
+        for &t in &ts {
+            let (_, _, dist) = quad.project_point(self.point_at(t));
+            error = error.max(cast(dist));
+        }
+        for &t in &[0.25, 0.5, 0.75] {
+            let (_, _, dist) = self.project_point(quad.point_at(t));
+            error = error.max(cast(dist));
+        }
+        error
     }
 
+    // Adds quadratic Beziers to `quad_beziers` that together approximate this curve and are no
+    // farther than about `tolerance` from it. The curve is split at its inflections and in the
+    // middle of its loop, since a quadratic Bezier can't have either, and then the pieces are split
+    // in half until each one's approximation is close enough.
     pub fn get_quad_bezier_approximation(&self,
                                          quad_beziers: &mut SmallVec<[QuadBezier<N>; 2]>,
                                          tolerance: f32) {
+        let mut split_ts: SmallVec<[f32; 2]> = SmallVec::new();
+        match self.inflection_points() {
+            (Some(t0), Some(t1)) => split_ts.extend_from_slice(&[t0, t1]),
+            (Some(t), None) | (None, Some(t)) => split_ts.push(t),
+            (None, None) => split_ts.extend(self.loop_points().map(|points| points.midpoint)),
+        }
+        split_ts.retain(|t| *t > 1e-3 && *t < 1.0 - 1e-3);
 
-        unimplemented!()
+        let pieces: SmallVec<[CubicBezier<N>; 3]> =
+            split_at_ts(*self, &split_ts, |bez, t| bez.split(t));
+        for piece in pieces {
+            piece.push_quad_beziers(quad_beziers, tolerance, MAX_QUAD_DEPTH);
+        }
+    }
 
+    fn push_quad_beziers(&self, quad_beziers: &mut SmallVec<[QuadBezier<N>; 2]>,
+                         tolerance: f32, depth: u32) {
+        if let Some(quad) = self.to_quad_bezier() {
+            if depth == 0 || self.quad_error(&quad) <= tolerance {
+                quad_beziers.push(quad);
+                return;
+            }
+        } else if depth == 0 {
+            quad_beziers.push(self.quad_approximation());
+            return;
+        }
+        let (first, second) = self.split(0.5);
+        first.push_quad_beziers(quad_beziers, tolerance, depth - 1);
+        second.push_quad_beziers(quad_beziers, tolerance, depth - 1);
     }
 
     pub fn curve_type(&self) -> CurveType {
@@ -355,19 +667,20 @@ impl<N, F> CubicBezier<N> where F: BaseFloat
             _ => {},
         }
 
-        // TODO: loop
-
-        CurveTypeData::Plain
+        match self.loop_points() {
+            Some(loop_points) => CurveTypeData::FormsLoop(loop_points),
+            None => CurveTypeData::Plain,
+        }
     }
 }
 
-struct Roots {
+pub(crate) struct Roots {
     arr: [f32; 3],
     len: u32,
 }
 
 impl Roots {
-    fn get(&self) -> &[f32] {
+    pub(crate) fn get(&self) -> &[f32] {
         &self.arr[0..self.len as usize]
     }
 }
@@ -391,7 +704,31 @@ fn reduce_to_depressed_cubic(a: f32, b: f32, c: f32, d: f32) -> (f32, f32) {
 }
 
 
-fn solve_cubic(a: f32, b: f32, c: f32, d: f32) -> Roots {
+// Returns the real roots of ax^2 + bx + c = 0, handling when a is zero.
+pub(crate) fn solve_quadratic(a: f32, b: f32, c: f32) -> SmallVec<[f32; 2]> {
+    let mut roots = SmallVec::new();
+    // If `a` is tiny compared to the other coefficients, the equation is close to linear, and the
+    // quadratic formula loses precision.
+    if a.abs() <= 1e-6 * (b.abs() + c.abs()) {
+        if b != 0.0 {
+            roots.push(-c / b);
+        }
+        return roots;
+    }
+    let discrim = b * b - 4.0 * a * c;
+    if discrim < 0.0 {
+        return roots;
+    }
+    // https://en.wikipedia.org/wiki/Loss_of_significance#A_better_algorithm
+    let q = -0.5 * (b + discrim.sqrt().copysign(b));
+    roots.push(q / a);
+    if q != 0.0 && discrim > 0.0 {
+        roots.push(c / q);
+    }
+    roots
+}
+
+pub(crate) fn solve_cubic(a: f32, b: f32, c: f32, d: f32) -> Roots {
     let (p, q) = reduce_to_depressed_cubic(a, b, c, d);
     let b_d_3a = b / (3.0 * a);
 
@@ -428,14 +765,85 @@ fn solve_cubic(a: f32, b: f32, c: f32, d: f32) -> Roots {
         let delta_sqrt = delta.sqrt();
         let u = (-q_d_2 + delta_sqrt).cbrt();
         let v = (q_d_2 + delta_sqrt).cbrt();
+        let mut root = u - v - b_d_3a;
+
+        // When u and v are almost the same, subtracting them loses most of the precision, so
+        // improve the root with Newton's method, keeping each step that gets closer.
+        let value_at = |x: f32| ((a * x + b) * x + c) * x + d;
+        for _ in 0..4 {
+            let slope = (3.0 * a * root + 2.0 * b) * root + c;
+            if slope == 0.0 {
+                break;
+            }
+            let next = root - value_at(root) / slope;
+            if !(value_at(next).abs() < value_at(root).abs()) {
+                break;
+            }
+            root = next;
+        }
 
         return Roots {
-            arr: [u - v - b_d_3a, 0.0, 0.0],
+            arr: [root, 0.0, 0.0],
             len: 1,
         };
     }
 }
 
+// Returns the t from 0 to 1 where a function is smallest, given a closure that returns the
+// function's value and derivative at t. Other than at the ends, a minimum is where the derivative
+// goes from negative to positive, which is looked for between `sample_count` evenly spaced t
+// values. Two minimums closer together than that spacing can be missed.
+pub(crate) fn find_minimum<V>(sample_count: u32, value_and_slope: V) -> f32
+    where V: Fn(f32) -> (f32, f32) {
+    let (mut best_t, mut best_value) = (0.0, value_and_slope(0.0).0);
+    let (mut prev_t, mut prev_slope) = (0.0, value_and_slope(0.0).1);
+    for i in 1..=sample_count {
+        let t = i as f32 / sample_count as f32;
+        let (value, slope) = value_and_slope(t);
+        if value < best_value {
+            best_t = t;
+            best_value = value;
+        }
+        if prev_slope < 0.0 && slope >= 0.0 {
+            let (mut t0, mut t1) = (prev_t, t);
+            for _ in 0..32 {
+                let mid = (t0 + t1) * 0.5;
+                if value_and_slope(mid).1 < 0.0 {
+                    t0 = mid;
+                } else {
+                    t1 = mid;
+                }
+            }
+            let root = (t0 + t1) * 0.5;
+            let value = value_and_slope(root).0;
+            if value < best_value {
+                best_t = root;
+                best_value = value;
+            }
+        }
+        prev_t = t;
+        prev_slope = slope;
+    }
+    best_t
+}
+
+// Splits a curve at each of the t values, which must be in increasing order, and returns the
+// pieces. `split` splits a curve into two at a t value.
+pub(crate) fn split_at_ts<C, A, S>(curve: C, ts: &[f32], split: S) -> SmallVec<A>
+    where A: Array<Item = C>, S: Fn(&C, f32) -> (C, C) {
+    let mut pieces = SmallVec::new();
+    let mut rest = curve;
+    let mut prev_t = 0.0;
+    for &t in ts {
+        let (piece, next_rest) = split(&rest, (t - prev_t) / (1.0 - prev_t));
+        pieces.push(piece);
+        rest = next_rest;
+        prev_t = t;
+    }
+    pieces.push(rest);
+    pieces
+}
+
 #[cfg(test)]
 mod benchmarks {
     use std::hint::black_box;
@@ -521,6 +929,145 @@ fn test_split() {
     assert_approx_eq!(pair0.1, pair1.1);
 }
 
+#[test]
+fn test_point_at() {
+    let bez = CubicBezier::new(Point2::new(5.0f32, 10.0), Point2::new(10.0, 30.0),
+                               Point2::new(50.0, 30.0), Point2::new(60.0, 10.0));
+    assert_eq!(bez.point_at(0.0), bez.p0);
+    assert_eq!(bez.point_at(1.0), bez.p3);
+    assert_approx_eq!(bez.point_at(0.5), Point2::new(30.625, 25.0));
+    for &t in &[0.1, 0.3, 0.7] {
+        assert_approx_eq_eps!(bez.point_at(t), bez.split(t).0.p3, 1e-4);
+    }
+}
+
+#[test]
+fn test_derivatives() {
+    let bez = CubicBezier::new(Point2::new(5.0f32, 10.0), Point2::new(10.0, 30.0),
+                               Point2::new(50.0, 30.0), Point2::new(60.0, 10.0));
+    assert_approx_eq!(bez.derivative_at(0.0), Vector2::new(15.0, 60.0));
+    assert_approx_eq!(bez.derivative_at(1.0), Vector2::new(30.0, -60.0));
+    assert_approx_eq!(bez.second_derivative_at(0.0), Vector2::new(210.0, -120.0));
+    for &t in &[0.1, 0.3, 0.5, 0.8] {
+        let h = 1e-3;
+        let diff = (bez.point_at(t + h) - bez.point_at(t - h)) / (2.0 * h);
+        assert_approx_eq_eps!(bez.derivative_at(t), diff, 0.5);
+        let diff = (bez.derivative_at(t + h) - bez.derivative_at(t - h)) / (2.0 * h);
+        assert_approx_eq_eps!(bez.second_derivative_at(t), diff, 0.5);
+        assert_approx_eq!(bez.tangent_at(t), bez.derivative_at(t).normalize());
+    }
+
+    // The derivative is zero where a control point is on an end point, but the tangent isn't.
+    let bez = CubicBezier::new(Point2::new(0.0f32, 0.0), Point2::new(0.0, 0.0),
+                               Point2::new(10.0, 10.0), Point2::new(20.0, 10.0));
+    assert_eq!(bez.derivative_at(0.0), Vector2::new(0.0, 0.0));
+    assert_approx_eq_eps!(bez.tangent_at(0.0), Vector2::new(1.0, 1.0).normalize(), 1e-2);
+    let bez = CubicBezier::new(Point2::new(5.0f32, 5.0), Point2::new(5.0, 5.0),
+                               Point2::new(5.0, 5.0), Point2::new(5.0, 5.0));
+    assert_eq!(bez.tangent_at(0.5), Vector2::new(0.0, 0.0));
+}
+
+#[test]
+fn test_curvature() {
+    // About a quarter of a circle with a radius of 100, going clockwise when y points down
+    let k = 0.552_284_8 * 100.0;
+    let bez = CubicBezier::new(Point2::new(100.0f32, 0.0), Point2::new(100.0, k),
+                               Point2::new(k, 100.0), Point2::new(0.0, 100.0));
+    for &t in &[0.0, 0.25, 0.5, 1.0] {
+        assert_approx_eq_eps!(bez.curvature_at(t), 1.0 / 100.0, 5e-4);
+    }
+    // The other way, it's negative.
+    let bez = CubicBezier::new(bez.p3, bez.p2, bez.p1, bez.p0);
+    assert_approx_eq_eps!(bez.curvature_at(0.5), -1.0 / 100.0, 5e-4);
+    // A straight line has none.
+    let bez = CubicBezier::new(Point2::new(0.0f32, 0.0), Point2::new(10.0, 10.0),
+                               Point2::new(20.0, 20.0), Point2::new(30.0, 30.0));
+    assert_eq!(bez.curvature_at(0.3), 0.0);
+}
+
+#[test]
+fn test_split_at_extrema() {
+    let bez = CubicBezier::new(Point2::new(5.0f32, 10.0), Point2::new(10.0, 30.0),
+                               Point2::new(50.0, 30.0), Point2::new(60.0, 10.0));
+    assert!(bez.extrema(0).is_empty());
+    assert_eq!(bez.extrema(1).into_vec(), vec![0.5]);
+
+    // A curve with extrema in both x and y
+    let bez = CubicBezier::new(Point2::new(30.0f32, 350.0), Point2::new(135.0, 210.0),
+                               Point2::new(275.0, 176.0), Point2::new(220.0, 40.0));
+    let mut ts = bez.extrema(0).into_vec();
+    ts.extend(bez.extrema(1));
+    let pieces = bez.split_at_extrema();
+    assert_eq!(pieces.len(), ts.len() + 1);
+    assert_eq!(pieces[0].p0, bez.p0);
+    assert_eq!(pieces[pieces.len() - 1].p3, bez.p3);
+    for w in pieces.windows(2) {
+        assert_eq!(w[0].p3, w[1].p0);
+    }
+    // Each piece's bounding box is the box around its end points.
+    for piece in &pieces {
+        assert!(piece.extrema(0).is_empty() && piece.extrema(1).is_empty());
+        let rect = piece.bounding_box();
+        assert_approx_eq_eps!(rect.x, piece.p0.x.min(piece.p3.x), 1e-3);
+        assert_approx_eq_eps!(rect.y, piece.p0.y.min(piece.p3.y), 1e-3);
+        assert_approx_eq_eps!(rect.right(), piece.p0.x.max(piece.p3.x), 1e-3);
+        assert_approx_eq_eps!(rect.bottom(), piece.p0.y.max(piece.p3.y), 1e-3);
+    }
+}
+
+#[test]
+fn test_bounding_box() {
+    let bez = CubicBezier::new(Point2::new(0.0, 0.0), Point2::new(0.0, 40.0),
+                               Point2::new(30.0, 40.0), Point2::new(30.0, 0.0));
+    assert_approx_eq!(bez.bounding_box(), Rect::new(0.0, 0.0, 30.0, 30.0));
+
+    // A loop that goes past both ends horizontally. Compare with the bounds of points along it.
+    let bez = CubicBezier::new(Point2::new(10.0f32, 0.0), Point2::new(40.0, 20.0),
+                               Point2::new(-20.0, 20.0), Point2::new(10.0, 0.0));
+    let (mut min, mut max) = (bez.p0, bez.p0);
+    for i in 0..=1000 {
+        let (pt, _) = bez.split(i as f32 / 1000.0);
+        min = Point2::new(min.x.min(pt.p3.x), min.y.min(pt.p3.y));
+        max = Point2::new(max.x.max(pt.p3.x), max.y.max(pt.p3.y));
+    }
+    assert_approx_eq_eps!(bez.bounding_box(), Rect::new(min.x, min.y, max.x - min.x, max.y - min.y),
+                          0.001);
+
+    let bez = CubicBezier::new(Point2::new(1.0, 2.0), Point2::new(3.0, 4.0),
+                               Point2::new(5.0, 6.0), Point2::new(7.0, 8.0));
+    assert_eq!(bez.bounding_box(), Rect::new(1.0, 2.0, 6.0, 6.0));
+}
+
+#[test]
+fn test_project_point() {
+    use nalgebra::Norm;
+
+    let bez = CubicBezier::new(Point2::new(0.0f32, 0.0), Point2::new(0.0, 40.0),
+                               Point2::new(30.0, 40.0), Point2::new(30.0, 0.0));
+    let (pt, t, dist) = bez.project_point(Point2::new(15.0, 50.0));
+    assert_approx_eq_eps!(pt, Point2::new(15.0, 30.0), 1e-4);
+    assert_approx_eq_eps!(t, 0.5, 1e-5);
+    assert_approx_eq_eps!(dist, 20.0, 1e-4);
+    // past the end
+    let (pt, t, dist) = bez.project_point(Point2::new(40.0, -10.0));
+    assert_eq!((pt, t), (bez.p3, 1.0));
+    assert_approx_eq!(dist, 200.0f32.sqrt());
+
+    // No point along the curve is closer than the one found.
+    let bez = CubicBezier::new(Point2::new(10.0f32, 0.0), Point2::new(40.0, 20.0),
+                               Point2::new(-20.0, 20.0), Point2::new(10.0, 0.0));
+    for &(x, y) in &[(10.0, 10.0), (10.0, 20.0), (-5.0, 3.0), (30.0, 0.0), (12.0, 9.0)] {
+        let target = Point2::new(x, y);
+        let (pt, t, dist) = bez.project_point(target);
+        assert_approx_eq!((pt - target).norm(), dist);
+        assert_approx_eq_eps!(bez.point_at(t), pt, 1e-3);
+        for i in 0..=1000 {
+            let (sample, _) = bez.split(i as f32 / 1000.0);
+            assert!((sample.p3 - target).norm() >= dist - 1e-3);
+        }
+    }
+}
+
 #[test]
 fn test_axis_aligned() {
     let bez0 = CubicBezier::new(Point2::new(80.0, 100.0), Point2::new(20.0, 150.0),
@@ -556,6 +1103,15 @@ fn test_inflection_points() {
                                Point2::new(150.0, 35.0));
     let pts = bez.inflection_points();
     assert_eq!((None, None), pts);
+
+    // In a symmetric S-curve, the t² term is zero, and the one inflection is in the middle.
+    let bez = CubicBezier::new(Point2::new(0.0, 0.0),
+                               Point2::new(30.0, 40.0),
+                               Point2::new(70.0, -40.0),
+                               Point2::new(100.0, 0.0));
+    let pts = bez.inflection_points();
+    assert_eq!(pts.1, None);
+    assert_approx_eq!(0.5, pts.0.unwrap());
 }
 
 #[test]
@@ -629,6 +1185,132 @@ fn test_curve_type() {
 
 }
 
+#[test]
+fn test_loop_points() {
+    // A loop that's symmetric about x = 50
+    let bez = CubicBezier::new(Point2::new(0.0f32, 0.0), Point2::new(150.0, 100.0),
+                               Point2::new(-50.0, 100.0), Point2::new(100.0, 0.0));
+    let loop_points = bez.loop_points().unwrap();
+    assert_approx_eq!(loop_points.midpoint, 0.5);
+    assert_approx_eq!(loop_points.first + loop_points.second, 1.0);
+    assert!(loop_points.first > 0.0 && loop_points.first < 0.5);
+    assert_approx_eq_eps!(bez.point_at(loop_points.first), bez.point_at(loop_points.second),
+                          1e-3);
+    assert_approx_eq_eps!(bez.point_at(loop_points.first).x, 50.0, 1e-3);
+    assert_eq!(bez.curve_type_data(), CurveTypeData::FormsLoop(loop_points));
+
+    // The same curves as in `test_curve_type()` only have loop points if they form a loop.
+    for &(x0, y0, x3, y3) in &[(30.0, 350.0, 220.0, 40.0), (135.0, 35.0, 220.0, 40.0),
+                               (135.0, 35.0, 155.0, 150.0), (135.0, 35.0, 151.0, 188.0),
+                               (200.0, 130.0, 220.0, 40.0), (260.0, 80.0, 220.0, 40.0),
+                               (380.0, 50.0, 220.0, 40.0)] {
+        let bez = CubicBezier::new(Point2::new(x0, y0), Point2::new(135.0f32, 210.0),
+                                   Point2::new(275.0, 176.0), Point2::new(x3, y3));
+        match bez.loop_points() {
+            Some(loop_points) => {
+                assert_eq!(bez.curve_type(), CurveType::FormsLoop);
+                assert_approx_eq_eps!(bez.point_at(loop_points.first),
+                                      bez.point_at(loop_points.second), 1e-2);
+            },
+            None => assert!(bez.curve_type() != CurveType::FormsLoop),
+        }
+    }
+
+    // A loop that would be past the end of the curve
+    let bez = CubicBezier::new(Point2::new(0.0f32, 0.0), Point2::new(150.0, 100.0),
+                               Point2::new(-50.0, 100.0), Point2::new(10.0, 30.0));
+    assert_eq!(bez.loop_points(), None);
+    // A straight line
+    let bez = CubicBezier::new(Point2::new(0.0f32, 0.0), Point2::new(10.0, 10.0),
+                               Point2::new(20.0, 20.0), Point2::new(30.0, 30.0));
+    assert_eq!(bez.loop_points(), None);
+}
+
+#[test]
+fn test_to_quad_bezier() {
+    // A quadratic Bezier raised to a cubic one gives the same quadratic Bezier back.
+    let quad = QuadBezier::new(Point2::new(0.0f32, 0.0), Point2::new(30.0, 60.0),
+                               Point2::new(90.0, 0.0));
+    let bez = CubicBezier::new(quad.p0, Point2::new(20.0, 40.0), Point2::new(50.0, 40.0), quad.p2);
+    assert_approx_eq_eps!(bez.to_quad_bezier().unwrap(), quad, 1e-4);
+
+    // Curves with an inflection or loop can't be approximated without being split.
+    let bez = CubicBezier::new(Point2::new(0.0f32, 0.0), Point2::new(40.0, -40.0),
+                               Point2::new(60.0, 40.0), Point2::new(100.0, 0.0));
+    assert_eq!(bez.to_quad_bezier(), None);
+    let bez = CubicBezier::new(Point2::new(0.0f32, 0.0), Point2::new(150.0, 100.0),
+                               Point2::new(-50.0, 100.0), Point2::new(100.0, 0.0));
+    assert_eq!(bez.to_quad_bezier(), None);
+
+    // The end directions are kept.
+    let bez = CubicBezier::new(Point2::new(0.0f32, 0.0), Point2::new(0.0, 50.0),
+                               Point2::new(50.0, 100.0), Point2::new(100.0, 100.0));
+    assert_approx_eq_eps!(bez.to_quad_bezier().unwrap().p1, Point2::new(0.0, 100.0), 1e-4);
+}
+
+#[test]
+fn test_get_quad_bezier_approximation() {
+    let curves = [
+        // a quarter of a circle
+        CubicBezier::new(Point2::new(100.0f32, 0.0), Point2::new(100.0, 55.228),
+                         Point2::new(55.228, 100.0), Point2::new(0.0, 100.0)),
+        // an S curve
+        CubicBezier::new(Point2::new(0.0, 0.0), Point2::new(40.0, -40.0),
+                         Point2::new(60.0, 40.0), Point2::new(100.0, 0.0)),
+        // a loop
+        CubicBezier::new(Point2::new(0.0, 0.0), Point2::new(150.0, 100.0),
+                         Point2::new(-50.0, 100.0), Point2::new(100.0, 0.0)),
+        // a turn so sharp the ends go opposite directions
+        CubicBezier::new(Point2::new(0.0, 0.0), Point2::new(0.0, 40.0),
+                         Point2::new(30.0, 40.0), Point2::new(30.0, 0.0)),
+        // a control point on an end point
+        CubicBezier::new(Point2::new(0.0, 0.0), Point2::new(0.0, 0.0),
+                         Point2::new(100.0, 50.0), Point2::new(100.0, 0.0)),
+    ];
+    for bez in &curves {
+        let mut counts = vec![];
+        for &tolerance in &[1.0, 0.1, 0.01] {
+            let mut quads = SmallVec::new();
+            bez.get_quad_bezier_approximation(&mut quads, tolerance);
+            counts.push(quads.len());
+
+            assert_eq!(quads[0].p0, bez.p0);
+            assert_eq!(quads[quads.len() - 1].p2, bez.p3);
+            for w in quads.windows(2) {
+                assert_approx_eq_eps!(w[0].p2, w[1].p0, 1e-3);
+            }
+            // Every point on the curve is close to one of the quadratic Beziers.
+            for i in 0..=200 {
+                let pt = bez.point_at(i as f32 / 200.0);
+                let dist = quads.iter()
+                                .map(|quad| quad.project_point(pt).2)
+                                .fold(f32::MAX, f32::min);
+                assert!(dist <= tolerance * 1.01, "{} from the curve at {:?}", dist, pt);
+            }
+        }
+        assert!(counts[0] <= counts[1] && counts[1] <= counts[2]);
+        assert!(counts[2] < 40);
+    }
+
+    // Some pieces are split more than others, which is what makes it better than splitting evenly.
+    let mut quads = SmallVec::new();
+    curves[4].get_quad_bezier_approximation(&mut quads, 0.1);
+    let lengths: Vec<f32> = quads.iter().map(|quad| (quad.p2 - quad.p0).norm()).collect();
+    let max = lengths.iter().cloned().fold(0.0, f32::max);
+    let min = lengths.iter().cloned().fold(f32::MAX, f32::min);
+    assert!(max > min * 2.0);
+}
+
+#[test]
+fn test_solve_quadratic() {
+    // (x - 2)(x + 3)
+    let mut roots = solve_quadratic(1.0, 1.0, -6.0);
+    roots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(&roots[..], &[-3.0, 2.0]);
+    assert_eq!(&solve_quadratic(0.0, 2.0, -1.0)[..], &[0.5]);
+    assert!(solve_quadratic(1.0, 0.0, 1.0).is_empty());
+}
+
 #[test]
 fn test_solve_cubic() {
     // I used Wolfram Alpha to solve and graph.
@@ -651,4 +1333,9 @@ fn test_solve_cubic() {
     assert_eq!(roots.get().len(), 2);
     assert_approx_eq!(roots.get()[0], 1.8164966);
     assert_approx_eq!(roots.get()[1], -0.6329931618);
+
+    // One real root, where the formula subtracts two numbers that are almost the same
+    let roots = solve_cubic(5.706_104, -57.431_62, 192.307_58, -69.737_69);
+    assert_eq!(roots.get().len(), 1);
+    assert_approx_eq_eps!(roots.get()[0], 0.411_030_56, 1e-6);
 }
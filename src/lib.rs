@@ -63,9 +63,9 @@ pub mod font;
 
 pub use nalgebra::{Point2, Vector2};
 pub use color::Color;
-pub use coordinates::{Size2, Rect, BorderSize2};
-pub use cubic_bezier::{CubicBezier, CurveType};
-pub use path::{PathSegment, PathBuf, StrokeStyle};
+pub use coordinates::{Size2, Rect, BorderSize2, CornerRadii2};
+pub use cubic_bezier::{CubicBezier, CurveType, CurveTypeData, LoopPoints};
+pub use path::{BakedFilledPath, BooleanOp, FillRule, FillVertex, Flatten, LineCap, LineJoin, Path, PathSegment, PathBuf, PathParseError, PathParseErrorKind, StrokeStyle, Subpaths, Transformed, TrimPosition};
 pub use quad_bezier::QuadBezier;
 pub use retained::{DrawCommand, ImageBuf, LinearGradient, ScalingMode, RenderingBackend, SwapchainSurface};
 pub use painter::{AsPathIter, Brush, Error, Painter, PainterExt};
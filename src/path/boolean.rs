@@ -0,0 +1,573 @@
+use nalgebra::{origin, Dot, Norm, Vector2};
+
+use crate::painter::AsPathIter;
+use crate::{Point2, Rect};
+use super::curve::Curve;
+use super::{FillRule, Path, PathBuf, PathSegment};
+
+// Limits how many times pieces of two curves are split while looking for where they intersect.
+pub(super) const MAX_DEPTH: u32 = 40;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BooleanOp {
+    Union,
+    Intersection,
+    // The area of the first path that isn't in the second
+    Difference,
+    // The area that is in exactly one of the paths
+    Xor,
+}
+
+impl BooleanOp {
+    fn contains(&self, in_a: bool, in_b: bool) -> bool {
+        match *self {
+            BooleanOp::Union => in_a || in_b,
+            BooleanOp::Intersection => in_a && in_b,
+            BooleanOp::Difference => in_a && !in_b,
+            BooleanOp::Xor => in_a != in_b,
+        }
+    }
+}
+
+// A part of a curve between two of the points it is split at
+struct Fragment {
+    curve: Curve,
+    start_vertex: usize,
+    end_vertex: usize,
+}
+
+impl<'a> Path<'a> {
+    // Combines the areas filled by this path and `other` and returns a path that fills the result.
+    // The result's edges are pieces of the two paths' segments, so curves stay curves. It has no
+    // overlapping or self-intersecting subpaths, and the filled area is always on the right side
+    // of each subpath (clockwise when y points down), so it can be filled using either fill rule.
+    pub fn boolean_op(&self, fill_rule: FillRule, other: &Path, other_fill_rule: FillRule,
+                      op: BooleanOp) -> PathBuf {
+        let mut curves = closed_curves(self);
+        curves.extend(closed_curves(other));
+        let mut result = PathBuf::new();
+        if curves.is_empty() {
+            return result;
+        }
+
+        // The tolerances are relative to the size of the paths so that they work at any scale.
+        let size = curves.iter()
+                         .map(|curve| curve.bounding_box())
+                         .fold(None, |acc: Option<Rect<f32>>, rect| {
+                             Some(acc.map_or(rect, |acc| acc.union(rect)))
+                         })
+                         .map(|rect| rect.width.max(rect.height))
+                         .unwrap();
+        let tolerance = (size * 1e-5).max(1e-6);
+        let vertex_tolerance = tolerance * 4.0;
+        let side_offset = tolerance * 10.0;
+
+        let split_ts = find_split_points(&curves, tolerance);
+        let mut vertices = vec![];
+        let fragments = split_curves(&curves, &split_ts, &mut vertices, vertex_tolerance);
+
+        // Keep each fragment that has the result's area on one side and not the other, turned so
+        // the area is on its right.
+        let mask = fill_rule.mask();
+        let other_mask = other_fill_rule.mask();
+        let is_inside = |pt: Point2<f32>| {
+            op.contains(self.is_point_in_fill(pt, mask), other.is_point_in_fill(pt, other_mask))
+        };
+        let mut kept: Vec<Fragment> = vec![];
+        for frag in fragments {
+            let mid_pt = frag.curve.point_at(0.5);
+            let offset = perpendicular(frag.curve.tangent_at(0.5)) * side_offset;
+            let inside_right = is_inside(mid_pt + offset);
+            let inside_left = is_inside(mid_pt - offset);
+            if inside_right == inside_left {
+                continue;
+            }
+            let frag = if inside_right {
+                frag
+            } else {
+                Fragment {
+                    curve: frag.curve.reversed(),
+                    start_vertex: frag.end_vertex,
+                    end_vertex: frag.start_vertex,
+                }
+            };
+            // Where the paths have edges on top of each other, only one is needed.
+            let is_duplicate = kept.iter().any(|other| {
+                other.start_vertex == frag.start_vertex && other.end_vertex == frag.end_vertex &&
+                (other.curve.point_at(0.5) - mid_pt).norm() <= vertex_tolerance
+            });
+            if !is_duplicate {
+                kept.push(frag);
+            }
+        }
+
+        // Connect the fragments into subpaths.
+        let mut outgoing = vec![vec![]; vertices.len()];
+        for (i, frag) in kept.iter().enumerate() {
+            outgoing[frag.start_vertex].push(i);
+        }
+        let mut used = vec![false; kept.len()];
+        for first in 0..kept.len() {
+            if used[first] {
+                continue;
+            }
+            used[first] = true;
+            result.move_to(kept[first].curve.point_at(0.0));
+            result.push_curve(&kept[first].curve);
+            let mut current = first;
+            while kept[current].end_vertex != kept[first].start_vertex {
+                // Where subpaths touch at a vertex, taking the sharpest right turn keeps them
+                // separate.
+                let dir = kept[current].curve.tangent_at(1.0);
+                let next = outgoing[kept[current].end_vertex].iter()
+                    .cloned()
+                    .filter(|&i| !used[i])
+                    .max_by(|&i, &j| {
+                        let turn_i = turn_angle(dir, kept[i].curve.tangent_at(0.0));
+                        let turn_j = turn_angle(dir, kept[j].curve.tangent_at(0.0));
+                        turn_i.partial_cmp(&turn_j).unwrap()
+                    });
+                let next = match next {
+                    Some(next) => next,
+                    // This only happens if the tolerances weren't enough to connect fragments.
+                    None => break,
+                };
+                used[next] = true;
+                result.push_curve(&kept[next].curve);
+                current = next;
+            }
+            result.close();
+        }
+        result
+    }
+}
+
+// Returns the curves of the path, with a line closing each subpath that isn't closed already.
+// Curves without any length are left out.
+fn closed_curves(path: &Path) -> Vec<Curve> {
+    let mut curves = vec![];
+    let mut current_pt = origin();
+    let mut subpath_start_pt = origin();
+    for seg in path.path_iter() {
+        match seg {
+            PathSegment::Move(pt) => {
+                curves.push(Curve::Line(current_pt, subpath_start_pt));
+                subpath_start_pt = pt;
+                current_pt = pt;
+            },
+            PathSegment::Close => {
+                curves.push(Curve::Line(current_pt, subpath_start_pt));
+                current_pt = subpath_start_pt;
+            },
+            _ => {
+                let curve = Curve::from_segment(current_pt, &seg).unwrap();
+                curves.push(curve);
+                current_pt = curve.end_point();
+            },
+        }
+    }
+    curves.push(Curve::Line(current_pt, subpath_start_pt));
+    curves.retain(|curve| curve.tangent_at(0.0).norm_squared() > 0.0);
+    curves
+}
+
+// Returns, for each curve, the t values where it intersects itself or any other curve, as well as
+// 0 and 1.
+fn find_split_points(curves: &[Curve], tolerance: f32) -> Vec<Vec<f32>> {
+    let pieces = monotonic_pieces(curves);
+    let mut split_ts = vec![vec![0.0, 1.0]; curves.len()];
+    let mut intersections = vec![];
+    for (i, &(curve_index0, t0, t1)) in pieces.iter().enumerate() {
+        for &(curve_index1, u0, u1) in &pieces[i + 1..] {
+            intersections.clear();
+            intersect_pieces(&curves[curve_index0], t0, t1, &curves[curve_index1], u0, u1,
+                             tolerance, MAX_DEPTH, &mut intersections);
+            for &(t, u) in &intersections {
+                // Neighboring pieces of a curve always touch where they meet.
+                if curve_index0 == curve_index1 && t1 == u0 {
+                    let curve = &curves[curve_index0];
+                    let meeting_pt = curve.point_at(t1);
+                    if (curve.point_at(t) - meeting_pt).norm() <= tolerance * 4.0 &&
+                       (curve.point_at(u) - meeting_pt).norm() <= tolerance * 4.0 {
+                        continue;
+                    }
+                }
+                split_ts[curve_index0].push(t);
+                split_ts[curve_index1].push(u);
+            }
+        }
+    }
+    split_ts
+}
+
+// Splits the curves where they turn around in x or y, which makes pieces whose bounding boxes are
+// just the boxes around their end points. Returns the index of each piece's curve and the t values
+// it goes between.
+pub(super) fn monotonic_pieces(curves: &[Curve]) -> Vec<(usize, f32, f32)> {
+    let mut pieces = vec![];
+    for (i, curve) in curves.iter().enumerate() {
+        let mut ts: Vec<f32> = curve.extrema(0).into_iter().chain(curve.extrema(1)).collect();
+        ts.push(0.0);
+        ts.push(1.0);
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        ts.dedup();
+        for w in ts.windows(2) {
+            pieces.push((i, w[0], w[1]));
+        }
+    }
+    pieces
+}
+
+// Finds where two monotonic pieces of curves intersect by splitting them in half until they are
+// close enough to lines, and adds the t values to `intersections`.
+pub(super) fn intersect_pieces(curve0: &Curve, t0: f32, t1: f32, curve1: &Curve, u0: f32,
+                               u1: f32, tolerance: f32, depth: u32,
+                               intersections: &mut Vec<(f32, f32)>) {
+    let (pt0, pt1) = (curve0.point_at(t0), curve0.point_at(t1));
+    let (pt2, pt3) = (curve1.point_at(u0), curve1.point_at(u1));
+    if pt0.x.min(pt1.x) > pt2.x.max(pt3.x) + tolerance ||
+       pt2.x.min(pt3.x) > pt0.x.max(pt1.x) + tolerance ||
+       pt0.y.min(pt1.y) > pt2.y.max(pt3.y) + tolerance ||
+       pt2.y.min(pt3.y) > pt0.y.max(pt1.y) + tolerance {
+        return;
+    }
+
+    let flat0 = is_flat(curve0, t0, t1, tolerance);
+    let flat1 = is_flat(curve1, u0, u1, tolerance);
+    if (flat0 && flat1) || depth == 0 {
+        for (s, v) in intersect_lines(pt0, pt1, pt2, pt3, tolerance) {
+            intersections.push((t0 + (t1 - t0) * s, u0 + (u1 - u0) * v));
+        }
+        return;
+    }
+    if !flat0 && (flat1 || (pt1 - pt0).norm_squared() >= (pt3 - pt2).norm_squared()) {
+        let mid = (t0 + t1) * 0.5;
+        intersect_pieces(curve0, t0, mid, curve1, u0, u1, tolerance, depth - 1, intersections);
+        intersect_pieces(curve0, mid, t1, curve1, u0, u1, tolerance, depth - 1, intersections);
+    } else {
+        let mid = (u0 + u1) * 0.5;
+        intersect_pieces(curve0, t0, t1, curve1, u0, mid, tolerance, depth - 1, intersections);
+        intersect_pieces(curve0, t0, t1, curve1, mid, u1, tolerance, depth - 1, intersections);
+    }
+}
+
+// Returns whether the part of the curve from t0 to t1 is within `tolerance` of a line.
+fn is_flat(curve: &Curve, t0: f32, t1: f32, tolerance: f32) -> bool {
+    if let Curve::Line(..) = *curve {
+        return true;
+    }
+    let (pt0, pt1) = (curve.point_at(t0), curve.point_at(t1));
+    [0.25, 0.5, 0.75].iter().all(|&s| {
+        dist_to_line(curve.point_at(t0 + (t1 - t0) * s), pt0, pt1) <= tolerance
+    })
+}
+
+fn dist_to_line(pt: Point2<f32>, line_pt0: Point2<f32>, line_pt1: Point2<f32>) -> f32 {
+    let dir = line_pt1 - line_pt0;
+    let len = dir.norm();
+    if len == 0.0 {
+        return (pt - line_pt0).norm();
+    }
+    cross(dir, pt - line_pt0).abs() / len
+}
+
+// Returns the fractions along each line segment where they intersect. If the lines overlap, the
+// ends of the overlapping part are returned.
+fn intersect_lines(pt0: Point2<f32>, pt1: Point2<f32>, pt2: Point2<f32>, pt3: Point2<f32>,
+                   tolerance: f32) -> Vec<(f32, f32)> {
+    let dir0 = pt1 - pt0;
+    let dir1 = pt3 - pt2;
+    let (len0, len1) = (dir0.norm(), dir1.norm());
+    let mut result = vec![];
+    if len0 == 0.0 || len1 == 0.0 {
+        return result;
+    }
+    // how far past the ends the intersection can be and still count
+    let (slack0, slack1) = (tolerance / len0, tolerance / len1);
+    let in_range = |s: f32, slack: f32| s >= -slack && s <= 1.0 + slack;
+    let clamp = |s: f32| s.max(0.0).min(1.0);
+
+    let denom = cross(dir0, dir1);
+    if denom.abs() > len0 * len1 * 1e-6 {
+        let s = cross(pt2 - pt0, dir1) / denom;
+        let v = cross(pt2 - pt0, dir0) / denom;
+        if in_range(s, slack0) && in_range(v, slack1) {
+            result.push((clamp(s), clamp(v)));
+        }
+        return result;
+    }
+    if dist_to_line(pt2, pt0, pt1) > tolerance {
+        return result;
+    }
+    // The lines are on top of each other, so find where each end is along the other line.
+    let project = |pt: Point2<f32>, line_pt: Point2<f32>, dir: Vector2<f32>, len: f32| {
+        (pt - line_pt).dot(&dir) / (len * len)
+    };
+    for &(s, pt) in &[(0.0, pt0), (1.0, pt1)] {
+        let v = project(pt, pt2, dir1, len1);
+        if in_range(v, slack1) {
+            result.push((s, clamp(v)));
+        }
+    }
+    for &(v, pt) in &[(0.0, pt2), (1.0, pt3)] {
+        let s = project(pt, pt0, dir0, len0);
+        if in_range(s, slack0) {
+            result.push((clamp(s), v));
+        }
+    }
+    result
+}
+
+// Splits the curves at the t values and returns the pieces. The ends of the pieces are added to
+// `vertices`, with points closer than `vertex_tolerance` treated as the same vertex.
+fn split_curves(curves: &[Curve], split_ts: &[Vec<f32>], vertices: &mut Vec<Point2<f32>>,
+                vertex_tolerance: f32) -> Vec<Fragment> {
+    let mut fragments = vec![];
+    for (curve, ts) in curves.iter().zip(split_ts) {
+        let mut ts = ts.clone();
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        // Drop split points that are too close together to make a useful fragment. The size of
+        // the part between them is what matters, since a curve that crosses itself is split at
+        // two t values with the same point.
+        let is_tiny = |t0: f32, t1: f32| {
+            let size = curve.subcurve(t0, t1).bounding_box();
+            size.width.max(size.height) <= vertex_tolerance
+        };
+        let mut kept_ts = vec![0.0];
+        for &t in &ts {
+            if t > 0.0 && t < 1.0 && !is_tiny(kept_ts[kept_ts.len() - 1], t) && !is_tiny(t, 1.0) {
+                kept_ts.push(t);
+            }
+        }
+        kept_ts.push(1.0);
+
+        for w in kept_ts.windows(2) {
+            let mut frag_curve = curve.subcurve(w[0], w[1]);
+            let start_vertex = find_vertex(vertices, frag_curve.point_at(0.0), vertex_tolerance);
+            let end_vertex = find_vertex(vertices, frag_curve.end_point(), vertex_tolerance);
+            let size = frag_curve.bounding_box();
+            if start_vertex == end_vertex && size.width.max(size.height) <= vertex_tolerance {
+                continue;
+            }
+            // Move the ends onto the vertices so that the fragments connect exactly. An arc's end
+            // points can't be moved, but they are within the tolerance.
+            let (start_pt, end_pt) = (vertices[start_vertex], vertices[end_vertex]);
+            match frag_curve {
+                Curve::Line(ref mut pt0, ref mut pt1) => {
+                    *pt0 = start_pt;
+                    *pt1 = end_pt;
+                },
+                Curve::Quad(ref mut bez) => {
+                    bez.p0 = start_pt;
+                    bez.p2 = end_pt;
+                },
+                Curve::Cubic(ref mut bez) => {
+                    bez.p0 = start_pt;
+                    bez.p3 = end_pt;
+                },
+                Curve::Arc(_) => {},
+            }
+            fragments.push(Fragment { curve: frag_curve, start_vertex, end_vertex });
+        }
+    }
+    fragments
+}
+
+fn find_vertex(vertices: &mut Vec<Point2<f32>>, pt: Point2<f32>, vertex_tolerance: f32) -> usize {
+    match vertices.iter().position(|v| (*v - pt).norm() <= vertex_tolerance) {
+        Some(index) => index,
+        None => {
+            vertices.push(pt);
+            vertices.len() - 1
+        },
+    }
+}
+
+fn cross(v0: Vector2<f32>, v1: Vector2<f32>) -> f32 {
+    v0.x * v1.y - v0.y * v1.x
+}
+
+fn perpendicular(v: Vector2<f32>) -> Vector2<f32> {
+    Vector2::new(-v.y, v.x)
+}
+
+// Returns the angle from `dir0` to `dir1`, positive when turning right (when y points down).
+fn turn_angle(dir0: Vector2<f32>, dir1: Vector2<f32>) -> f32 {
+    cross(dir0, dir1).atan2(dir0.dot(&dir1))
+}
+
+#[cfg(test)]
+fn rect_path(path: &mut PathBuf, x: f32, y: f32, width: f32, height: f32) {
+    path.move_to(Point2::new(x, y));
+    path.line_to(Point2::new(x + width, y));
+    path.line_to(Point2::new(x + width, y + height));
+    path.line_to(Point2::new(x, y + height));
+    path.close();
+}
+
+// Checks the result of the operation against the input paths at points on a grid, skipping points
+// close to an edge of either one.
+#[cfg(test)]
+fn check_boolean_op(path0: &Path, fill_rule0: FillRule, path1: &Path, fill_rule1: FillRule,
+                    op: BooleanOp) -> PathBuf {
+    use super::StrokeStyle;
+
+    let result = path0.boolean_op(fill_rule0, path1, fill_rule1, op);
+    let near_edge_style = StrokeStyle::with_width(1.0);
+    for y in -5..=35 {
+        for x in -5..=35 {
+            let pt = Point2::new(x as f32 * 2.0 + 0.25, y as f32 * 2.0 + 0.25);
+            if path0.is_point_in_stroke(pt, &near_edge_style, 0.1) ||
+               path1.is_point_in_stroke(pt, &near_edge_style, 0.1) {
+                continue;
+            }
+            let expected = op.contains(path0.is_point_in_fill(pt, fill_rule0.mask()),
+                                       path1.is_point_in_fill(pt, fill_rule1.mask()));
+            let result_path = result.as_path();
+            assert_eq!(result_path.is_point_in_fill(pt, FillRule::NonZero.mask()), expected,
+                       "{:?} at {:?}", op, pt);
+            assert_eq!(result_path.is_point_in_fill(pt, FillRule::EvenOdd.mask()), expected,
+                       "{:?} at {:?}", op, pt);
+        }
+    }
+    result
+}
+
+#[test]
+fn test_boolean_op_rects() {
+    let ops = [BooleanOp::Union, BooleanOp::Intersection, BooleanOp::Difference, BooleanOp::Xor];
+    let mut path0 = PathBuf::new();
+    rect_path(&mut path0, 0.0, 0.0, 40.0, 40.0);
+    let mut path1 = PathBuf::new();
+    rect_path(&mut path1, 20.0, 10.0, 40.0, 40.0);
+    for &op in &ops {
+        check_boolean_op(&path0.as_path(), FillRule::NonZero, &path1.as_path(),
+                         FillRule::NonZero, op);
+    }
+    let result = path0.as_path().boolean_op(FillRule::NonZero, &path1.as_path(),
+                                            FillRule::NonZero, BooleanOp::Union);
+    let segs: Vec<_> = result.path_iter().collect();
+    assert_eq!(segs.iter().filter(|seg| **seg == PathSegment::Close).count(), 1);
+    assert_eq!(segs.iter().filter(|seg| match **seg {
+        PathSegment::Line(_) => true,
+        _ => false,
+    }).count(), 8);
+
+    // Rectangles that share part of an edge. Neither the shared edge nor a second copy of it
+    // should be in the union.
+    let mut path1 = PathBuf::new();
+    rect_path(&mut path1, 40.0, 10.0, 20.0, 20.0);
+    for &op in &ops {
+        check_boolean_op(&path0.as_path(), FillRule::NonZero, &path1.as_path(),
+                         FillRule::NonZero, op);
+    }
+    let result = path0.as_path().boolean_op(FillRule::NonZero, &path1.as_path(),
+                                            FillRule::NonZero, BooleanOp::Union);
+    let segs: Vec<_> = result.path_iter().collect();
+    assert_eq!(segs.iter().filter(|seg| **seg == PathSegment::Close).count(), 1);
+    assert_eq!(segs.iter().filter(|seg| match **seg {
+        PathSegment::Line(_) => true,
+        _ => false,
+    }).count(), 8);
+
+    // the same rectangle going the other direction
+    let mut path1 = PathBuf::new();
+    path1.move_to(Point2::new(0.0, 0.0));
+    path1.line_to(Point2::new(0.0, 40.0));
+    path1.line_to(Point2::new(40.0, 40.0));
+    path1.line_to(Point2::new(40.0, 0.0));
+    let result = path0.as_path().boolean_op(FillRule::NonZero, &path1.as_path(),
+                                            FillRule::NonZero, BooleanOp::Xor);
+    assert_eq!(result.path_iter().count(), 0);
+}
+
+#[test]
+fn test_boolean_op_curves() {
+    use std::f32::consts::PI;
+
+    let ops = [BooleanOp::Union, BooleanOp::Intersection, BooleanOp::Difference, BooleanOp::Xor];
+    let mut path0 = PathBuf::new();
+    path0.arc_to(Point2::new(25.0, 30.0), 20.0, 20.0, 0.0, PI * 2.0);
+    let mut path1 = PathBuf::new();
+    path1.move_to(Point2::new(30.0, 0.0));
+    path1.cubic_curve_to(Point2::new(80.0, 0.0), Point2::new(80.0, 50.0),
+                         Point2::new(30.0, 50.0));
+    path1.quad_curve_to(Point2::new(50.0, 25.0), Point2::new(30.0, 0.0));
+    for &op in &ops {
+        let result = check_boolean_op(&path0.as_path(), FillRule::NonZero, &path1.as_path(),
+                                      FillRule::NonZero, op);
+        // no curves were replaced by lines
+        assert!(result.path_iter().all(|seg| match seg {
+            PathSegment::Line(_) => false,
+            _ => true,
+        }));
+    }
+}
+
+#[test]
+fn test_boolean_op_self_intersecting() {
+    use std::f32::consts::PI;
+
+    // a star, which has a hole in the middle with the even-odd fill rule
+    let mut star = PathBuf::new();
+    star.move_to(Point2::new(30.0, 0.0));
+    for i in 1..5 {
+        let angle = PI * 0.8 * i as f32 - PI * 0.5;
+        star.line_to(Point2::new(30.0 + angle.cos() * 30.0, 30.0 + angle.sin() * 30.0));
+    }
+    star.close();
+    let mut rect = PathBuf::new();
+    rect_path(&mut rect, 25.0, 10.0, 30.0, 10.0);
+
+    let ops = [BooleanOp::Union, BooleanOp::Intersection, BooleanOp::Difference, BooleanOp::Xor];
+    for &op in &ops {
+        for &rule in &[FillRule::NonZero, FillRule::EvenOdd] {
+            check_boolean_op(&star.as_path(), rule, &rect.as_path(), FillRule::NonZero, op);
+        }
+    }
+
+    // Combining with an empty path just removes the self-intersections.
+    let empty = PathBuf::new();
+    let result = star.as_path().boolean_op(FillRule::EvenOdd, &empty.as_path(),
+                                           FillRule::NonZero, BooleanOp::Union);
+    assert!(!result.as_path().is_point_in_fill(Point2::new(30.0, 32.0),
+                                               FillRule::NonZero.mask()));
+    let result = star.as_path().boolean_op(FillRule::NonZero, &empty.as_path(),
+                                           FillRule::NonZero, BooleanOp::Union);
+    assert!(result.as_path().is_point_in_fill(Point2::new(30.0, 32.0), FillRule::NonZero.mask()));
+    assert_eq!(result.path_iter().filter(|seg| *seg == PathSegment::Close).count(), 1);
+}
+
+#[test]
+fn test_boolean_op_cubic_loop() {
+    // A cubic curve with a loop crosses itself at a single point, so it has to be split at two t
+    // values with the same point.
+    let mut path0 = PathBuf::new();
+    path0.move_to(Point2::new(0.0, 0.0));
+    path0.cubic_curve_to(Point2::new(90.0, 60.0), Point2::new(-30.0, 60.0),
+                         Point2::new(60.0, 0.0));
+    path0.close();
+    let mut path1 = PathBuf::new();
+    rect_path(&mut path1, 20.0, 20.0, 40.0, 10.0);
+
+    let ops = [BooleanOp::Union, BooleanOp::Intersection, BooleanOp::Difference, BooleanOp::Xor];
+    for &op in &ops {
+        for &rule in &[FillRule::NonZero, FillRule::EvenOdd] {
+            check_boolean_op(&path0.as_path(), rule, &path1.as_path(), FillRule::NonZero, op);
+        }
+    }
+
+    // Both parts of the curve on either side of the loop are kept, as well as the loop, so none
+    // of them are replaced by a line.
+    let empty = PathBuf::new();
+    let result = path0.as_path().boolean_op(FillRule::NonZero, &empty.as_path(),
+                                            FillRule::NonZero, BooleanOp::Union);
+    assert_eq!(result.path_iter().filter(|seg| match *seg {
+        PathSegment::CubicCurve(..) => true,
+        _ => false,
+    }).count(), 3);
+    assert_eq!(result.path_iter().filter(|seg| match *seg {
+        PathSegment::Line(_) => true,
+        _ => false,
+    }).count(), 1);
+}
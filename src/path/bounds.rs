@@ -0,0 +1,338 @@
+use std::f32;
+
+use nalgebra::{origin, Norm, Vector2};
+
+use crate::painter::AsPathIter;
+use crate::{Point2, Rect};
+use super::curve::Curve;
+use super::dash::dash_subpaths;
+use super::{ArcSegment, LineCap, LineJoin, Path, PathSegment, StrokeStyle};
+
+// The smallest and largest coordinates of the points added to it
+struct Bounds {
+    min: Point2<f32>,
+    max: Point2<f32>,
+}
+
+impl Bounds {
+    fn new() -> Self {
+        Self {
+            min: Point2::new(f32::INFINITY, f32::INFINITY),
+            max: Point2::new(f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    fn add(&mut self, pt: Point2<f32>) {
+        self.min = Point2::new(self.min.x.min(pt.x), self.min.y.min(pt.y));
+        self.max = Point2::new(self.max.x.max(pt.x), self.max.y.max(pt.y));
+    }
+
+    fn add_rect(&mut self, rect: Rect<f32>) {
+        self.add(rect.top_left());
+        self.add(rect.bottom_right());
+    }
+
+    // Adds the square centered on `pt` with sides of twice `half_size`.
+    fn add_square(&mut self, pt: Point2<f32>, half_size: f32) {
+        self.add(pt - Vector2::new(half_size, half_size));
+        self.add(pt + Vector2::new(half_size, half_size));
+    }
+
+    // Returns an empty rectangle at the origin if no points were added.
+    fn to_rect(&self) -> Rect<f32> {
+        if self.min.x > self.max.x {
+            return Rect::new(0.0, 0.0, 0.0, 0.0);
+        }
+        Rect::new(self.min.x, self.min.y, self.max.x - self.min.x, self.max.y - self.min.y)
+    }
+}
+
+impl ArcSegment {
+    pub fn bounding_box(&self) -> Rect<f32> {
+        let curve = Curve::Arc(*self);
+        let mut bounds = Bounds::new();
+        bounds.add(curve.point_at(0.0));
+        bounds.add(self.end_point());
+        for axis in 0..2 {
+            for t in curve.extrema(axis) {
+                bounds.add(curve.point_at(t));
+            }
+        }
+        bounds.to_rect()
+    }
+}
+
+impl Curve {
+    pub(crate) fn bounding_box(&self) -> Rect<f32> {
+        match *self {
+            Curve::Line(pt0, pt1) => {
+                let mut bounds = Bounds::new();
+                bounds.add(pt0);
+                bounds.add(pt1);
+                bounds.to_rect()
+            },
+            Curve::Quad(ref bez) => bez.bounding_box(),
+            Curve::Cubic(ref bez) => bez.bounding_box(),
+            Curve::Arc(ref arc) => arc.bounding_box(),
+        }
+    }
+}
+
+impl<'a> Path<'a> {
+    // Returns the smallest rectangle containing every point on the path. The points that moves go
+    // to are included, even if nothing is drawn from them. An empty path returns an empty
+    // rectangle at the origin.
+    pub fn bounding_box(&self) -> Rect<f32> {
+        let mut bounds = Bounds::new();
+        let mut current_pt = origin();
+        let mut subpath_start_pt = origin();
+        for seg in self.path_iter() {
+            match seg {
+                PathSegment::Move(pt) => {
+                    bounds.add(pt);
+                    subpath_start_pt = pt;
+                    current_pt = pt;
+                },
+                PathSegment::Close => current_pt = subpath_start_pt,
+                _ => {
+                    let curve = Curve::from_segment(current_pt, &seg).unwrap();
+                    bounds.add_rect(curve.bounding_box());
+                    current_pt = curve.end_point();
+                },
+            }
+        }
+        bounds.to_rect()
+    }
+
+    // Returns a rectangle containing every point of the path's segments, including the control
+    // points of curves. It is faster to compute than `bounding_box()`, but may be larger. For an
+    // arc, the bounds of its whole ellipse are used.
+    pub fn control_bounds(&self) -> Rect<f32> {
+        let mut bounds = Bounds::new();
+        for seg in self.path_iter() {
+            match seg {
+                PathSegment::Move(pt) | PathSegment::Line(pt) => bounds.add(pt),
+                PathSegment::QuadCurve(pt1, pt2) => {
+                    bounds.add(pt1);
+                    bounds.add(pt2);
+                },
+                PathSegment::CubicCurve(pt1, pt2, pt3) => {
+                    bounds.add(pt1);
+                    bounds.add(pt2);
+                    bounds.add(pt3);
+                },
+                PathSegment::Arc(arc) => {
+                    // the bounding box of the whole (possibly rotated) ellipse
+                    let (sin_rot, cos_rot) = arc.rotation.sin_cos();
+                    let (rx, ry) = (arc.x_radius, arc.y_radius);
+                    let radius = Vector2::new((rx * cos_rot).hypot(ry * sin_rot),
+                                              (rx * sin_rot).hypot(ry * cos_rot));
+                    bounds.add(arc.center_pt - radius);
+                    bounds.add(arc.center_pt + radius);
+                },
+                PathSegment::Close => {},
+            }
+        }
+        bounds.to_rect()
+    }
+
+    // Returns the smallest rectangle containing the area covered when stroking the path with
+    // `style`, including joins and caps. Where a curve bends more tightly than half the stroke
+    // width near the end of an open subpath, the inside of the bend can stick out slightly
+    // farther than this.
+    pub fn stroke_bounds(&self, style: &StrokeStyle) -> Rect<f32> {
+        let mut stroke_bounds = StrokeBounds {
+            bounds: Bounds::new(),
+            style,
+            half_width: style.width * 0.5,
+        };
+        for mut subpath in dash_subpaths(self, &style.dash_pattern, style.dash_offset) {
+            if subpath.closed {
+                let end_pt = subpath.curves.last().map_or(subpath.start_pt, |c| c.end_point());
+                subpath.curves.push(Curve::Line(end_pt, subpath.start_pt));
+            }
+            let (initial_end_cap, terminal_end_cap) = style.end_caps();
+            let (initial_dash_cap, terminal_dash_cap) = style.dash_caps();
+            let start_cap = if subpath.dash_start { initial_dash_cap } else { initial_end_cap };
+            let end_cap = if subpath.dash_end { terminal_dash_cap } else { terminal_end_cap };
+            stroke_bounds.add_subpath(&subpath.curves, subpath.closed, subpath.start_pt,
+                                      start_cap, end_cap);
+        }
+        stroke_bounds.bounds.to_rect()
+    }
+}
+
+struct StrokeBounds<'s> {
+    bounds: Bounds,
+    style: &'s StrokeStyle,
+    half_width: f32,
+}
+
+impl<'s> StrokeBounds<'s> {
+    fn add_subpath(&mut self, curves: &[Curve], closed: bool, start_pt: Point2<f32>,
+                   start_cap: LineCap, end_cap: LineCap) {
+        let half_width = self.half_width;
+        // Curves with no length have no direction for joins or caps.
+        let curves: Vec<&Curve> = curves.iter()
+                                        .filter(|curve| curve.tangent_at(0.0).norm_squared() > 0.0)
+                                        .collect();
+        if curves.is_empty() {
+            // A zero length subpath is drawn as just its caps, which are aligned to the axes.
+            if start_cap != LineCap::Flat || end_cap != LineCap::Flat {
+                self.bounds.add_square(start_pt, half_width);
+            }
+            return;
+        }
+
+        for curve in &curves {
+            // The stroke is widest in a direction at the ends of the curve and where the curve's
+            // tangent is perpendicular to that direction.
+            for &t in &[0.0, 1.0] {
+                let offset = perpendicular(curve.tangent_at(t)) * half_width;
+                let pt = curve.point_at(t);
+                self.bounds.add(pt + offset);
+                self.bounds.add(pt - offset);
+            }
+            for axis in 0..2 {
+                let offset = if axis == 0 {
+                    Vector2::new(half_width, 0.0)
+                } else {
+                    Vector2::new(0.0, half_width)
+                };
+                for t in curve.extrema(axis) {
+                    let pt = curve.point_at(t);
+                    self.bounds.add(pt + offset);
+                    self.bounds.add(pt - offset);
+                }
+            }
+        }
+
+        for pair in curves.windows(2) {
+            self.add_join(pair[0].end_point(), pair[0].tangent_at(1.0), pair[1].tangent_at(0.0));
+        }
+        let first = curves[0];
+        let last = curves[curves.len() - 1];
+        if closed {
+            self.add_join(last.end_point(), last.tangent_at(1.0), first.tangent_at(0.0));
+        } else {
+            self.add_cap(first.point_at(0.0), -first.tangent_at(0.0), start_cap);
+            self.add_cap(last.end_point(), last.tangent_at(1.0), end_cap);
+        }
+    }
+
+    // The corners of bevels are already added as the ends of the curves, so only miters and round
+    // joins can stick out farther.
+    fn add_join(&mut self, vertex: Point2<f32>, dir0: Vector2<f32>, dir1: Vector2<f32>) {
+        let half_width = self.half_width;
+        match self.style.line_join {
+            LineJoin::Round => self.bounds.add_square(vertex, half_width),
+            LineJoin::None | LineJoin::Bevel => {},
+            join @ LineJoin::Miter(_) | join @ LineJoin::MiterRevert(_) |
+            join @ LineJoin::MiterTruncate(_) => {
+                let cross = dir0.x * dir1.y - dir0.y * dir1.x;
+                if cross == 0.0 {
+                    return;
+                }
+                let outer0 = perpendicular(dir0) * -cross.signum();
+                let outer1 = perpendicular(dir1) * -cross.signum();
+                for pt in join.miter_points(vertex, dir0, dir1, outer0, outer1, half_width) {
+                    self.bounds.add(pt);
+                }
+            },
+        }
+    }
+
+    // `dir` is a unit vector pointing away from the end of the line.
+    fn add_cap(&mut self, end_pt: Point2<f32>, dir: Vector2<f32>, cap: LineCap) {
+        let half_width = self.half_width;
+        match cap {
+            LineCap::Flat => {},
+            LineCap::Square => {
+                let offset = perpendicular(dir) * half_width;
+                self.bounds.add(end_pt + dir * half_width + offset);
+                self.bounds.add(end_pt + dir * half_width - offset);
+            },
+            LineCap::Round => self.bounds.add_square(end_pt, half_width),
+            LineCap::Triangular => self.bounds.add(end_pt + dir * half_width),
+        }
+    }
+}
+
+fn perpendicular(v: Vector2<f32>) -> Vector2<f32> {
+    Vector2::new(-v.y, v.x)
+}
+
+#[test]
+fn test_bounding_box() {
+    use std::f32::consts::PI;
+    use nalgebra::ApproxEq;
+    use super::PathBuf;
+
+    let mut path = PathBuf::new();
+    assert_eq!(path.as_path().bounding_box(), Rect::new(0.0, 0.0, 0.0, 0.0));
+    path.move_to(Point2::new(10.0, 10.0));
+    path.quad_curve_to(Point2::new(20.0, 30.0), Point2::new(30.0, 10.0));
+    path.arc_to(Point2::new(40.0, 10.0), 10.0, 5.0, PI, PI * 2.5);
+    assert_approx_eq!(path.as_path().bounding_box(), Rect::new(10.0, 5.0, 40.0, 15.0));
+    assert_approx_eq!(path.as_path().control_bounds(), Rect::new(10.0, 5.0, 40.0, 25.0));
+
+    path.move_to(Point2::new(-10.0, 0.0));
+    path.cubic_curve_to(Point2::new(-10.0, -20.0), Point2::new(10.0, -20.0),
+                        Point2::new(10.0, 0.0));
+    assert_approx_eq!(path.as_path().bounding_box(), Rect::new(-10.0, -15.0, 60.0, 35.0));
+    assert_approx_eq!(path.as_path().control_bounds(), Rect::new(-10.0, -20.0, 60.0, 50.0));
+}
+
+#[test]
+fn test_stroke_bounds() {
+    use std::f32::consts::PI;
+    use nalgebra::ApproxEq;
+    use super::PathBuf;
+
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(100.0, 0.0));
+    path.line_to(Point2::new(100.0, 100.0));
+    let path = path.as_path();
+    let mut style = StrokeStyle::with_width(10.0);
+    style.line_cap = LineCap::Flat;
+    style.line_join = LineJoin::Miter(4.0);
+    assert_approx_eq!(path.stroke_bounds(&style), Rect::new(0.0, -5.0, 105.0, 105.0));
+    style.line_cap = LineCap::Square;
+    assert_approx_eq!(path.stroke_bounds(&style), Rect::new(-5.0, -5.0, 110.0, 110.0));
+    style.line_cap = LineCap::Flat;
+    style.line_join = LineJoin::Bevel;
+    assert_approx_eq!(path.stroke_bounds(&style), Rect::new(0.0, -5.0, 105.0, 105.0));
+
+    // The miter of a sharp corner sticks out farther than the stroke width.
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(100.0, 50.0));
+    path.line_to(Point2::new(0.0, 100.0));
+    let path = path.as_path();
+    style.line_join = LineJoin::Miter(10.0);
+    let miter_length = 5.0 / (0.5f32.atan()).sin();
+    assert_approx_eq_eps!(path.stroke_bounds(&style).right(), 100.0 + miter_length, 1e-4);
+    style.line_join = LineJoin::Miter(2.0);
+    assert!(path.stroke_bounds(&style).right() < 100.0 + 5.0);
+    style.line_join = LineJoin::MiterTruncate(2.0);
+    assert_approx_eq_eps!(path.stroke_bounds(&style).right(), 100.0 + 10.0, 1e-4);
+    style.line_cap = LineCap::Triangular;
+    assert_approx_eq_eps!(path.stroke_bounds(&style).x, -10.0 / 5.0f32.sqrt(), 1e-4);
+    style.line_cap = LineCap::Flat;
+
+    let mut path = PathBuf::new();
+    path.arc_to(Point2::new(0.0, 0.0), 50.0, 20.0, 0.0, PI);
+    path.close();
+    assert_approx_eq_eps!(path.as_path().stroke_bounds(&style), Rect::new(-55.0, -5.0, 110.0, 30.0),
+                          1e-4);
+
+    // zero length subpaths
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(10.0, 10.0));
+    path.line_to(Point2::new(10.0, 10.0));
+    path.move_to(Point2::new(100.0, 100.0));
+    let path = path.as_path();
+    style.line_cap = LineCap::Round;
+    assert_approx_eq!(path.stroke_bounds(&style), Rect::new(5.0, 5.0, 10.0, 10.0));
+}
@@ -0,0 +1,382 @@
+use std::f32::consts::PI;
+use std::iter::Enumerate;
+
+use nalgebra::{origin, Dot, Norm, Vector2};
+use smallvec::SmallVec;
+
+use crate::painter::AsPathIter;
+use crate::cubic_bezier::find_minimum;
+use crate::{CubicBezier, Point2, QuadBezier};
+use super::flatten::{flatten_arc, flatten_cubic, flatten_quad};
+use super::{ArcSegment, Path, PathBuf, PathIter, PathSegment};
+
+// A path segment along with the point it starts at, which is needed to evaluate it. Every curve is
+// parameterized from t = 0 at the start to t = 1 at the end. For an arc, t is proportional to the
+// angle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Curve {
+    Line(Point2<f32>, Point2<f32>),
+    Quad(QuadBezier<f32>),
+    Cubic(CubicBezier<f32>),
+    Arc(ArcSegment),
+}
+
+impl Curve {
+    // Returns `None` for moves and closes.
+    pub(crate) fn from_segment(start_pt: Point2<f32>, seg: &PathSegment) -> Option<Curve> {
+        match *seg {
+            PathSegment::Move(_) | PathSegment::Close => None,
+            PathSegment::Line(pt) => Some(Curve::Line(start_pt, pt)),
+            PathSegment::QuadCurve(pt1, pt2) =>
+                Some(Curve::Quad(QuadBezier::new(start_pt, pt1, pt2))),
+            PathSegment::CubicCurve(pt1, pt2, pt3) =>
+                Some(Curve::Cubic(CubicBezier::new(start_pt, pt1, pt2, pt3))),
+            PathSegment::Arc(arc) => Some(Curve::Arc(arc)),
+        }
+    }
+
+    // Returns the segment that continues a path from the curve's start point along the curve.
+    pub(crate) fn to_segment(&self) -> PathSegment {
+        match *self {
+            Curve::Line(_, pt1) => PathSegment::Line(pt1),
+            Curve::Quad(ref bez) => PathSegment::QuadCurve(bez.p1, bez.p2),
+            Curve::Cubic(ref bez) => PathSegment::CubicCurve(bez.p1, bez.p2, bez.p3),
+            Curve::Arc(arc) => PathSegment::Arc(arc),
+        }
+    }
+
+    pub(crate) fn end_point(&self) -> Point2<f32> {
+        match *self {
+            Curve::Line(_, pt1) => pt1,
+            Curve::Quad(ref bez) => bez.p2,
+            Curve::Cubic(ref bez) => bez.p3,
+            Curve::Arc(ref arc) => arc.end_point(),
+        }
+    }
+
+    pub(crate) fn point_at(&self, t: f32) -> Point2<f32> {
+        match *self {
+            // Written so that t = 1 gives exactly the end point.
+            Curve::Line(pt0, pt1) => pt0 * (1.0 - t) + pt1.to_vector() * t,
+            Curve::Quad(ref bez) => bez.point_at(t),
+            Curve::Cubic(ref bez) => bez.point_at(t),
+            Curve::Arc(ref arc) => arc.point_at_angle(arc.angle1 + (arc.angle2 - arc.angle1) * t),
+        }
+    }
+
+    // Returns the derivative of the curve with respect to t.
+    pub(crate) fn derivative_at(&self, t: f32) -> Vector2<f32> {
+        match *self {
+            Curve::Line(pt0, pt1) => pt1 - pt0,
+            Curve::Quad(ref bez) => bez.derivative_at(t),
+            Curve::Cubic(ref bez) => bez.derivative_at(t),
+            Curve::Arc(ref arc) => {
+                let sweep_angle = arc.angle2 - arc.angle1;
+                let angle = arc.angle1 + sweep_angle * t;
+                arc.derivative_at_angle(angle) * sweep_angle
+            },
+        }
+    }
+
+    // Returns a unit vector in the direction of the curve at t, or a zero vector if the curve is
+    // a single point.
+    pub(crate) fn tangent_at(&self, t: f32) -> Vector2<f32> {
+        let mut deriv = self.derivative_at(t);
+        if deriv.norm_squared() == 0.0 {
+            // The derivative is zero at a cusp or where a control point is on an end point, but
+            // the curve still has a direction there, which can be found from a point next to it.
+            deriv = self.derivative_at(if t < 0.5 { t + 1e-3 } else { t - 1e-3 });
+            if deriv.norm_squared() == 0.0 {
+                return deriv;
+            }
+        }
+        deriv.normalize()
+    }
+
+    // Returns the part of the curve from t0 to t1. If t1 is less than t0, the part is reversed.
+    pub(crate) fn subcurve(&self, t0: f32, t1: f32) -> Curve {
+        if t1 < t0 {
+            return self.subcurve(t1, t0).reversed();
+        }
+        match *self {
+            Curve::Line(..) => Curve::Line(self.point_at(t0), self.point_at(t1)),
+            Curve::Quad(ref bez) => {
+                let bez = if t1 < 1.0 { bez.split(t1).0 } else { *bez };
+                Curve::Quad(if t0 > 0.0 { bez.split(t0 / t1).1 } else { bez })
+            },
+            Curve::Cubic(ref bez) => {
+                let bez = if t1 < 1.0 { bez.split(t1).0 } else { *bez };
+                Curve::Cubic(if t0 > 0.0 { bez.split(t0 / t1).1 } else { bez })
+            },
+            Curve::Arc(ref arc) => {
+                let sweep_angle = arc.angle2 - arc.angle1;
+                Curve::Arc(ArcSegment {
+                    angle1: arc.angle1 + sweep_angle * t0,
+                    angle2: arc.angle1 + sweep_angle * t1,
+                    ..*arc
+                })
+            },
+        }
+    }
+
+    // Returns the same curve going from the end to the start.
+    pub(crate) fn reversed(&self) -> Curve {
+        match *self {
+            Curve::Line(pt0, pt1) => Curve::Line(pt1, pt0),
+            Curve::Quad(ref bez) => Curve::Quad(QuadBezier::new(bez.p2, bez.p1, bez.p0)),
+            Curve::Cubic(ref bez) => Curve::Cubic(CubicBezier::new(bez.p3, bez.p2, bez.p1, bez.p0)),
+            Curve::Arc(ref arc) => Curve::Arc(ArcSegment {
+                angle1: arc.angle2,
+                angle2: arc.angle1,
+                ..*arc
+            }),
+        }
+    }
+
+    // Adds points to `points` the same way as `flatten_quad()` and the other flatten functions.
+    pub(crate) fn flatten(&self, tolerance: f32, points: &mut Vec<Point2<f32>>) {
+        match *self {
+            Curve::Line(_, pt1) => points.push(pt1),
+            Curve::Quad(ref bez) => flatten_quad(bez, tolerance, points),
+            Curve::Cubic(ref bez) => flatten_cubic(bez, tolerance, points),
+            Curve::Arc(ref arc) => flatten_arc(arc, tolerance, points),
+        }
+    }
+
+    // Returns the t values strictly between 0 and 1 where the x (`axis` = 0) or y (`axis` = 1)
+    // coordinate has a local minimum or maximum, in increasing order. Splitting the curve at them
+    // makes pieces that are monotonic in that coordinate.
+    pub(crate) fn extrema(&self, axis: usize) -> SmallVec<[f32; 4]> {
+        let mut ts: SmallVec<[f32; 4]> = SmallVec::new();
+        match *self {
+            Curve::Line(..) => {},
+            Curve::Quad(ref bez) => ts.extend(bez.extrema(axis)),
+            Curve::Cubic(ref bez) => ts.extend(bez.extrema(axis)),
+            Curve::Arc(ref arc) => {
+                // Each axis has extrema where the derivative along it is zero, which happens at
+                // multiples of pi from the first one. Without a rotation, they're at multiples of
+                // pi for x and at pi/2 plus multiples of pi for y.
+                let (sin_rot, cos_rot) = arc.rotation.sin_cos();
+                let offset = if axis == 0 {
+                    (-arc.y_radius * sin_rot).atan2(arc.x_radius * cos_rot)
+                } else {
+                    (arc.y_radius * cos_rot).atan2(arc.x_radius * sin_rot)
+                };
+                let sweep_angle = arc.angle2 - arc.angle1;
+                let (min_angle, max_angle) = if sweep_angle > 0.0 {
+                    (arc.angle1, arc.angle2)
+                } else {
+                    (arc.angle2, arc.angle1)
+                };
+                let mut k = ((min_angle - offset) / PI).ceil();
+                while offset + k * PI <= max_angle {
+                    ts.push((offset + k * PI - arc.angle1) / sweep_angle);
+                    k += 1.0;
+                }
+            },
+        }
+        ts.retain(|t| *t > 0.0 && *t < 1.0);
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        ts
+    }
+
+    // Returns the t of the point on the curve closest to `pt` and the distance to it.
+    pub(crate) fn project_point(&self, pt: Point2<f32>) -> (f32, f32) {
+        let t = match *self {
+            Curve::Line(pt0, pt1) => {
+                let v = pt1 - pt0;
+                let length_sq = v.norm_squared();
+                if length_sq > 0.0 {
+                    ((pt - pt0).dot(&v) / length_sq).max(0.0).min(1.0)
+                } else {
+                    0.0
+                }
+            },
+            Curve::Quad(ref bez) => {
+                let (_, t, dist) = bez.project_point(pt);
+                return (t, dist);
+            },
+            Curve::Cubic(ref bez) => {
+                let (_, t, dist) = bez.project_point(pt);
+                return (t, dist);
+            },
+            Curve::Arc(ref arc) => {
+                // Past one turn, the arc goes over the same points again.
+                let turn_t = (PI * 2.0 / (arc.angle2 - arc.angle1).abs()).min(1.0);
+                let t = find_minimum(16, |t| {
+                    let t = t * turn_t;
+                    let v = self.point_at(t) - pt;
+                    (v.norm_squared(), v.dot(&self.derivative_at(t)))
+                });
+                t * turn_t
+            },
+        };
+        (t, (self.point_at(t) - pt).norm())
+    }
+
+    // Returns the curve's part of the signed area of a closed path it's in, which is half the
+    // integral of x dy - y dx along it. The total is positive for a path going clockwise when y
+    // points down.
+    pub(crate) fn signed_area(&self) -> f32 {
+        let cross = |pt0: Point2<f32>, pt1: Point2<f32>| pt0.x * pt1.y - pt0.y * pt1.x;
+        match *self {
+            Curve::Line(pt0, pt1) => cross(pt0, pt1) * 0.5,
+            Curve::Quad(ref bez) => {
+                (cross(bez.p0, bez.p1) * 2.0 + cross(bez.p1, bez.p2) * 2.0
+                    + cross(bez.p0, bez.p2)) / 6.0
+            },
+            Curve::Cubic(ref bez) => {
+                (cross(bez.p0, bez.p1) * 6.0 + cross(bez.p0, bez.p2) * 3.0 + cross(bez.p0, bez.p3)
+                    + cross(bez.p1, bez.p2) * 3.0 + cross(bez.p1, bez.p3) * 3.0
+                    + cross(bez.p2, bez.p3) * 6.0) / 20.0
+            },
+            Curve::Arc(ref arc) => {
+                // The area swept around the center, which the rotation doesn't change, plus the
+                // part from the center not being at the origin
+                let chord = arc.end_point() - arc.start_point();
+                let center = arc.center_pt;
+                (arc.x_radius * arc.y_radius * (arc.angle2 - arc.angle1)
+                    + center.x * chord.y - center.y * chord.x) * 0.5
+            },
+        }
+    }
+
+    // Returns the number of times the curve crosses the horizontal ray going from `pt` to the
+    // right. Crossings going down (toward positive y) count as 1 and going up as -1. Only one end
+    // of each curve counts, so that a ray through a point where two curves meet isn't counted
+    // twice.
+    pub(crate) fn winding_number(&self, pt: Point2<f32>) -> i32 {
+        let mut ts: SmallVec<[f32; 6]> = SmallVec::new();
+        ts.push(0.0);
+        ts.extend(self.extrema(1));
+        ts.push(1.0);
+
+        let mut winding = 0;
+        for w in ts.windows(2) {
+            let (t0, t1) = (w[0], w[1]);
+            let (y0, y1) = (self.point_at(t0).y, self.point_at(t1).y);
+            let dir = if y0 <= pt.y && pt.y < y1 {
+                1
+            } else if y1 <= pt.y && pt.y < y0 {
+                -1
+            } else {
+                continue;
+            };
+            let t = self.solve_monotonic_y(t0, t1, pt.y);
+            if self.point_at(t).x > pt.x {
+                winding += dir;
+            }
+        }
+        winding
+    }
+
+    // Finds the t between `t0` and `t1` where the curve's y coordinate is `y`. The curve must be
+    // monotonic in y in that range.
+    fn solve_monotonic_y(&self, mut t0: f32, mut t1: f32, y: f32) -> f32 {
+        if let Curve::Line(pt0, pt1) = *self {
+            return ((y - pt0.y) / (pt1.y - pt0.y)).max(0.0).min(1.0);
+        }
+        let increasing = self.point_at(t0).y < self.point_at(t1).y;
+        // Bisection is slow compared to Newton's method, but it can't fail.
+        for _ in 0..32 {
+            let t = (t0 + t1) * 0.5;
+            if (self.point_at(t).y < y) == increasing {
+                t0 = t;
+            } else {
+                t1 = t;
+            }
+        }
+        (t0 + t1) * 0.5
+    }
+}
+
+// An iterator over the curves of a path, along with the index of the segment each came from. Moves
+// are skipped, and a close gives the line back to the start of the subpath.
+pub(crate) struct Curves<'a> {
+    iter: Enumerate<PathIter<'a>>,
+    current_pt: Point2<f32>,
+    subpath_start_pt: Point2<f32>,
+}
+
+impl<'a> Iterator for Curves<'a> {
+    type Item = (usize, Curve);
+
+    fn next(&mut self) -> Option<(usize, Curve)> {
+        loop {
+            let (index, seg) = self.iter.next()?;
+            let curve = match seg {
+                PathSegment::Move(pt) => {
+                    self.subpath_start_pt = pt;
+                    self.current_pt = pt;
+                    continue;
+                },
+                PathSegment::Close => Curve::Line(self.current_pt, self.subpath_start_pt),
+                _ => Curve::from_segment(self.current_pt, &seg).unwrap(),
+            };
+            self.current_pt = curve.end_point();
+            return Some((index, curve));
+        }
+    }
+}
+
+impl<'a> Path<'a> {
+    pub(crate) fn curves(&self) -> Curves<'a> {
+        Curves {
+            iter: self.path_iter().enumerate(),
+            current_pt: origin(),
+            subpath_start_pt: origin(),
+        }
+    }
+}
+
+impl PathBuf {
+    // Adds a segment for the curve, which must start at the current point.
+    pub(crate) fn push_curve(&mut self, curve: &Curve) {
+        match *curve {
+            Curve::Line(_, pt1) => self.line_to(pt1),
+            Curve::Quad(ref bez) => self.quad_curve_to(bez.p1, bez.p2),
+            Curve::Cubic(ref bez) => self.cubic_curve_to(bez.p1, bez.p2, bez.p3),
+            Curve::Arc(ref arc) => {
+                self.push_arc(arc.center_pt, arc.x_radius, arc.y_radius, arc.rotation, arc.angle1,
+                              arc.angle2)
+            },
+        }
+    }
+}
+
+#[test]
+fn test_extrema() {
+    use nalgebra::ApproxEq;
+
+    let curve = Curve::Cubic(CubicBezier::new(Point2::new(0.0, 0.0), Point2::new(0.0, 10.0),
+                                              Point2::new(10.0, 10.0), Point2::new(10.0, 0.0)));
+    assert!(curve.extrema(0).is_empty());
+    assert_eq!(&curve.extrema(1)[..], &[0.5]);
+
+    let curve = Curve::Arc(ArcSegment {
+        center_pt: Point2::new(0.0, 0.0),
+        x_radius: 10.0,
+        y_radius: 5.0,
+        rotation: 0.0,
+        angle1: -PI * 0.25,
+        angle2: PI * 1.75,
+    });
+    let ts = curve.extrema(0);
+    assert_eq!(ts.len(), 2);
+    assert_approx_eq!(ts[0], 0.125);
+    assert_approx_eq!(ts[1], 0.625);
+    let ts = curve.extrema(1);
+    assert_eq!(ts.len(), 2);
+    assert_approx_eq!(ts[0], 0.375);
+    assert_approx_eq!(ts[1], 0.875);
+
+    // A rotated ellipse has its extrema where the derivative along the axis is zero.
+    let curve = Curve::Arc(ArcSegment::new(Point2::new(0.0, 0.0), 10.0, 5.0, 0.5, 0.0, PI * 2.0));
+    for axis in 0..2 {
+        let ts = curve.extrema(axis);
+        assert_eq!(ts.len(), 2);
+        for &t in &ts {
+            assert_approx_eq_eps!(curve.derivative_at(t)[axis], 0.0, 1e-3);
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use nalgebra::origin;
+
+use crate::painter::AsPathIter;
+use crate::Point2;
+use super::curve::Curve;
+use super::{Path, PathBuf, PathSegment, StrokeStyle};
+
+// A subpath, or a dash made from part of one. If it is closed, `curves` doesn't include the line
+// back to the start.
+pub(crate) struct DashedSubpath {
+    pub(crate) curves: Vec<Curve>,
+    pub(crate) start_pt: Point2<f32>,
+    pub(crate) closed: bool,
+    // Whether the start and end are where a dash starts or ends, rather than the start or end of
+    // an open subpath, so that they get dash caps instead of line caps
+    pub(crate) dash_start: bool,
+    pub(crate) dash_end: bool,
+}
+
+impl StrokeStyle {
+    // Returns the dash pattern with an even number of lengths, or `None` if the line is solid.
+    pub(crate) fn even_dash_pattern(&self) -> Option<Vec<f32>> {
+        even_dash_pattern(&self.dash_pattern)
+    }
+}
+
+fn even_dash_pattern(dash_pattern: &[f32]) -> Option<Vec<f32>> {
+    if dash_pattern.iter().any(|length| !(length.is_finite() && *length >= 0.0)) {
+        return None;
+    }
+    let sum: f32 = dash_pattern.iter().sum();
+    if !(sum > 0.0) {
+        return None;
+    }
+    let mut pattern = dash_pattern.to_vec();
+    if pattern.len() % 2 == 1 {
+        pattern.extend_from_slice(dash_pattern);
+    }
+    Some(pattern)
+}
+
+impl<'a> Path<'a> {
+    // Returns a path with a subpath for each dash made by dashing this path with `dash_pattern`,
+    // starting `dash_offset` into the pattern at the start of each subpath. See
+    // `StrokeStyle::dash_pattern` for how the pattern is used. A dash of zero length becomes a
+    // subpath with a line of zero length, so that it still gets caps when stroked.
+    pub fn dash(&self, dash_pattern: &[f32], dash_offset: f32) -> PathBuf {
+        if even_dash_pattern(dash_pattern).is_none() {
+            return self.path_iter().collect();
+        }
+        let mut path = PathBuf::new();
+        for subpath in dash_subpaths(self, dash_pattern, dash_offset) {
+            path.move_to(subpath.start_pt);
+            if subpath.curves.is_empty() {
+                path.line_to(subpath.start_pt);
+            }
+            for curve in &subpath.curves {
+                path.push_curve(curve);
+            }
+            if subpath.closed {
+                path.close();
+            }
+        }
+        path
+    }
+}
+
+// Splits the path's subpaths into dashes. If the pattern makes a solid line, the subpaths are
+// returned as they are. Subpaths with only a move are left out, since they aren't drawn.
+pub(crate) fn dash_subpaths(path: &Path, dash_pattern: &[f32], dash_offset: f32)
+                            -> Vec<DashedSubpath> {
+    let pattern = even_dash_pattern(dash_pattern);
+    let mut dashes = vec![];
+    let mut curves = vec![];
+    let mut has_segments = false;
+    let mut current_pt = origin();
+    let mut subpath_start_pt = origin();
+    let mut add_subpath = |curves: &mut Vec<Curve>, closed: bool, has_segments: bool,
+                           start_pt: Point2<f32>| {
+        if has_segments {
+            match pattern {
+                Some(ref pattern) => {
+                    dash_subpath(curves, closed, start_pt, pattern, dash_offset, &mut dashes)
+                },
+                None => dashes.push(DashedSubpath {
+                    curves: curves.clone(),
+                    start_pt,
+                    closed,
+                    dash_start: false,
+                    dash_end: false,
+                }),
+            }
+        }
+        curves.clear();
+    };
+    for seg in path.path_iter() {
+        match seg {
+            PathSegment::Move(pt) => {
+                add_subpath(&mut curves, false, has_segments, subpath_start_pt);
+                has_segments = false;
+                subpath_start_pt = pt;
+                current_pt = pt;
+            },
+            PathSegment::Close => {
+                add_subpath(&mut curves, true, true, subpath_start_pt);
+                has_segments = false;
+                current_pt = subpath_start_pt;
+            },
+            _ => {
+                let curve = Curve::from_segment(current_pt, &seg).unwrap();
+                curves.push(curve);
+                has_segments = true;
+                current_pt = curve.end_point();
+            },
+        }
+    }
+    add_subpath(&mut curves, false, has_segments, subpath_start_pt);
+    dashes
+}
+
+// `pattern` must have an even number of lengths, none negative, that add up to more than zero.
+fn dash_subpath(curves: &[Curve], closed: bool, start_pt: Point2<f32>, pattern: &[f32],
+                dash_offset: f32, dashes: &mut Vec<DashedSubpath>) {
+    let total_length: f32 = pattern.iter().sum();
+    let mut offset = dash_offset.rem_euclid(total_length);
+    if offset >= total_length {
+        // The remainder can be rounded up when the offset is a tiny negative number.
+        offset = 0.0;
+    }
+    // A dash of zero length where the subpath starts is kept, but a longer one the offset goes
+    // all the way through isn't.
+    let mut index = 0;
+    while offset >= pattern[index] && (pattern[index] > 0.0 || offset > 0.0) {
+        offset -= pattern[index];
+        index = (index + 1) % pattern.len();
+    }
+    let mut remaining = pattern[index] - offset;
+
+    let new_dash = |start_pt, dash_start| DashedSubpath {
+        curves: vec![],
+        start_pt,
+        closed: false,
+        dash_start,
+        dash_end: true,
+    };
+    let first_index = dashes.len();
+    let starts_on = index % 2 == 0;
+    // A closed subpath has no start or end, so all its dashes get dash caps.
+    let mut dash = if starts_on { Some(new_dash(start_pt, closed)) } else { None };
+    let mut is_split = false;
+
+    let closing_line = if closed {
+        let end_pt = curves.last().map_or(start_pt, |curve| curve.end_point());
+        Some(Curve::Line(end_pt, start_pt))
+    } else {
+        None
+    };
+    for curve in curves.iter().chain(closing_line.iter()) {
+        let length = curve.length();
+        if length == 0.0 {
+            continue;
+        }
+        let mut pos = 0.0;
+        let mut t = 0.0;
+        loop {
+            let end_pos = pos + remaining;
+            // A gap that ends at the end of the curve starts the next dash on it, so that a dash
+            // of zero length there isn't missed.
+            if end_pos > length || (end_pos == length && dash.is_some()) {
+                if let Some(ref mut dash) = dash {
+                    if t < 1.0 {
+                        dash.curves.push(curve.subcurve(t, 1.0));
+                    }
+                }
+                remaining = end_pos - length;
+                break;
+            }
+            let end_t = curve.t_at_distance(end_pos, length);
+            match dash.take() {
+                Some(mut finished) => {
+                    if end_t > t {
+                        finished.curves.push(curve.subcurve(t, end_t));
+                    }
+                    dashes.push(finished);
+                },
+                None => dash = Some(new_dash(curve.point_at(end_t), true)),
+            }
+            is_split = true;
+            pos = end_pos;
+            t = end_t;
+            index = (index + 1) % pattern.len();
+            remaining = pattern[index];
+        }
+    }
+
+    // A zero length subpath is still drawn if it's in a dash, but a dash that starts at the end
+    // of the subpath isn't unless its length is zero.
+    let mut dash = match dash {
+        Some(ref dash) if is_split && dash.curves.is_empty() && remaining > 0.0 => return,
+        Some(dash) => dash,
+        None => return,
+    };
+    if !is_split {
+        // The whole subpath is in one dash.
+        dashes.push(DashedSubpath {
+            curves: curves.to_vec(),
+            start_pt,
+            closed,
+            dash_start: false,
+            dash_end: false,
+        });
+    } else if closed && starts_on {
+        // The last dash continues through the start of the subpath into the first dash.
+        let first = &mut dashes[first_index];
+        dash.curves.extend(first.curves.drain(..));
+        dash.dash_end = first.dash_end;
+        *first = dash;
+    } else {
+        dash.dash_end = closed;
+        dashes.push(dash);
+    }
+}
+
+#[test]
+fn test_dash() {
+    use nalgebra::ApproxEq;
+
+    // Returns the points of the path's moves and lines, with a new list for each move.
+    fn subpath_points(path: &PathBuf) -> Vec<Vec<Point2<f32>>> {
+        let mut subpaths: Vec<Vec<Point2<f32>>> = vec![];
+        for seg in path.path_iter() {
+            match seg {
+                PathSegment::Move(pt) => subpaths.push(vec![pt]),
+                PathSegment::Line(pt) => subpaths.last_mut().unwrap().push(pt),
+                _ => panic!("expected a move or line"),
+            }
+        }
+        subpaths
+    }
+
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(100.0, 0.0));
+    let path = path.as_path();
+    let subpaths = subpath_points(&path.dash(&[10.0, 5.0], 0.0));
+    assert_eq!(subpaths.len(), 7);
+    assert_approx_eq!(subpaths[1][0], Point2::new(15.0, 0.0));
+    assert_approx_eq!(subpaths[1][1], Point2::new(25.0, 0.0));
+    assert_approx_eq!(subpaths[6][1], Point2::new(100.0, 0.0));
+
+    // An odd number of lengths is repeated, and the offset can be negative.
+    let subpaths = subpath_points(&path.dash(&[10.0, 5.0, 5.0], -5.0));
+    assert_eq!(subpaths.len(), 7);
+    assert_approx_eq!(subpaths[0][0], Point2::new(5.0, 0.0));
+    assert_approx_eq!(subpaths[0][1], Point2::new(15.0, 0.0));
+    assert_approx_eq!(subpaths[1][0], Point2::new(20.0, 0.0));
+    assert_approx_eq!(subpaths[1][1], Point2::new(25.0, 0.0));
+    assert_approx_eq!(subpaths[2][0], Point2::new(35.0, 0.0));
+    assert_approx_eq!(subpaths[2][1], Point2::new(40.0, 0.0));
+
+    // dots, including one at the end
+    let subpaths = subpath_points(&path.dash(&[0.0, 25.0], 0.0));
+    assert_eq!(subpaths.len(), 5);
+    assert_eq!(subpaths[0], vec![Point2::new(0.0, 0.0), Point2::new(0.0, 0.0)]);
+    assert_approx_eq!(subpaths[4][0], Point2::new(100.0, 0.0));
+
+    // A pattern that adds up to zero draws a solid line.
+    let subpaths = subpath_points(&path.dash(&[0.0, 0.0], 0.0));
+    assert_eq!(subpaths, vec![vec![Point2::new(0.0, 0.0), Point2::new(100.0, 0.0)]]);
+
+    // The dash at the end of a closed subpath continues into the one at the start.
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(40.0, 0.0));
+    path.line_to(Point2::new(40.0, 40.0));
+    path.line_to(Point2::new(0.0, 40.0));
+    path.close();
+    let subpaths = subpath_points(&path.as_path().dash(&[30.0, 10.0], 15.0));
+    assert_eq!(subpaths.len(), 4);
+    assert_approx_eq!(subpaths[0][0], Point2::new(0.0, 15.0));
+    assert_approx_eq!(subpaths[0][1], Point2::new(0.0, 0.0));
+    assert_approx_eq!(subpaths[0][2], Point2::new(15.0, 0.0));
+    assert_approx_eq!(subpaths[1][0], Point2::new(25.0, 0.0));
+    assert_approx_eq!(subpaths[1][1], Point2::new(40.0, 0.0));
+    assert_approx_eq!(subpaths[1][2], Point2::new(40.0, 15.0));
+}
+
+#[test]
+fn test_dash_curves() {
+    use std::f32::consts::PI;
+    use nalgebra::{ApproxEq, Norm};
+
+    let mut path = PathBuf::new();
+    path.arc_to(Point2::new(0.0, 0.0), 50.0, 50.0, 0.0, PI);
+    path.cubic_curve_to(Point2::new(-50.0, 50.0), Point2::new(50.0, 100.0),
+                        Point2::new(50.0, 150.0));
+    let path = path.as_path();
+    let dashed = path.dash(&[7.0, 3.0], 0.0);
+    let dashed = dashed.as_path();
+    assert_approx_eq_eps!(dashed.length(..), path.length(..) * 0.7, 7.0);
+    // Each dash is the right length, except maybe the last.
+    let mut dash_lengths = vec![];
+    for (index, seg) in dashed.path_iter().enumerate() {
+        if let PathSegment::Move(_) = seg {
+            dash_lengths.push(0.0);
+        } else {
+            *dash_lengths.last_mut().unwrap() += dashed.length(index..index + 1);
+        }
+    }
+    dash_lengths.pop();
+    for length in dash_lengths {
+        assert_approx_eq_eps!(length, 7.0, 5e-3);
+    }
+    let (pt, _) = dashed.point_at_distance(.., 7.0).unwrap();
+    assert_approx_eq_eps!((pt - Point2::new(0.0, 0.0)).norm(), 50.0, 1e-3);
+}
@@ -0,0 +1,247 @@
+use std::sync::atomic::Ordering;
+
+use smallvec::SmallVec;
+
+use crate::Point2;
+use super::{next_generation, PathBuf, PathIter, PathSegment, PathSegmentType};
+
+// Returns the type of the segment and the floats it stores in `seg_data`.
+fn segment_data(seg: &PathSegment) -> (PathSegmentType, SmallVec<[f32; 7]>) {
+    let mut data = SmallVec::new();
+    let ty = match *seg {
+        PathSegment::Move(pt) => {
+            data.extend_from_slice(&[pt.x, pt.y]);
+            PathSegmentType::Move
+        },
+        PathSegment::Line(pt) => {
+            data.extend_from_slice(&[pt.x, pt.y]);
+            PathSegmentType::Line
+        },
+        PathSegment::QuadCurve(pt1, pt2) => {
+            data.extend_from_slice(&[pt1.x, pt1.y, pt2.x, pt2.y]);
+            PathSegmentType::QuadCurve
+        },
+        PathSegment::CubicCurve(pt1, pt2, pt3) => {
+            data.extend_from_slice(&[pt1.x, pt1.y, pt2.x, pt2.y, pt3.x, pt3.y]);
+            PathSegmentType::CubicCurve
+        },
+        PathSegment::Arc(ref arc) => {
+            data.extend_from_slice(&[arc.center_pt.x, arc.center_pt.y, arc.x_radius, arc.y_radius,
+                                     arc.rotation, arc.angle1, arc.angle2]);
+            PathSegmentType::Arc
+        },
+        PathSegment::Close => PathSegmentType::Close,
+    };
+    (ty, data)
+}
+
+// Editing a path in place only changes the segments being edited, so it's cheap enough to do
+// every frame while dragging a point. The segments are indexed the same as in `path_iter()`. These
+// functions don't check that the path stays valid, so if the first segment is removed, for
+// example, a line can be left without a move before it.
+impl PathBuf {
+    // Returns a number that changes every time the segments change, so that data made from the
+    // path, like a tessellation, can be checked to see whether it's stale.
+    pub fn generation(&self) -> u64 {
+        let generation = self.generation.load(Ordering::Relaxed);
+        if generation != 0 {
+            return generation;
+        }
+        // The segments can't change while `self` is borrowed, so if another thread got here
+        // first, its generation is just as good.
+        let new_generation = next_generation();
+        match self.generation.compare_exchange(0, new_generation, Ordering::Relaxed,
+                                               Ordering::Relaxed) {
+            Ok(_) => new_generation,
+            Err(generation) => generation,
+        }
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.seg_types.len()
+    }
+
+    // Returns the index in `seg_data` where the segment at `index` starts.
+    fn data_index(&self, index: usize) -> usize {
+        self.seg_types[..index].iter().map(|ty| ty.data_len()).sum()
+    }
+
+    pub fn segment(&self, index: usize) -> Option<PathSegment> {
+        if index >= self.seg_types.len() {
+            return None;
+        }
+        PathIter {
+            types: &self.seg_types,
+            data: &self.seg_data,
+            types_index: index,
+            data_index: self.data_index(index),
+        }.next()
+    }
+
+    // Replaces the segment at `index` with `seg`, which doesn't have to be the same type.
+    pub fn replace_segment(&mut self, index: usize, seg: PathSegment) {
+        assert!(index < self.seg_types.len(), "segment index out of range");
+        let start = self.data_index(index);
+        let end = start + self.seg_types[index].data_len();
+        let (ty, data) = segment_data(&seg);
+        self.seg_types[index] = ty;
+        self.seg_data.splice(start..end, data);
+        self.segments_changed();
+    }
+
+    // Inserts `seg` before the segment at `index`, or at the end if `index` is the segment count.
+    pub fn insert_segment(&mut self, index: usize, seg: PathSegment) {
+        assert!(index <= self.seg_types.len(), "segment index out of range");
+        let start = self.data_index(index);
+        let (ty, data) = segment_data(&seg);
+        self.seg_types.insert(index, ty);
+        self.seg_data.splice(start..start, data);
+        self.segments_changed();
+    }
+
+    pub fn remove_segment(&mut self, index: usize) -> PathSegment {
+        let seg = self.segment(index).expect("segment index out of range");
+        let start = self.data_index(index);
+        let ty = self.seg_types.remove(index);
+        self.seg_data.drain(start..start + ty.data_len());
+        self.segments_changed();
+        seg
+    }
+
+    // Moves one of the points of the segment at `index`. The points are numbered in the order
+    // they're in in the segment's `PathSegment` variant, and an arc's only point is its center.
+    // Panics if the segment doesn't have that point.
+    pub fn set_point(&mut self, index: usize, point_index: usize, pt: Point2<f32>) {
+        assert!(index < self.seg_types.len(), "segment index out of range");
+        let point_count = match self.seg_types[index] {
+            PathSegmentType::Move | PathSegmentType::Line | PathSegmentType::Arc => 1,
+            PathSegmentType::QuadCurve => 2,
+            PathSegmentType::CubicCurve => 3,
+            PathSegmentType::Close => 0,
+        };
+        assert!(point_index < point_count, "the segment has no point {}", point_index);
+        let data_index = self.data_index(index) + point_index * 2;
+        self.seg_data[data_index] = pt.x;
+        self.seg_data[data_index + 1] = pt.y;
+        self.segments_changed();
+    }
+
+    // Splits the segment at `index` into two that make the same shape, the first going from its
+    // start to t and the second from t to its end. Splitting a close adds a line before it. Panics
+    // if the segment is a move.
+    pub fn split_segment(&mut self, index: usize, t: f32) {
+        let curve = self.as_path()
+                        .curves()
+                        .find(|&(curve_index, _)| curve_index >= index)
+                        .and_then(|(curve_index, curve)| {
+                            if curve_index == index { Some(curve) } else { None }
+                        })
+                        .expect("only a line, curve, arc, or close can be split");
+        let first = curve.subcurve(0.0, t).to_segment();
+        if self.seg_types[index] == PathSegmentType::Close {
+            self.insert_segment(index, first);
+        } else {
+            self.replace_segment(index, first);
+            self.insert_segment(index + 1, curve.subcurve(t, 1.0).to_segment());
+        }
+    }
+}
+
+#[test]
+fn test_edit_segments() {
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(10.0, 0.0));
+    path.cubic_curve_to(Point2::new(20.0, 0.0), Point2::new(20.0, 10.0), Point2::new(10.0, 10.0));
+    path.close();
+    assert_eq!(path.segment_count(), 4);
+    assert_eq!(path.segment(2), Some(PathSegment::CubicCurve(Point2::new(20.0, 0.0),
+                                                             Point2::new(20.0, 10.0),
+                                                             Point2::new(10.0, 10.0))));
+    assert_eq!(path.segment(4), None);
+
+    let generation = path.generation();
+    assert_eq!(path.generation(), generation);
+    path.set_point(2, 1, Point2::new(30.0, 10.0));
+    assert!(path.generation() != generation);
+    path.replace_segment(1, PathSegment::QuadCurve(Point2::new(5.0, -5.0),
+                                                   Point2::new(10.0, 0.0)));
+    path.insert_segment(3, PathSegment::Line(Point2::new(0.0, 10.0)));
+    path.insert_segment(5, PathSegment::Move(Point2::new(50.0, 50.0)));
+    assert_eq!(path.remove_segment(0), PathSegment::Move(Point2::new(0.0, 0.0)));
+    path.insert_segment(0, PathSegment::Move(Point2::new(0.0, 1.0)));
+    path.set_point(0, 0, Point2::new(0.0, 0.0));
+    assert_eq!(path.path_iter().collect::<Vec<_>>(), [
+        PathSegment::Move(Point2::new(0.0, 0.0)),
+        PathSegment::QuadCurve(Point2::new(5.0, -5.0), Point2::new(10.0, 0.0)),
+        PathSegment::CubicCurve(Point2::new(20.0, 0.0), Point2::new(30.0, 10.0),
+                                Point2::new(10.0, 10.0)),
+        PathSegment::Line(Point2::new(0.0, 10.0)),
+        PathSegment::Close,
+        PathSegment::Move(Point2::new(50.0, 50.0)),
+    ]);
+    assert_eq!(path.current_point(), Some(Point2::new(50.0, 50.0)));
+
+    // Every change gets a new generation.
+    let mut generations = vec![path.generation()];
+    path.line_to(Point2::new(60.0, 50.0));
+    generations.push(path.generation());
+    path.split_segment(6, 0.5);
+    generations.push(path.generation());
+    path.remove_segment(6);
+    generations.push(path.generation());
+    generations.dedup();
+    assert_eq!(generations.len(), 4);
+
+    // A path that replaces another one doesn't reuse its generations, even after the same number
+    // of changes.
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    let old_generation = path.generation();
+    path = PathBuf::new();
+    path.move_to(Point2::new(5.0, 5.0));
+    assert!(path.generation() != old_generation);
+}
+
+#[test]
+#[should_panic]
+fn test_set_point_out_of_range() {
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(10.0, 0.0));
+    path.set_point(1, 1, Point2::new(0.0, 0.0));
+}
+
+#[test]
+fn test_split_segment() {
+    use std::f32::consts::PI;
+    use nalgebra::ApproxEq;
+    use super::ArcSegment;
+
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(10.0, 0.0));
+    path.quad_curve_to(Point2::new(20.0, 0.0), Point2::new(20.0, 10.0));
+    path.arc_to(Point2::new(20.0, 20.0), 10.0, 10.0, -PI * 0.5, PI * 0.5);
+    path.close();
+    let length = path.as_path().length(..);
+    let bounds = path.as_path().bounding_box();
+
+    path.split_segment(4, 0.5);
+    path.split_segment(3, 0.25);
+    path.split_segment(2, 0.5);
+    path.split_segment(1, 0.5);
+    assert_eq!(path.segment_count(), 9);
+    assert_approx_eq_eps!(path.as_path().length(..), length, 1e-3);
+    assert_approx_eq_eps!(path.as_path().bounding_box(), bounds, 1e-4);
+    assert_eq!(path.segment(1), Some(PathSegment::Line(Point2::new(5.0, 0.0))));
+    assert_eq!(path.segment(2), Some(PathSegment::Line(Point2::new(10.0, 0.0))));
+    assert_eq!(path.segment(5), Some(PathSegment::Arc(
+        ArcSegment::new(Point2::new(20.0, 20.0), 10.0, 10.0, 0.0, -PI * 0.5, -PI * 0.25))));
+    // The close's line is split by a line to its middle.
+    match path.segment(7) {
+        Some(PathSegment::Line(pt)) => assert_approx_eq_eps!(pt, Point2::new(10.0, 15.0), 1e-4),
+        seg => panic!("expected a line but got {:?}", seg),
+    }
+    assert_eq!(path.segment(8), Some(PathSegment::Close));
+}
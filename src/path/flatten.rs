@@ -0,0 +1,208 @@
+use std::f32::consts::FRAC_PI_2;
+
+use nalgebra::{origin, Norm, Vector2};
+
+use crate::painter::AsPathIter;
+use crate::{CubicBezier, Point2, QuadBezier};
+use super::{ArcSegment, Path, PathIter, PathSegment};
+
+// Limits how many times a curve is split in half, in case the tolerance is zero or a point is NaN.
+const MAX_DEPTH: u32 = 16;
+
+// Adds points along the curve to `points` so that lines between them are never farther than
+// `tolerance` from the curve. The start point isn't added, but the end point is.
+pub(crate) fn flatten_quad(bez: &QuadBezier<f32>, tolerance: f32, points: &mut Vec<Point2<f32>>) {
+    flatten_quad_recursive(bez, tolerance, MAX_DEPTH, points);
+    points.push(bez.p2);
+}
+
+fn flatten_quad_recursive(bez: &QuadBezier<f32>, tolerance: f32, depth: u32,
+                          points: &mut Vec<Point2<f32>>) {
+    // The farthest a quadratic curve gets from the line between its end points is a quarter of
+    // this vector's length.
+    let dd = bez.p0.to_vector() - bez.p1.to_vector() * 2.0 + bez.p2.to_vector();
+    if depth == 0 || dd.norm() * 0.25 <= tolerance {
+        return;
+    }
+    let (bez0, bez1) = bez.split(0.5);
+    flatten_quad_recursive(&bez0, tolerance, depth - 1, points);
+    points.push(bez0.p2);
+    flatten_quad_recursive(&bez1, tolerance, depth - 1, points);
+}
+
+pub(crate) fn flatten_cubic(bez: &CubicBezier<f32>, tolerance: f32,
+                            points: &mut Vec<Point2<f32>>) {
+    flatten_cubic_recursive(bez, tolerance, MAX_DEPTH, points);
+    points.push(bez.p3);
+}
+
+fn flatten_cubic_recursive(bez: &CubicBezier<f32>, tolerance: f32, depth: u32,
+                           points: &mut Vec<Point2<f32>>) {
+    // A Bezier curve of degree n is never farther than n(n-1)/8 times the largest second
+    // difference of its control points from the line between its end points, so 3/4 for a cubic
+    // curve (and 1/4 for a quadratic curve, as above).
+    let dd0 = bez.p0.to_vector() - bez.p1.to_vector() * 2.0 + bez.p2.to_vector();
+    let dd1 = bez.p1.to_vector() - bez.p2.to_vector() * 2.0 + bez.p3.to_vector();
+    if depth == 0 || dd0.norm().max(dd1.norm()) * 0.75 <= tolerance {
+        return;
+    }
+    let (bez0, bez1) = bez.split(0.5);
+    flatten_cubic_recursive(&bez0, tolerance, depth - 1, points);
+    points.push(bez0.p3);
+    flatten_cubic_recursive(&bez1, tolerance, depth - 1, points);
+}
+
+pub(crate) fn flatten_arc(arc: &ArcSegment, tolerance: f32, points: &mut Vec<Point2<f32>>) {
+    // Splitting into quarter turns first keeps each piece convex, so that its farthest point from
+    // the line is near the middle.
+    let sweep_angle = arc.angle2 - arc.angle1;
+    let piece_count = (sweep_angle.abs() / FRAC_PI_2).ceil().max(1.0) as u32;
+    for i in 0..piece_count {
+        let angle1 = arc.angle1 + sweep_angle * (i as f32 / piece_count as f32);
+        let angle2 = arc.angle1 + sweep_angle * ((i + 1) as f32 / piece_count as f32);
+        flatten_arc_recursive(arc, angle1, angle2, tolerance, MAX_DEPTH, points);
+        points.push(arc.point_at_angle(angle2));
+    }
+    // Make sure it ends exactly where the next segment starts.
+    if let Some(pt) = points.last_mut() {
+        *pt = arc.end_point();
+    }
+}
+
+fn flatten_arc_recursive(arc: &ArcSegment, angle1: f32, angle2: f32, tolerance: f32, depth: u32,
+                         points: &mut Vec<Point2<f32>>) {
+    // On a circle, the middle of the arc is r * (1 - cos(sweep / 2)) from the line. The ellipse
+    // is a scaled circle, so the distance is scaled by the radius in that direction.
+    let mid_angle = (angle1 + angle2) * 0.5;
+    let radius = Vector2::new(arc.x_radius * mid_angle.cos(), arc.y_radius * mid_angle.sin());
+    let dist = radius.norm() * (1.0 - ((angle2 - angle1) * 0.5).cos());
+    if depth == 0 || dist <= tolerance {
+        return;
+    }
+    flatten_arc_recursive(arc, angle1, mid_angle, tolerance, depth - 1, points);
+    points.push(arc.point_at_angle(mid_angle));
+    flatten_arc_recursive(arc, mid_angle, angle2, tolerance, depth - 1, points);
+}
+
+// An iterator over the segments of a path with the curves replaced by lines. It only returns
+// `PathSegment::Move`, `PathSegment::Line`, and `PathSegment::Close`.
+pub struct Flatten<'a> {
+    iter: PathIter<'a>,
+    tolerance: f32,
+    current_pt: Point2<f32>,
+    subpath_start_pt: Point2<f32>,
+    // the points of the curve being flattened, in reverse order so they can be popped off
+    points: Vec<Point2<f32>>,
+}
+
+impl<'a> Iterator for Flatten<'a> {
+    type Item = PathSegment;
+
+    fn next(&mut self) -> Option<PathSegment> {
+        if let Some(pt) = self.points.pop() {
+            return Some(PathSegment::Line(pt));
+        }
+        let seg = self.iter.next()?;
+        match seg {
+            PathSegment::Move(pt) => {
+                self.subpath_start_pt = pt;
+                self.current_pt = pt;
+                return Some(seg);
+            },
+            PathSegment::Line(pt) => {
+                self.current_pt = pt;
+                return Some(seg);
+            },
+            PathSegment::Close => {
+                self.current_pt = self.subpath_start_pt;
+                return Some(seg);
+            },
+            PathSegment::QuadCurve(pt1, pt2) => {
+                let bez = QuadBezier::new(self.current_pt, pt1, pt2);
+                flatten_quad(&bez, self.tolerance, &mut self.points);
+            },
+            PathSegment::CubicCurve(pt1, pt2, pt3) => {
+                let bez = CubicBezier::new(self.current_pt, pt1, pt2, pt3);
+                flatten_cubic(&bez, self.tolerance, &mut self.points);
+            },
+            PathSegment::Arc(ref arc) => {
+                flatten_arc(arc, self.tolerance, &mut self.points);
+            },
+        }
+        self.points.reverse();
+        self.current_pt = self.points[0];
+        self.points.pop().map(PathSegment::Line)
+    }
+}
+
+impl<'a> Path<'a> {
+    // Returns an iterator of moves, lines, and closes that approximate the path. Curves are
+    // adaptively split so that no line is farther than `tolerance` from the curve it replaces.
+    pub fn flatten(&self, tolerance: f32) -> Flatten<'a> {
+        Flatten {
+            iter: self.path_iter(),
+            tolerance,
+            current_pt: origin(),
+            subpath_start_pt: origin(),
+            points: Vec::new(),
+        }
+    }
+}
+
+#[test]
+fn test_flatten() {
+    use super::PathBuf;
+
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(10.0, 0.0));
+    path.quad_curve_to(Point2::new(20.0, 0.0), Point2::new(20.0, 10.0));
+    path.cubic_curve_to(Point2::new(20.0, 30.0), Point2::new(0.0, 0.0), Point2::new(0.0, 30.0));
+    path.arc_to(Point2::new(0.0, 50.0), 30.0, 20.0, -FRAC_PI_2, FRAC_PI_2 * 3.0);
+    path.close();
+
+    for &tolerance in &[1.0, 0.1, 0.01] {
+        let segs: Vec<_> = path.flatten(tolerance).collect();
+        assert_eq!(segs[0], PathSegment::Move(Point2::new(0.0, 0.0)));
+        assert_eq!(segs[1], PathSegment::Line(Point2::new(10.0, 0.0)));
+        assert_eq!(*segs.last().unwrap(), PathSegment::Close);
+        assert!(segs[2..segs.len() - 1].iter().all(|seg| match *seg {
+            PathSegment::Line(_) => true,
+            _ => false,
+        }));
+        // The curves end exactly where they did before.
+        assert!(segs.contains(&PathSegment::Line(Point2::new(20.0, 10.0))));
+        assert!(segs.contains(&PathSegment::Line(Point2::new(0.0, 30.0))));
+
+        // Check that the midpoints of the lines approximating the quad curve are close to it.
+        let bez = QuadBezier::new(Point2::new(10.0, 0.0), Point2::new(20.0, 0.0),
+                                  Point2::new(20.0, 10.0));
+        let mut prev_pt = Point2::new(10.0, 0.0);
+        for seg in &segs[2..] {
+            let pt = match *seg {
+                PathSegment::Line(pt) => pt,
+                _ => unreachable!(),
+            };
+            let mid_pt = prev_pt + (pt - prev_pt) * 0.5;
+            let dist = (0..=1000).map(|i| (bez.point_at(i as f32 / 1000.0) - mid_pt).norm())
+                                 .fold(f32::INFINITY, f32::min);
+            assert!(dist <= tolerance * 1.01);
+            if pt == bez.p2 {
+                break;
+            }
+            prev_pt = pt;
+        }
+    }
+
+    // a smaller tolerance needs more lines
+    assert!(path.flatten(0.01).count() > path.flatten(0.1).count() * 2);
+
+    // The flatter ends of an ellipse need fewer lines than the ends with more curvature.
+    let mut path = PathBuf::new();
+    path.arc_to(Point2::new(0.0, 0.0), 100.0, 10.0, -FRAC_PI_2, FRAC_PI_2);
+    let xs: Vec<_> = path.flatten(0.1).filter_map(|seg| match seg {
+        PathSegment::Line(pt) => Some(pt.x),
+        _ => None,
+    }).collect();
+    assert!(xs.iter().filter(|&&x| x < 50.0).count() < xs.iter().filter(|&&x| x > 50.0).count());
+}
@@ -0,0 +1,464 @@
+use nalgebra::{origin, Dot, Norm, Vector2};
+use smallvec::SmallVec;
+
+use crate::painter::AsPathIter;
+use crate::Point2;
+use super::curve::Curve;
+use super::dash::dash_subpaths;
+use super::{LineCap, LineJoin, Path, PathSegment, StrokeStyle};
+
+impl<'a> Path<'a> {
+    // Returns the sum of the winding numbers of all the subpaths around `pt`. Subpaths are treated
+    // as closed, the same as when filling. The sign depends on the direction the path goes around
+    // the point.
+    pub fn winding_number(&self, pt: Point2<f32>) -> i32 {
+        let mut winding = 0;
+        let mut current_pt = origin();
+        let mut subpath_start_pt = origin();
+        for seg in self.path_iter() {
+            match seg {
+                PathSegment::Move(seg_pt) => {
+                    winding += Curve::Line(current_pt, subpath_start_pt).winding_number(pt);
+                    subpath_start_pt = seg_pt;
+                    current_pt = seg_pt;
+                },
+                PathSegment::Close => {
+                    winding += Curve::Line(current_pt, subpath_start_pt).winding_number(pt);
+                    current_pt = subpath_start_pt;
+                },
+                _ => {
+                    let curve = Curve::from_segment(current_pt, &seg).unwrap();
+                    winding += curve.winding_number(pt);
+                    current_pt = curve.end_point();
+                },
+            }
+        }
+        winding + Curve::Line(current_pt, subpath_start_pt).winding_number(pt)
+    }
+
+    // Returns whether `pt` is inside the filled path. The winding number around the point is
+    // bitwise ANDed with `mask`, and the point is inside if the result isn't zero. Use
+    // `FillRule::mask()` to get the mask for a fill rule.
+    pub fn is_point_in_fill(&self, pt: Point2<f32>, mask: u32) -> bool {
+        self.winding_number(pt) as u32 & mask != 0
+    }
+
+    // Returns the point on the path closest to `pt`, the index of the segment it's on and its t
+    // along that segment, and its distance from `pt`. A close counts as the line back to the start
+    // of its subpath. Returns `None` if the path has nothing but moves.
+    pub fn project_point(&self, pt: Point2<f32>) -> Option<(Point2<f32>, usize, f32, f32)> {
+        let mut closest: Option<(Point2<f32>, usize, f32, f32)> = None;
+        for (index, curve) in self.curves() {
+            // Skip curves that can't be closer than the closest point so far.
+            if let Some((_, _, _, closest_dist)) = closest {
+                let rect = curve.bounding_box();
+                let dx = (rect.x - pt.x).max(pt.x - rect.right()).max(0.0);
+                let dy = (rect.y - pt.y).max(pt.y - rect.bottom()).max(0.0);
+                if dx * dx + dy * dy >= closest_dist * closest_dist {
+                    continue;
+                }
+            }
+            let (t, dist) = curve.project_point(pt);
+            if closest.map_or(true, |(_, _, _, closest_dist)| dist < closest_dist) {
+                closest = Some((curve.point_at(t), index, t, dist));
+            }
+        }
+        closest
+    }
+
+    // Returns whether `pt` is inside the area covered when stroking the path with `style`. Curves
+    // are replaced by lines that are within `tolerance` of them, so points closer than that to the
+    // edge of the stroke may be on the wrong side of it.
+    pub fn is_point_in_stroke(&self, pt: Point2<f32>, style: &StrokeStyle, tolerance: f32)
+                              -> bool {
+        let mut subpath = StrokedSubpath::new(pt, style);
+        for dashed in dash_subpaths(self, &style.dash_pattern, style.dash_offset) {
+            subpath.points.clear();
+            subpath.points.push((dashed.start_pt, true));
+            for curve in &dashed.curves {
+                let mut points = vec![];
+                curve.flatten(tolerance, &mut points);
+                subpath.points.extend(points.iter().map(|&p| (p, false)));
+                // Joins are only added at the ends of segments. Between the lines of a flattened
+                // curve, round joins are used to fill the gaps, since the curve is smooth there.
+                subpath.points.last_mut().unwrap().1 = true;
+            }
+            if dashed.closed {
+                subpath.points.push((dashed.start_pt, true));
+            }
+            subpath.closed = dashed.closed;
+            let (initial_end_cap, terminal_end_cap) = style.end_caps();
+            let (initial_dash_cap, terminal_dash_cap) = style.dash_caps();
+            subpath.start_cap = if dashed.dash_start { initial_dash_cap } else { initial_end_cap };
+            subpath.end_cap = if dashed.dash_end { terminal_dash_cap } else { terminal_end_cap };
+            if subpath.contains_pt() {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+struct StrokedSubpath<'s> {
+    pt: Point2<f32>,
+    style: &'s StrokeStyle,
+    half_width: f32,
+    // Each point and whether it is at the end of a segment, where a join should be
+    points: Vec<(Point2<f32>, bool)>,
+    closed: bool,
+    start_cap: LineCap,
+    end_cap: LineCap,
+}
+
+impl<'s> StrokedSubpath<'s> {
+    fn new(pt: Point2<f32>, style: &'s StrokeStyle) -> Self {
+        Self {
+            pt,
+            style,
+            half_width: style.width * 0.5,
+            points: vec![],
+            closed: false,
+            start_cap: style.end_caps().0,
+            end_cap: style.end_caps().1,
+        }
+    }
+
+    fn contains_pt(&mut self) -> bool {
+        // Remove repeated points, since they have no direction to put joins or caps on.
+        self.points.dedup_by(|b, a| {
+            if a.0 == b.0 {
+                a.1 |= b.1;
+                true
+            } else {
+                false
+            }
+        });
+        let len = self.points.len();
+        if self.closed && len > 1 && self.points[0].0 == self.points[len - 1].0 {
+            let (_, is_join) = self.points.pop().unwrap();
+            self.points[0].1 |= is_join;
+        }
+
+        let pt = self.pt;
+        let half_width = self.half_width;
+        let points = &self.points;
+        let count = points.len();
+        if count == 0 {
+            return false;
+        }
+        if count == 1 {
+            // A zero length subpath is drawn as just its caps, aligned to the axes as if it were
+            // going to the right.
+            return is_point_in_cap(pt, points[0].0, Vector2::new(1.0, 0.0), self.end_cap,
+                                   half_width) ||
+                   is_point_in_cap(pt, points[0].0, Vector2::new(-1.0, 0.0), self.start_cap,
+                                   half_width);
+        }
+
+        let edge_count = if self.closed { count } else { count - 1 };
+        for i in 0..edge_count {
+            let pt0 = points[i].0;
+            let pt1 = points[(i + 1) % count].0;
+            let dir = pt1 - pt0;
+            let t = (pt - pt0).dot(&dir) / dir.norm_squared();
+            if t >= 0.0 && t <= 1.0 && ((pt0 + dir * t) - pt).norm() <= half_width {
+                return true;
+            }
+        }
+
+        for i in 0..count {
+            if !self.closed && (i == 0 || i == count - 1) {
+                continue;
+            }
+            let (vertex, is_join) = points[i];
+            let prev_pt = points[(i + count - 1) % count].0;
+            let next_pt = points[(i + 1) % count].0;
+            let join = if is_join { self.style.line_join } else { LineJoin::Round };
+            if is_point_in_join(pt, vertex, (vertex - prev_pt).normalize(),
+                                (next_pt - vertex).normalize(), join, half_width) {
+                return true;
+            }
+        }
+
+        if !self.closed {
+            let start_dir = (points[0].0 - points[1].0).normalize();
+            let end_dir = (points[count - 1].0 - points[count - 2].0).normalize();
+            if is_point_in_cap(pt, points[0].0, start_dir, self.start_cap, half_width) ||
+               is_point_in_cap(pt, points[count - 1].0, end_dir, self.end_cap, half_width) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+fn perpendicular(v: Vector2<f32>) -> Vector2<f32> {
+    Vector2::new(-v.y, v.x)
+}
+
+// `dir` is a unit vector pointing away from the end of the line.
+fn is_point_in_cap(pt: Point2<f32>, end_pt: Point2<f32>, dir: Vector2<f32>, cap: LineCap,
+                   half_width: f32) -> bool {
+    let v = pt - end_pt;
+    let along = v.dot(&dir);
+    match cap {
+        LineCap::Flat => false,
+        LineCap::Square => {
+            along >= 0.0 && along <= half_width && v.dot(&perpendicular(dir)).abs() <= half_width
+        },
+        LineCap::Round => along >= 0.0 && v.norm() <= half_width,
+        LineCap::Triangular => {
+            along >= 0.0 && along + v.dot(&perpendicular(dir)).abs() <= half_width
+        },
+    }
+}
+
+// Tests whether the point is in the part of a join on the outside of the corner, which isn't
+// covered by the lines on either side of it. `dir0` and `dir1` are unit vectors in the
+// direction of the lines coming into and going out of the corner.
+fn is_point_in_join(pt: Point2<f32>, vertex: Point2<f32>, dir0: Vector2<f32>, dir1: Vector2<f32>,
+                    join: LineJoin, half_width: f32) -> bool {
+    let cross = dir0.x * dir1.y - dir0.y * dir1.x;
+    let v = pt - vertex;
+    if cross == 0.0 {
+        // Either the lines are straight, and there is no gap to fill, or they double back, and
+        // a bevel has no area. A round join is then the half of the circle in front of the first
+        // line.
+        return matches!(join, LineJoin::Round) && dir0.dot(&dir1) < 0.0 &&
+               v.dot(&dir0) >= 0.0 && v.norm() <= half_width;
+    }
+    let outer0 = perpendicular(dir0) * -cross.signum();
+    let outer1 = perpendicular(dir1) * -cross.signum();
+    if let LineJoin::None = join {
+        return false;
+    }
+    if let LineJoin::Round = join {
+        // The part of the circle between the outer edges of the lines. The rest of the circle
+        // isn't part of the join, since it could stick out past a cap on a short line.
+        let cross_vec2 = |v0: Vector2<f32>, v1: Vector2<f32>| v0.x * v1.y - v0.y * v1.x;
+        let sign = cross_vec2(outer0, outer1);
+        return v.norm() <= half_width && cross_vec2(outer0, v) * sign >= 0.0 &&
+               cross_vec2(v, outer1) * sign >= 0.0;
+    }
+    let corner0 = vertex + outer0 * half_width;
+    let corner1 = vertex + outer1 * half_width;
+    let mut polygon: SmallVec<[Point2<f32>; 5]> = SmallVec::new();
+    polygon.push(vertex);
+    polygon.push(corner0);
+    polygon.extend(join.miter_points(vertex, dir0, dir1, outer0, outer1, half_width));
+    polygon.push(corner1);
+    is_point_in_convex_polygon(pt, &polygon)
+}
+
+fn is_point_in_convex_polygon(pt: Point2<f32>, polygon: &[Point2<f32>]) -> bool {
+    let mut has_pos = false;
+    let mut has_neg = false;
+    for i in 0..polygon.len() {
+        let edge = polygon[(i + 1) % polygon.len()] - polygon[i];
+        let v = pt - polygon[i];
+        let cross = edge.x * v.y - edge.y * v.x;
+        has_pos |= cross > 0.0;
+        has_neg |= cross < 0.0;
+    }
+    !(has_pos && has_neg)
+}
+
+#[test]
+fn test_is_point_in_fill() {
+    use std::f32::consts::PI;
+    use super::{FillRule, PathBuf};
+
+    // A square with a smaller square inside going the same direction and one going the opposite
+    // direction.
+    let mut path = PathBuf::new();
+    for &(x, y, size) in &[(0.0, 0.0, 30.0), (5.0, 5.0, 10.0)] {
+        path.move_to(Point2::new(x, y));
+        path.line_to(Point2::new(x + size, y));
+        path.line_to(Point2::new(x + size, y + size));
+        path.line_to(Point2::new(x, y + size));
+        path.close();
+    }
+    path.move_to(Point2::new(15.0, 15.0));
+    path.line_to(Point2::new(15.0, 25.0));
+    path.line_to(Point2::new(25.0, 25.0));
+    path.line_to(Point2::new(25.0, 15.0));
+    let path = path.as_path();
+    let non_zero = FillRule::NonZero.mask();
+    let even_odd = FillRule::EvenOdd.mask();
+    assert_eq!(path.winding_number(Point2::new(10.0, 10.0)), 2);
+    assert_eq!(path.winding_number(Point2::new(20.0, 20.0)), 0);
+    assert!(path.is_point_in_fill(Point2::new(10.0, 10.0), non_zero));
+    assert!(!path.is_point_in_fill(Point2::new(10.0, 10.0), even_odd));
+    assert!(!path.is_point_in_fill(Point2::new(20.0, 20.0), non_zero));
+    assert!(path.is_point_in_fill(Point2::new(2.0, 20.0), even_odd));
+    assert!(!path.is_point_in_fill(Point2::new(-2.0, 20.0), non_zero));
+    // on the same line as a corner
+    assert!(path.is_point_in_fill(Point2::new(2.0, 5.0), non_zero));
+    assert!(!path.is_point_in_fill(Point2::new(31.0, 5.0), non_zero));
+
+    // Test close to the edge of curves.
+    let mut path = PathBuf::new();
+    path.arc_to(Point2::new(0.0, 0.0), 20.0, 10.0, 0.0, PI * 2.0);
+    path.move_to(Point2::new(30.0, 0.0));
+    path.cubic_curve_to(Point2::new(30.0, 40.0), Point2::new(60.0, 40.0), Point2::new(60.0, 0.0));
+    let path = path.as_path();
+    let angle = 1.0f32;
+    let pt = Point2::new(20.0 * angle.cos(), 10.0 * angle.sin());
+    assert!(path.is_point_in_fill(pt * 0.999, non_zero));
+    assert!(!path.is_point_in_fill(pt * 1.001, non_zero));
+    assert!(path.is_point_in_fill(Point2::new(45.0, 29.9), non_zero));
+    assert!(!path.is_point_in_fill(Point2::new(45.0, 30.1), non_zero));
+    assert!(!path.is_point_in_fill(Point2::new(45.0, -0.1), non_zero));
+}
+
+#[test]
+fn test_project_point() {
+    use std::f32::consts::PI;
+    use nalgebra::ApproxEq;
+    use crate::QuadBezier;
+    use super::PathBuf;
+
+    let mut path = PathBuf::new();
+    path.arc_to(Point2::new(50.0, 50.0), 50.0, 50.0, PI, PI * 1.5);
+    path.line_to(Point2::new(100.0, 0.0));
+    path.quad_curve_to(Point2::new(100.0, 50.0), Point2::new(50.0, 50.0));
+    path.close();
+    path.move_to(Point2::new(200.0, 0.0));
+    let path = path.as_path();
+
+    let (pt, index, t, dist) = path.project_point(Point2::new(75.0, -10.0)).unwrap();
+    assert_approx_eq_eps!(pt, Point2::new(75.0, 0.0), 1e-5);
+    assert_eq!(index, 2);
+    assert_approx_eq_eps!(t, 0.5, 1e-5);
+    assert_approx_eq_eps!(dist, 10.0, 1e-5);
+
+    // The closest point on the arc is toward its center.
+    let (pt, index, t, dist) = path.project_point(Point2::new(10.0, 10.0)).unwrap();
+    let on_arc = 50.0 - 50.0 * 0.5f32.sqrt();
+    assert_approx_eq_eps!(pt, Point2::new(on_arc, on_arc), 1e-3);
+    assert_eq!(index, 1);
+    assert_approx_eq_eps!(t, 0.5, 1e-4);
+    assert_approx_eq_eps!(dist, (on_arc - 10.0) * 2.0f32.sqrt(), 1e-3);
+
+    // the close
+    let (pt, index, t, dist) = path.project_point(Point2::new(25.0, 55.0)).unwrap();
+    assert_approx_eq_eps!(pt, Point2::new(25.0, 50.0), 1e-4);
+    assert_eq!(index, 4);
+    assert_approx_eq_eps!(t, 0.5, 1e-4);
+    assert_approx_eq_eps!(dist, 5.0, 1e-4);
+
+    let (pt, index, t, _) = path.project_point(Point2::new(100.0, 30.0)).unwrap();
+    assert_eq!(index, 3);
+    assert_approx_eq_eps!(pt, QuadBezier::new(Point2::new(100.0, 0.0), Point2::new(100.0, 50.0),
+                                              Point2::new(50.0, 50.0)).point_at(t), 1e-4);
+
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    assert_eq!(path.as_path().project_point(Point2::new(1.0, 1.0)), None);
+}
+
+#[test]
+fn test_is_point_in_stroke() {
+    use std::f32::consts::PI;
+    use super::PathBuf;
+
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(100.0, 0.0));
+    path.line_to(Point2::new(100.0, 100.0));
+    let path = path.as_path();
+
+    let mut style = StrokeStyle::with_width(10.0);
+    style.line_cap = LineCap::Flat;
+    style.line_join = LineJoin::Miter(4.0);
+    assert!(path.is_point_in_stroke(Point2::new(50.0, 4.9), &style, 0.1));
+    assert!(!path.is_point_in_stroke(Point2::new(50.0, 5.1), &style, 0.1));
+    assert!(path.is_point_in_stroke(Point2::new(104.9, -4.9), &style, 0.1));
+    assert!(!path.is_point_in_stroke(Point2::new(-0.1, 0.0), &style, 0.1));
+    style.line_cap = LineCap::Square;
+    assert!(path.is_point_in_stroke(Point2::new(-4.9, 4.9), &style, 0.1));
+    assert!(path.is_point_in_stroke(Point2::new(95.1, 104.9), &style, 0.1));
+    assert!(!path.is_point_in_stroke(Point2::new(-5.1, 0.0), &style, 0.1));
+    style.line_cap = LineCap::Round;
+    assert!(path.is_point_in_stroke(Point2::new(-4.9, 0.0), &style, 0.1));
+    assert!(!path.is_point_in_stroke(Point2::new(-4.0, 4.0), &style, 0.1));
+
+    style.line_join = LineJoin::Bevel;
+    assert!(path.is_point_in_stroke(Point2::new(102.4, -2.4), &style, 0.1));
+    assert!(!path.is_point_in_stroke(Point2::new(104.9, -4.9), &style, 0.1));
+    style.line_join = LineJoin::Round;
+    assert!(path.is_point_in_stroke(Point2::new(103.5, -3.5), &style, 0.1));
+    assert!(!path.is_point_in_stroke(Point2::new(104.0, -4.0), &style, 0.1));
+    // A round join is only the part of the circle on the outside of the corner, so it doesn't
+    // stick out past a flat cap on a short line, the same as in the stroke's outline.
+    let mut short_path = PathBuf::new();
+    short_path.move_to(Point2::new(0.0, 0.0));
+    short_path.line_to(Point2::new(100.0, 0.0));
+    short_path.line_to(Point2::new(100.0, 2.0));
+    let mut flat_style = style.clone();
+    flat_style.line_cap = LineCap::Flat;
+    assert!(short_path.as_path().is_point_in_stroke(Point2::new(103.0, -3.0), &flat_style, 0.1));
+    assert!(!short_path.as_path().is_point_in_stroke(Point2::new(102.0, 4.0), &flat_style, 0.1));
+    // A right angle needs a miter limit of at least sqrt(2).
+    style.line_join = LineJoin::Miter(1.4);
+    assert!(!path.is_point_in_stroke(Point2::new(104.9, -4.9), &style, 0.1));
+    style.line_join = LineJoin::Miter(1.5);
+    assert!(path.is_point_in_stroke(Point2::new(104.9, -4.9), &style, 0.1));
+    // `MiterRevert` is the same as `Miter`.
+    style.line_join = LineJoin::MiterRevert(1.4);
+    assert!(!path.is_point_in_stroke(Point2::new(104.9, -4.9), &style, 0.1));
+    style.line_join = LineJoin::MiterRevert(1.5);
+    assert!(path.is_point_in_stroke(Point2::new(104.9, -4.9), &style, 0.1));
+    // The miter is cut off 1.2 * 5 from the vertex, which is about 4.24 along each axis.
+    style.line_join = LineJoin::MiterTruncate(1.2);
+    assert!(path.is_point_in_stroke(Point2::new(104.0, -4.0), &style, 0.1));
+    assert!(!path.is_point_in_stroke(Point2::new(104.4, -4.4), &style, 0.1));
+    style.line_join = LineJoin::None;
+    assert!(!path.is_point_in_stroke(Point2::new(100.5, -0.5), &style, 0.1));
+    assert!(path.is_point_in_stroke(Point2::new(99.5, -0.5), &style, 0.1));
+
+    style.initial_end_cap = Some(LineCap::Triangular);
+    style.terminal_end_cap = Some(LineCap::Flat);
+    assert!(path.is_point_in_stroke(Point2::new(-2.0, 2.9), &style, 0.1));
+    assert!(!path.is_point_in_stroke(Point2::new(-2.0, 3.1), &style, 0.1));
+    assert!(!path.is_point_in_stroke(Point2::new(100.0, 100.1), &style, 0.1));
+    // An end cap that isn't set is `line_cap`, and `set_line_cap()` sets all of them.
+    style.terminal_end_cap = None;
+    assert!(path.is_point_in_stroke(Point2::new(100.0, 100.1), &style, 0.1));
+    style.initial_dash_cap = Some(LineCap::Round);
+    style.set_line_cap(LineCap::Flat);
+    assert_eq!(style.end_caps(), (LineCap::Flat, LineCap::Flat));
+    assert_eq!(style.dash_caps(), (LineCap::Flat, LineCap::Flat));
+    assert!(!path.is_point_in_stroke(Point2::new(-2.0, 2.9), &style, 0.1));
+    style.line_join = LineJoin::Miter(1.5);
+
+    // Closed subpaths have joins instead of caps. The join at the start is too sharp for the
+    // miter limit, so it's beveled.
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(100.0, 0.0));
+    path.line_to(Point2::new(100.0, 100.0));
+    path.close();
+    assert!(path.as_path().is_point_in_stroke(Point2::new(-1.0, -1.0), &style, 0.1));
+    assert!(!path.as_path().is_point_in_stroke(Point2::new(-2.0, -2.0), &style, 0.1));
+
+    let mut path = PathBuf::new();
+    path.arc_to(Point2::new(0.0, 0.0), 50.0, 50.0, 0.0, PI);
+    let path = path.as_path();
+    assert!(path.is_point_in_stroke(Point2::new(0.0, 54.0), &style, 0.01));
+    assert!(path.is_point_in_stroke(Point2::new(0.0, 46.0), &style, 0.01));
+    assert!(!path.is_point_in_stroke(Point2::new(0.0, 56.0), &style, 0.01));
+    assert!(!path.is_point_in_stroke(Point2::new(0.0, 44.0), &style, 0.01));
+
+    // zero length subpaths
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.close();
+    path.move_to(Point2::new(20.0, 0.0));
+    let path = path.as_path();
+    style.line_cap = LineCap::Square;
+    assert!(path.is_point_in_stroke(Point2::new(4.9, 4.9), &style, 0.1));
+    assert!(!path.is_point_in_stroke(Point2::new(20.0, 0.0), &style, 0.1));
+    style.line_cap = LineCap::Flat;
+    assert!(!path.is_point_in_stroke(Point2::new(0.0, 0.0), &style, 0.1));
+}
@@ -0,0 +1,251 @@
+use nalgebra::Norm;
+
+use crate::painter::AsPathIter;
+use crate::{CubicBezier, Point2};
+use super::curve::Curve;
+use super::subpath::{contours, Contour};
+use super::{ArcSegment, Path, PathBuf, PathSegment};
+
+fn lerp(pt0: Point2<f32>, pt1: Point2<f32>, t: f32) -> Point2<f32> {
+    // Written so that t = 0 and t = 1 give exactly the end points.
+    pt0 * (1.0 - t) + pt1.to_vector() * t
+}
+
+fn lerp_f32(value0: f32, value1: f32, t: f32) -> f32 {
+    value0 * (1.0 - t) + value1 * t
+}
+
+fn lerp_segment(seg0: &PathSegment, seg1: &PathSegment, t: f32) -> PathSegment {
+    match (*seg0, *seg1) {
+        (PathSegment::Move(pt0), PathSegment::Move(pt1)) => PathSegment::Move(lerp(pt0, pt1, t)),
+        (PathSegment::Line(pt0), PathSegment::Line(pt1)) => PathSegment::Line(lerp(pt0, pt1, t)),
+        (PathSegment::QuadCurve(pt0_1, pt0_2), PathSegment::QuadCurve(pt1_1, pt1_2)) => {
+            PathSegment::QuadCurve(lerp(pt0_1, pt1_1, t), lerp(pt0_2, pt1_2, t))
+        },
+        (PathSegment::CubicCurve(pt0_1, pt0_2, pt0_3),
+         PathSegment::CubicCurve(pt1_1, pt1_2, pt1_3)) => {
+            PathSegment::CubicCurve(lerp(pt0_1, pt1_1, t), lerp(pt0_2, pt1_2, t),
+                                    lerp(pt0_3, pt1_3, t))
+        },
+        (PathSegment::Arc(arc0), PathSegment::Arc(arc1)) => {
+            PathSegment::Arc(ArcSegment::new(lerp(arc0.center_pt, arc1.center_pt, t),
+                                             lerp_f32(arc0.x_radius, arc1.x_radius, t),
+                                             lerp_f32(arc0.y_radius, arc1.y_radius, t),
+                                             lerp_f32(arc0.rotation, arc1.rotation, t),
+                                             lerp_f32(arc0.angle1, arc1.angle1, t),
+                                             lerp_f32(arc0.angle2, arc1.angle2, t)))
+        },
+        (PathSegment::Close, PathSegment::Close) => PathSegment::Close,
+        _ => panic!("the segments must be the same type"),
+    }
+}
+
+fn push_cubics(curve: &Curve, cubics: &mut Vec<CubicBezier<f32>>) {
+    match *curve {
+        Curve::Line(pt0, pt1) => {
+            let third = (pt1 - pt0) * (1.0 / 3.0);
+            cubics.push(CubicBezier::new(pt0, pt0 + third, pt1 - third, pt1));
+        },
+        Curve::Quad(ref bez) => {
+            cubics.push(CubicBezier::new(bez.p0,
+                                         bez.p0 + (bez.p1 - bez.p0) * (2.0 / 3.0),
+                                         bez.p2 + (bez.p1 - bez.p2) * (2.0 / 3.0),
+                                         bez.p2));
+        },
+        Curve::Cubic(ref bez) => cubics.push(*bez),
+        // An infinite tolerance gives one curve per quarter turn, the same as if the arc were
+        // drawn with cubic curves by hand.
+        Curve::Arc(ref arc) => cubics.extend(arc.to_cubics(f32::INFINITY)),
+    }
+}
+
+// A contour turned into cubic curves. The line back to the start of a closed contour is included
+// if it has any length.
+#[derive(Clone)]
+struct CubicContour {
+    start_pt: Point2<f32>,
+    cubics: Vec<CubicBezier<f32>>,
+    closed: bool,
+}
+
+impl CubicContour {
+    fn new(contour: &Contour) -> CubicContour {
+        let mut cubics = vec![];
+        for curve in &contour.curves {
+            push_cubics(curve, &mut cubics);
+        }
+        let end_pt = cubics.last().map_or(contour.start_pt, |bez| bez.p3);
+        if contour.closed && end_pt != contour.start_pt {
+            push_cubics(&Curve::Line(end_pt, contour.start_pt), &mut cubics);
+        }
+        CubicContour { start_pt: contour.start_pt, cubics, closed: contour.closed }
+    }
+
+    // Returns a contour that is only a point, which a contour without a match in the other path
+    // is interpolated with so that it grows out of or shrinks into that point.
+    fn point(pt: Point2<f32>, closed: bool) -> CubicContour {
+        CubicContour { start_pt: pt, cubics: vec![], closed }
+    }
+
+    // Returns the average of the end points of the curves, which is the center of a regular shape.
+    fn center(&self) -> Point2<f32> {
+        if self.cubics.is_empty() {
+            return self.start_pt;
+        }
+        let sum = self.cubics.iter().fold(Point2::new(0.0, 0.0), |sum, bez| {
+            sum + bez.p3.to_vector()
+        });
+        sum / self.cubics.len() as f32
+    }
+
+    // Splits the curves in half, longest first, until there are `count` of them. The length of the
+    // control polygon is used as the length of a curve, since it only needs to be roughly right.
+    fn subdivide(&mut self, count: usize) {
+        if self.cubics.is_empty() {
+            let pt = self.start_pt;
+            self.cubics.resize(count, CubicBezier::new(pt, pt, pt, pt));
+            return;
+        }
+        let polygon_length = |bez: &CubicBezier<f32>| {
+            (bez.p1 - bez.p0).norm() + (bez.p2 - bez.p1).norm() + (bez.p3 - bez.p2).norm()
+        };
+        while self.cubics.len() < count {
+            let (index, _) = self.cubics.iter().enumerate().fold((0, -1.0), |longest, (i, bez)| {
+                let length = polygon_length(bez);
+                if length > longest.1 { (i, length) } else { longest }
+            });
+            let (first, second) = self.cubics[index].split(0.5);
+            self.cubics[index] = first;
+            self.cubics.insert(index + 1, second);
+        }
+    }
+}
+
+impl<'a> Path<'a> {
+    // Returns the path between this one at t = 0 and `other` at t = 1, for animating one shape
+    // into another. If the paths have the same types of segments in the same order, each point of
+    // each segment is interpolated. Otherwise, every subpath is turned into cubic curves and the
+    // curves of the one with fewer are split until both have the same number, and then those are
+    // interpolated. A subpath in only one of the paths shrinks into or grows out of the center of
+    // its points. A subpath is closed if it is closed in both paths.
+    //
+    // How smooth the animation looks depends on the subpaths starting at matching points and
+    // going the same direction, which `reversed()` and `normalize_orientation()` can help with.
+    pub fn interpolate(&self, other: &Path, t: f32) -> PathBuf {
+        if self.seg_types == other.seg_types {
+            return self.path_iter()
+                       .zip(other.path_iter())
+                       .map(|(seg0, seg1)| lerp_segment(&seg0, &seg1, t))
+                       .collect();
+        }
+
+        let contours0: Vec<_> = contours(self).iter().map(CubicContour::new).collect();
+        let contours1: Vec<_> = contours(other).iter().map(CubicContour::new).collect();
+        let mut path = PathBuf::new();
+        for i in 0..contours0.len().max(contours1.len()) {
+            let (mut contour0, mut contour1) = match (contours0.get(i), contours1.get(i)) {
+                (Some(contour0), Some(contour1)) => (contour0.clone(), contour1.clone()),
+                (Some(contour0), None) => {
+                    (contour0.clone(), CubicContour::point(contour0.center(), contour0.closed))
+                },
+                (None, Some(contour1)) => {
+                    (CubicContour::point(contour1.center(), contour1.closed), contour1.clone())
+                },
+                (None, None) => unreachable!(),
+            };
+            let count = contour0.cubics.len().max(contour1.cubics.len());
+            contour0.subdivide(count);
+            contour1.subdivide(count);
+
+            path.move_to(lerp(contour0.start_pt, contour1.start_pt, t));
+            for (bez0, bez1) in contour0.cubics.iter().zip(&contour1.cubics) {
+                path.cubic_curve_to(lerp(bez0.p1, bez1.p1, t),
+                                    lerp(bez0.p2, bez1.p2, t),
+                                    lerp(bez0.p3, bez1.p3, t));
+            }
+            if contour0.closed && contour1.closed {
+                path.close();
+            }
+        }
+        path
+    }
+}
+
+#[test]
+fn test_interpolate_matching() {
+    use std::f32::consts::PI;
+
+    let mut path0 = PathBuf::new();
+    path0.move_to(Point2::new(0.0, 0.0));
+    path0.line_to(Point2::new(10.0, 0.0));
+    path0.quad_curve_to(Point2::new(20.0, 0.0), Point2::new(20.0, 10.0));
+    path0.arc_to(Point2::new(20.0, 20.0), 10.0, 10.0, -PI * 0.5, 0.0);
+    path0.close();
+    let mut path1 = PathBuf::new();
+    path1.move_to(Point2::new(0.0, 10.0));
+    path1.line_to(Point2::new(30.0, 0.0));
+    path1.quad_curve_to(Point2::new(40.0, 0.0), Point2::new(20.0, 30.0));
+    path1.arc_to(Point2::new(20.0, 50.0), 10.0, 20.0, -PI * 0.5, PI);
+    path1.close();
+    let (path0, path1) = (path0.as_path(), path1.as_path());
+
+    let half: Vec<_> = path0.interpolate(&path1, 0.5).path_iter().collect();
+    assert_eq!(half[..3], [
+        PathSegment::Move(Point2::new(0.0, 5.0)),
+        PathSegment::Line(Point2::new(20.0, 0.0)),
+        PathSegment::QuadCurve(Point2::new(30.0, 0.0), Point2::new(20.0, 20.0)),
+    ]);
+    assert_eq!(half[3], PathSegment::Arc(ArcSegment::new(Point2::new(20.0, 35.0), 10.0, 15.0,
+                                                         0.0, -PI * 0.5, PI * 0.5)));
+    assert_eq!(half.len(), 5);
+
+    let segs: Vec<_> = path0.path_iter().collect();
+    assert_eq!(path0.interpolate(&path1, 0.0).path_iter().collect::<Vec<_>>(), segs);
+    let segs: Vec<_> = path1.path_iter().collect();
+    assert_eq!(path0.interpolate(&path1, 1.0).path_iter().collect::<Vec<_>>(), segs);
+}
+
+#[test]
+fn test_interpolate_mismatched() {
+    use nalgebra::ApproxEq;
+    use crate::Rect;
+
+    // A diamond turning into a circle, with the corners on the circle
+    let diamond = PathBuf::regular_polygon(Point2::new(50.0, 50.0), 50.0, 4);
+    let circle = PathBuf::circle(Point2::new(50.0, 50.0), 50.0);
+    let (diamond, circle) = (diamond.as_path(), circle.as_path());
+    let start = diamond.interpolate(&circle, 0.0);
+    let end = diamond.interpolate(&circle, 1.0);
+    assert_eq!(start.path_iter().count(), end.path_iter().count());
+    assert_eq!(start.path_iter().last(), Some(PathSegment::Close));
+    assert_approx_eq_eps!(start.as_path().signed_area(), 5000.0, 1e-2);
+    assert_approx_eq_eps!(start.as_path().bounding_box(), diamond.bounding_box(), 1e-3);
+    // The cubic curves are slightly outside the arc.
+    assert_approx_eq_eps!(end.as_path().signed_area(), circle.signed_area(), 5.0);
+    assert_approx_eq_eps!(end.as_path().bounding_box(), circle.bounding_box(), 1e-3);
+    let half = diamond.interpolate(&circle, 0.5);
+    let area = half.as_path().signed_area();
+    assert!(area > 5000.0 && area < circle.signed_area());
+
+    // A triangle turning into two bars, like a play button turning into a pause button
+    let mut play = PathBuf::new();
+    play.move_to(Point2::new(0.0, 0.0));
+    play.line_to(Point2::new(90.0, 50.0));
+    play.line_to(Point2::new(0.0, 100.0));
+    play.close();
+    let mut pause = PathBuf::rect(Rect::new(0.0, 0.0, 30.0, 100.0));
+    pause.extend(PathBuf::rect(Rect::new(60.0, 0.0, 30.0, 100.0)).path_iter());
+    let (play, pause) = (play.as_path(), pause.as_path());
+    let start = play.interpolate(&pause, 0.0);
+    assert_eq!(start.as_path().subpaths().count(), 2);
+    assert_approx_eq_eps!(start.as_path().signed_area(), play.signed_area(), 1e-2);
+    assert_approx_eq_eps!(start.as_path().bounding_box(), play.bounding_box(), 1e-3);
+    let end = play.interpolate(&pause, 1.0);
+    assert_approx_eq_eps!(end.as_path().signed_area(), pause.signed_area(), 1e-2);
+    assert_approx_eq_eps!(end.as_path().bounding_box(), pause.bounding_box(), 1e-3);
+    // The second bar grows out of its center.
+    let second_bar = play.interpolate(&pause, 0.25).as_path().subpaths().nth(1).map(|subpath| {
+        subpath.bounding_box()
+    });
+    assert_approx_eq_eps!(second_bar.unwrap(), Rect::new(71.25, 37.5, 7.5, 25.0), 1e-3);
+}
@@ -0,0 +1,176 @@
+use nalgebra::{origin, Norm};
+
+use crate::painter::AsPathIter;
+use crate::{Point2, Rect};
+use super::boolean::{intersect_pieces, monotonic_pieces, MAX_DEPTH};
+use super::curve::Curve;
+use super::{Path, PathSegment};
+
+impl<'a> Path<'a> {
+    // Returns the points where the path crosses or touches itself, along with the indexes of the
+    // two segments there and the t values on them, with the first segment's index no larger than
+    // the second's. Where a cubic curve has a loop, both segment indexes are the same. A close is
+    // the line back to the start of its subpath, but open subpaths aren't closed, and the point
+    // where one segment ends and the next one starts doesn't count. Each point is only returned
+    // once, even if more than two segments go through it. The points are in the order of the
+    // first segment they are on.
+    pub fn self_intersections(&self) -> Vec<(Point2<f32>, usize, f32, usize, f32)> {
+        // The curves with any length, along with their segment indexes and which contour they are
+        // in, counting the part of a subpath after a close without a move as its own contour
+        let mut curves = vec![];
+        let mut seg_indexes = vec![];
+        let mut contour_indexes = vec![];
+        let mut contour_index = 0;
+        let mut current_pt = origin();
+        let mut subpath_start_pt = origin();
+        for (index, seg) in self.path_iter().enumerate() {
+            let curve = match seg {
+                PathSegment::Move(pt) => {
+                    subpath_start_pt = pt;
+                    current_pt = pt;
+                    contour_index += 1;
+                    continue;
+                },
+                PathSegment::Close => Curve::Line(current_pt, subpath_start_pt),
+                _ => Curve::from_segment(current_pt, &seg).unwrap(),
+            };
+            current_pt = curve.end_point();
+            if curve.tangent_at(0.0).norm_squared() > 0.0 {
+                curves.push(curve);
+                seg_indexes.push(index);
+                contour_indexes.push(contour_index);
+            }
+            if seg == PathSegment::Close {
+                contour_index += 1;
+            }
+        }
+        let mut result = vec![];
+        if curves.is_empty() {
+            return result;
+        }
+
+        // The tolerances are relative to the size of the path, like in `boolean_op()`.
+        let size = curves.iter()
+                         .map(|curve| curve.bounding_box())
+                         .fold(None, |acc: Option<Rect<f32>>, rect| {
+                             Some(acc.map_or(rect, |acc| acc.union(rect)))
+                         })
+                         .map(|rect| rect.width.max(rect.height))
+                         .unwrap();
+        let tolerance = (size * 1e-5).max(1e-6);
+        let vertex_tolerance = tolerance * 4.0;
+
+        // The curve after each one in its contour, which for the last one is the first one, so
+        // that the point where they meet can be ignored
+        let next: Vec<_> = (0..curves.len()).map(|i| {
+            if contour_indexes.get(i + 1) == Some(&contour_indexes[i]) {
+                i + 1
+            } else {
+                contour_indexes.iter().position(|&index| index == contour_indexes[i]).unwrap()
+            }
+        }).collect();
+        let meeting_point = |i: usize, j: usize| {
+            let pt = curves[i].end_point();
+            if next[i] == j && (pt - curves[j].point_at(0.0)).norm() <= vertex_tolerance {
+                Some(pt)
+            } else {
+                None
+            }
+        };
+        let is_near = |pt0: Point2<f32>, pt1: Point2<f32>| (pt0 - pt1).norm() <= vertex_tolerance;
+
+        let mut found = vec![];
+        for (i, curve) in curves.iter().enumerate() {
+            if let Curve::Cubic(ref bez) = *curve {
+                if let Some(loop_points) = bez.loop_points() {
+                    found.push((i, loop_points.first, i, loop_points.second));
+                }
+            }
+        }
+        let pieces = monotonic_pieces(&curves);
+        let mut intersections = vec![];
+        for (k, &(i, t0, t1)) in pieces.iter().enumerate() {
+            for &(j, u0, u1) in &pieces[k + 1..] {
+                // A curve can only cross itself if it's a cubic curve with a loop.
+                if i == j {
+                    continue;
+                }
+                intersections.clear();
+                intersect_pieces(&curves[i], t0, t1, &curves[j], u0, u1, tolerance, MAX_DEPTH,
+                                 &mut intersections);
+                for &(t, u) in &intersections {
+                    let (pt0, pt1) = (curves[i].point_at(t), curves[j].point_at(u));
+                    let is_meeting = |meeting_pt: Option<Point2<f32>>| {
+                        meeting_pt.map_or(false, |pt| is_near(pt0, pt) && is_near(pt1, pt))
+                    };
+                    if !is_meeting(meeting_point(i, j)) && !is_meeting(meeting_point(j, i)) {
+                        found.push((i, t, j, u));
+                    }
+                }
+            }
+        }
+
+        found.sort_by(|a, b| (a.0, a.1).partial_cmp(&(b.0, b.1)).unwrap());
+        for (i, t, j, u) in found {
+            let pt = curves[i].point_at(t);
+            if !result.iter().any(|&(other_pt, ..)| is_near(pt, other_pt)) {
+                result.push((pt, seg_indexes[i], t, seg_indexes[j], u));
+            }
+        }
+        result
+    }
+}
+
+#[test]
+fn test_self_intersections() {
+    use nalgebra::ApproxEq;
+    use super::PathBuf;
+
+    // A bow tie crosses itself once, in the middle.
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(100.0, 100.0));
+    path.line_to(Point2::new(100.0, 0.0));
+    path.line_to(Point2::new(0.0, 100.0));
+    path.close();
+    let intersections = path.as_path().self_intersections();
+    assert_eq!(intersections.len(), 1);
+    let (pt, index0, t, index1, u) = intersections[0];
+    assert_approx_eq_eps!(pt, Point2::new(50.0, 50.0), 1e-3);
+    assert_eq!((index0, index1), (1, 3));
+    assert_approx_eq_eps!(t, 0.5, 1e-4);
+    assert_approx_eq_eps!(u, 0.5, 1e-4);
+
+    // Shapes that don't cross themselves, including where curves meet, have none.
+    let square = PathBuf::rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+    assert!(square.as_path().self_intersections().is_empty());
+    let circle = PathBuf::circle(Point2::new(0.0, 0.0), 50.0);
+    assert!(circle.as_path().self_intersections().is_empty());
+    let star = PathBuf::star(Point2::new(0.0, 0.0), 50.0, 20.0, 5);
+    assert!(star.as_path().self_intersections().is_empty());
+
+    // A cubic curve with a loop, and an open subpath whose end isn't joined to its start
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.cubic_curve_to(Point2::new(150.0, 100.0), Point2::new(-50.0, 100.0),
+                        Point2::new(100.0, 0.0));
+    let intersections = path.as_path().self_intersections();
+    assert_eq!(intersections.len(), 1);
+    let (pt, index0, t, index1, u) = intersections[0];
+    assert_eq!((index0, index1), (1, 1));
+    assert!(t < u);
+    assert_approx_eq_eps!(pt.x, 50.0, 1e-3);
+    // Closing it doesn't add any, since the line back to the start only meets the curve at its
+    // ends.
+    path.close();
+    assert_eq!(path.as_path().self_intersections().len(), 1);
+
+    // Separate subpaths that cross each other
+    let mut path = PathBuf::rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+    path.extend(PathBuf::rect(Rect::new(50.0, 50.0, 100.0, 100.0)).path_iter());
+    let intersections = path.as_path().self_intersections();
+    let pts: Vec<_> = intersections.iter().map(|&(pt, ..)| pt).collect();
+    assert_eq!(pts.len(), 2);
+    assert_approx_eq_eps!(pts[0], Point2::new(100.0, 50.0), 1e-3);
+    assert_approx_eq_eps!(pts[1], Point2::new(50.0, 100.0), 1e-3);
+}
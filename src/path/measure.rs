@@ -0,0 +1,218 @@
+use std::ops::RangeBounds;
+
+use nalgebra::{Norm, Vector2};
+
+use crate::Point2;
+use super::curve::Curve;
+use super::Path;
+
+// The abscissas and weights of 8 point Gauss-Legendre quadrature on [-1, 1]. Each abscissa is
+// used both positive and negative.
+const GAUSS_LEGENDRE: [(f32, f32); 4] = [
+    (0.183_434_64, 0.362_683_78),
+    (0.525_532_4, 0.313_706_64),
+    (0.796_666_5, 0.222_381_03),
+    (0.960_289_9, 0.101_228_54),
+];
+
+// Limits how many times the range is split in half when a curve isn't smooth enough for the
+// quadrature to be accurate, such as at a cusp.
+const MAX_DEPTH: u32 = 12;
+
+// The quadrature is accurate enough once splitting the range in half changes the length by less
+// than this fraction of it.
+const LENGTH_TOLERANCE: f32 = 1e-6;
+
+impl Curve {
+    // Returns the length of the curve between t0 and t1.
+    pub(crate) fn length_between(&self, t0: f32, t1: f32) -> f32 {
+        if let Curve::Line(pt0, pt1) = *self {
+            return (pt1 - pt0).norm() * (t1 - t0);
+        }
+        let length = self.gauss_legendre(t0, t1);
+        self.length_recursive(t0, t1, length, MAX_DEPTH)
+    }
+
+    pub(crate) fn length(&self) -> f32 {
+        self.length_between(0.0, 1.0)
+    }
+
+    fn gauss_legendre(&self, t0: f32, t1: f32) -> f32 {
+        let half = (t1 - t0) * 0.5;
+        let mid = (t0 + t1) * 0.5;
+        let sum: f32 = GAUSS_LEGENDRE.iter().map(|&(x, weight)| {
+            weight * (self.derivative_at(mid - half * x).norm() +
+                      self.derivative_at(mid + half * x).norm())
+        }).sum();
+        sum * half
+    }
+
+    fn length_recursive(&self, t0: f32, t1: f32, length: f32, depth: u32) -> f32 {
+        let mid = (t0 + t1) * 0.5;
+        let length0 = self.gauss_legendre(t0, mid);
+        let length1 = self.gauss_legendre(mid, t1);
+        let split_length = length0 + length1;
+        if depth == 0 || (split_length - length).abs() <= split_length * LENGTH_TOLERANCE {
+            return split_length;
+        }
+        self.length_recursive(t0, mid, length0, depth - 1) +
+            self.length_recursive(mid, t1, length1, depth - 1)
+    }
+
+    // Returns the t where the length of the curve from the start is `distance`. `length` must be
+    // the length of the whole curve.
+    pub(crate) fn t_at_distance(&self, distance: f32, length: f32) -> f32 {
+        if distance <= 0.0 {
+            return 0.0;
+        } else if distance >= length {
+            return 1.0;
+        }
+        if let Curve::Line(..) = *self {
+            return distance / length;
+        }
+        // Use Newton's method, since the derivative of the length is the speed, and fall back to
+        // bisection if a step would leave the range the answer is known to be in.
+        let (mut low, mut high) = (0.0, 1.0);
+        let mut t = distance / length;
+        for _ in 0..16 {
+            let error = self.length_between(0.0, t) - distance;
+            if error.abs() <= length * LENGTH_TOLERANCE * 10.0 {
+                break;
+            }
+            if error < 0.0 {
+                low = t;
+            } else {
+                high = t;
+            }
+            let speed = self.derivative_at(t).norm();
+            let next_t = t - error / speed;
+            t = if speed > 0.0 && next_t > low && next_t < high {
+                next_t
+            } else {
+                (low + high) * 0.5
+            };
+        }
+        t
+    }
+}
+
+impl<'a> Path<'a> {
+    // Returns the length of the segments with indexes in `segments`. Moves have no length, and a
+    // close has the length of the line back to the start of its subpath.
+    pub fn length<R: RangeBounds<usize>>(&self, segments: R) -> f32 {
+        self.curves()
+            .filter(|&(index, _)| segments.contains(&index))
+            .map(|(_, curve)| curve.length())
+            .sum()
+    }
+
+    // Returns the point `distance` along the segments with indexes in `segments` and a unit vector
+    // in the direction of the path there. The distance is clamped to the length of the segments.
+    // Returns `None` if there are no segments in the range other than moves.
+    pub fn point_at_distance<R: RangeBounds<usize>>(&self, segments: R, distance: f32)
+                                                    -> Option<(Point2<f32>, Vector2<f32>)> {
+        let mut remaining = distance.max(0.0);
+        let mut last_curve = None;
+        for (_, curve) in self.curves().filter(|&(index, _)| segments.contains(&index)) {
+            let length = curve.length();
+            if length == 0.0 {
+                // Only use a curve with no length if there is nothing else.
+                if last_curve.is_none() {
+                    last_curve = Some(curve);
+                }
+                continue;
+            }
+            if remaining <= length {
+                let t = curve.t_at_distance(remaining, length);
+                return Some((curve.point_at(t), curve.tangent_at(t)));
+            }
+            remaining -= length;
+            last_curve = Some(curve);
+        }
+        last_curve.map(|curve| (curve.end_point(), curve.tangent_at(1.0)))
+    }
+}
+
+#[test]
+fn test_length() {
+    use std::f32::consts::PI;
+    use nalgebra::ApproxEq;
+    use super::PathBuf;
+
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(30.0, 40.0));
+    path.arc_to(Point2::new(30.0, 50.0), 10.0, 10.0, -PI * 0.5, PI * 0.5);
+    path.move_to(Point2::new(100.0, 0.0));
+    path.quad_curve_to(Point2::new(110.0, 0.0), Point2::new(120.0, 0.0));
+    path.cubic_curve_to(Point2::new(120.0, 10.0), Point2::new(120.0, 20.0),
+                        Point2::new(120.0, 30.0));
+    path.close();
+    let path = path.as_path();
+
+    assert_approx_eq_eps!(path.length(..), 50.0 + PI * 10.0 + 20.0 + 30.0 + 10.0 * 13f32.sqrt(),
+                          1e-3);
+    assert_approx_eq_eps!(path.length(1..2), 50.0, 1e-4);
+    assert_approx_eq_eps!(path.length(2..3), PI * 10.0, 1e-4);
+    assert_eq!(path.length(3..4), 0.0);
+
+    // A quarter of an ellipse with a known length.
+    let mut path = PathBuf::new();
+    path.arc_to(Point2::new(0.0, 0.0), 2.0, 1.0, 0.0, PI * 0.5);
+    assert_approx_eq_eps!(path.as_path().length(..), 2.422_112, 1e-5);
+
+    // A cubic curve with a cusp in the middle
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.cubic_curve_to(Point2::new(1.0, 1.0), Point2::new(0.0, 1.0), Point2::new(1.0, 0.0));
+    let length = path.as_path().length(..);
+    let flattened_length: f32 = {
+        let pts: Vec<_> = (0..=10000).map(|i| {
+            let t = i as f32 / 10000.0;
+            let mt = 1.0 - t;
+            Point2::new(3.0 * mt * mt * t + t * t * t, 3.0 * mt * mt * t + 3.0 * mt * t * t)
+        }).collect();
+        pts.windows(2).map(|w| (w[1] - w[0]).norm()).sum()
+    };
+    assert_approx_eq_eps!(length, flattened_length, 1e-3);
+}
+
+#[test]
+fn test_point_at_distance() {
+    use std::f32::consts::PI;
+    use nalgebra::ApproxEq;
+    use super::PathBuf;
+
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(30.0, 40.0));
+    path.arc_to(Point2::new(30.0, 50.0), 10.0, 10.0, -PI * 0.5, PI * 0.5);
+    path.cubic_curve_to(Point2::new(0.0, 60.0), Point2::new(0.0, 60.0), Point2::new(0.0, 60.0));
+    let path = path.as_path();
+
+    let (pt, tangent) = path.point_at_distance(.., 25.0).unwrap();
+    assert_approx_eq!(pt, Point2::new(15.0, 20.0));
+    assert_approx_eq!(tangent, Vector2::new(0.6, 0.8));
+
+    // halfway around the arc
+    let (pt, tangent) = path.point_at_distance(.., 50.0 + PI * 5.0).unwrap();
+    assert_approx_eq_eps!(pt, Point2::new(40.0, 50.0), 1e-4);
+    assert_approx_eq_eps!(tangent, Vector2::new(0.0, 1.0), 1e-4);
+
+    // The end of the cubic curve has a zero derivative, but still has a direction.
+    let (pt, tangent) = path.point_at_distance(.., 1000.0).unwrap();
+    assert_eq!(pt, Point2::new(0.0, 60.0));
+    assert_approx_eq!(tangent, Vector2::new(-1.0, 0.0));
+    let (pt, _) = path.point_at_distance(.., -10.0).unwrap();
+    assert_eq!(pt, Point2::new(0.0, 0.0));
+
+    // The point moves along the cubic curve at a constant speed.
+    let mut prev_pt = Point2::new(30.0, 60.0);
+    for i in 1..=10 {
+        let (pt, _) = path.point_at_distance(3..4, i as f32 * 3.0).unwrap();
+        assert_approx_eq_eps!((pt - prev_pt).norm(), 3.0, 1e-3);
+        prev_pt = pt;
+    }
+
+    assert!(path.point_at_distance(0..1, 10.0).is_none());
+}
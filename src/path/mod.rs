@@ -1,21 +1,133 @@
 
 use std::any::Any;
 use std::collections::HashMap;
+use std::error;
+use std::f32::consts::PI;
+use std::fmt;
+use std::iter::FromIterator;
 use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
 use coordinates::*;
 use crate::painter::AsPathIter;
 
-use super::{Point2, QuadBezier};
-use nalgebra::{ApproxEq, Cross, origin, Norm, Vector2};
-
+use super::{CubicBezier, Point2, QuadBezier};
+use nalgebra::{ApproxEq, Cross, Dot, origin, Norm, Vector2};
+use smallvec::SmallVec;
+
+mod boolean;
+mod bounds;
+mod curve;
+mod dash;
+mod edit;
+mod flatten;
+mod hit_test;
+mod intersect;
+mod interpolate;
+mod measure;
+mod offset;
+mod postscript;
+mod shapes;
+mod stroke;
+mod subpath;
+mod svg;
+mod tessellate;
+mod transform;
+mod trim;
+
+pub use self::boolean::BooleanOp;
+pub use self::flatten::Flatten;
+pub use self::subpath::Subpaths;
+pub use self::tessellate::{BakedFilledPath, FillVertex};
+pub use self::transform::Transformed;
+pub use self::trim::TrimPosition;
+
+// Limits how many curves `ArcSegment::to_cubics()` returns, in case the tolerance is zero.
+const MAX_ARC_CUBICS: u32 = 1024;
+
+// An arc of an ellipse. The ellipse's x axis is rotated by `rotation`, and the angles are measured
+// from it, so the angles are those of a point on the unrotated ellipse before it is rotated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
 pub struct ArcSegment {
-	center_pt: Point2<f32>,
-	x_radius: f32,
-	y_radius: f32,
-	angle1: f32,
-	angle2: f32,
+	pub center_pt: Point2<f32>,
+	pub x_radius: f32,
+	pub y_radius: f32,
+	pub rotation: f32,
+	pub angle1: f32,
+	pub angle2: f32,
 }
 
+impl ArcSegment {
+    pub fn new(center_pt: Point2<f32>, x_radius: f32, y_radius: f32, rotation: f32,
+               angle1: f32, angle2: f32) -> Self {
+        Self { center_pt, x_radius, y_radius, rotation, angle1, angle2 }
+    }
+
+    // Rotates a vector from the ellipse's axes to the path's.
+    fn rotate(&self, v: Vector2<f32>) -> Vector2<f32> {
+        if self.rotation == 0.0 {
+            return v;
+        }
+        let (sin_rot, cos_rot) = self.rotation.sin_cos();
+        Vector2::new(cos_rot * v.x - sin_rot * v.y, sin_rot * v.x + cos_rot * v.y)
+    }
+
+    // https://en.wikipedia.org/wiki/Ellipse#Equations
+    pub fn point_at_angle(&self, angle: f32) -> Point2<f32> {
+        self.center_pt +
+            self.rotate(Vector2::new(self.x_radius * angle.cos(), self.y_radius * angle.sin()))
+    }
+
+    // Returns the derivative of the point with respect to the angle.
+    pub(crate) fn derivative_at_angle(&self, angle: f32) -> Vector2<f32> {
+        self.rotate(Vector2::new(-self.x_radius * angle.sin(), self.y_radius * angle.cos()))
+    }
+
+    pub fn start_point(&self) -> Point2<f32> {
+        self.point_at_angle(self.angle1)
+    }
+
+    pub fn end_point(&self) -> Point2<f32> {
+        self.point_at_angle(self.angle2)
+    }
+
+    // Returns as few cubic curves as possible that are never farther than `tolerance` from the
+    // arc. Each curve covers an equal part of the arc and at most a quarter turn, so an infinite
+    // tolerance gives one curve per quarter turn.
+    pub fn to_cubics(&self, tolerance: f32) -> SmallVec<[CubicBezier<f32>; 4]> {
+        let sweep_angle = self.angle2 - self.angle1;
+        let radius = self.x_radius.abs().max(self.y_radius.abs());
+        // A cubic curve approximating a circular arc of angle theta is never farther than
+        // r * 4/27 * sin^6(theta/4) / cos^2(theta/4) from it. An ellipse is a scaled circle, so
+        // using the larger radius bounds its error too.
+        let error = |piece_count: u32| {
+            let quarter_angle = sweep_angle.abs() / piece_count as f32 * 0.25;
+            radius * 4.0 / 27.0 * quarter_angle.sin().powi(6) / quarter_angle.cos().powi(2)
+        };
+        let mut piece_count = (sweep_angle.abs() / (PI * 0.5)).ceil().max(1.0) as u32;
+        while piece_count < MAX_ARC_CUBICS && !(error(piece_count) <= tolerance) {
+            piece_count += 1;
+        }
+
+        let piece_sweep = sweep_angle / piece_count as f32;
+        // https://pomax.github.io/bezierinfo/#circles_cubic
+        let k = 4.0 / 3.0 * (piece_sweep * 0.25).tan();
+        let mut cubics = SmallVec::new();
+        for i in 0..piece_count {
+            let angle1 = self.angle1 + piece_sweep * i as f32;
+            let angle2 = if i + 1 == piece_count { self.angle2 } else { angle1 + piece_sweep };
+            let pt0 = self.point_at_angle(angle1);
+            let pt3 = self.point_at_angle(angle2);
+            cubics.push(CubicBezier::new(pt0,
+                                         pt0 + self.derivative_at_angle(angle1) * k,
+                                         pt3 - self.derivative_at_angle(angle2) * k,
+                                         pt3));
+        }
+        cubics
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum PathSegment {
 	Move(Point2<f32>),
 	Line(Point2<f32>),
@@ -25,6 +137,7 @@ pub enum PathSegment {
 	Close,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum PathSegmentType { // smaller than a PathSegment
     Move,
     Line,
@@ -34,48 +147,136 @@ enum PathSegmentType { // smaller than a PathSegment
     Close,
 }
 
+impl PathSegmentType {
+    // The number of floats the segment stores in `seg_data`.
+    fn data_len(&self) -> usize {
+        match *self {
+            PathSegmentType::Move => 2,
+            PathSegmentType::Line => 2,
+            PathSegmentType::QuadCurve => 4,
+            PathSegmentType::CubicCurve => 6,
+            PathSegmentType::Arc => 7,
+            PathSegmentType::Close => 0,
+        }
+    }
+}
+
+// Which parts of a path are inside it when it is filled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillRule {
+    // Inside where the winding number isn't zero.
+    NonZero,
+    // Inside where the winding number is odd.
+    EvenOdd,
+}
+
+impl FillRule {
+    // Returns the mask to pass to `Path::is_point_in_fill()`.
+    pub fn mask(&self) -> u32 {
+        match *self {
+            FillRule::NonZero => !0,
+            FillRule::EvenOdd => 1,
+        }
+    }
+}
+
 // should EndCap and JoinStyle be set here? They seem to be in NVpr.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum LineCap {
     Flat,
     Square,
     Round,
-    //Triangular,
+    // A triangle with its tip half the line width past the end
+    Triangular,
 }
 
 #[derive(Clone, Copy, Debug)]
 pub enum LineJoin {
-    //None,
+    // Nothing fills the notch on the outside of the corner.
+    None,
     Round,
     Bevel,
-    // (the miter limit)
-    Miter(f32),
-    //MiterRevert(f32), // default for SVG, PostScript, PDF, Cairo, NVpr
-    //MiterTruncate(f32), // default for Flash, XPS, and Qt
+    // (the miter limit) Where the miter would be longer than the limit times the line width, a
+    // bevel is used.
+    Miter(f32), // default for SVG, PostScript, PDF, Cairo, NVpr
+    // An alias of `Miter` for code that names each way of handling the miter limit. It behaves
+    // exactly the same, and `Miter` is the one to use otherwise.
+    MiterRevert(f32),
+    // Where the miter would be longer than the limit times the line width, it is cut off at that
+    // length, like SVG 2's `miter-clip`.
+    MiterTruncate(f32), // default for Flash, XPS, and Qt
 }
 
 impl LineJoin {
     pub fn miter_limit(&self) -> Option<f32> {
-        if let LineJoin::Miter(limit) = self {
-            Some(*limit)
-        } else {
-            None
+        match *self {
+            LineJoin::Miter(limit) | LineJoin::MiterRevert(limit) |
+            LineJoin::MiterTruncate(limit) => Some(limit),
+            _ => None,
+        }
+    }
+
+    // Returns the points that the outside of a miter join goes through between the outer corners
+    // of the two lines, or no points if the join should be a bevel instead. `outer0` and `outer1`
+    // are the unit normals of the lines pointing to the outside of the corner. A line that doubles
+    // back on itself always gets a bevel.
+    fn miter_points(
+        &self,
+        vertex: Point2<f32>,
+        dir0: Vector2<f32>,
+        dir1: Vector2<f32>,
+        outer0: Vector2<f32>,
+        outer1: Vector2<f32>,
+        half_width: f32,
+    ) -> SmallVec<[Point2<f32>; 2]> {
+        let mut points = SmallVec::new();
+        let limit = match self.miter_limit() {
+            Some(limit) => limit,
+            None => return points,
+        };
+        let cos = outer0.dot(&outer1);
+        if 1.0 + cos <= 0.0 {
+            return points;
+        }
+        // The miter length over the line width is 1 / sin(theta / 2), where theta is the angle
+        // between the lines, and sin(theta / 2)^2 = (1 + cos) / 2.
+        if 2.0 / (1.0 + cos) <= limit * limit {
+            points.push(vertex + (outer0 + outer1) * (half_width / (1.0 + cos)));
+        } else if let LineJoin::MiterTruncate(_) = *self {
+            // Cut the miter off perpendicular to its bisector.
+            let bisector = (outer0 + outer1).normalize();
+            let excess = half_width * limit - half_width * outer0.dot(&bisector);
+            if excess > 0.0 {
+                points.push(vertex + outer0 * half_width + dir0 * (excess / dir0.dot(&bisector)));
+                points.push(vertex + outer1 * half_width + dir1 * (excess / dir1.dot(&bisector)));
+            }
         }
+        points
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 #[non_exhaustive]
 pub struct StrokeStyle {
     pub width: f32,
+    // The cap at both ends of each open subpath and of each dash, unless one of the end or dash
+    // caps below is set
 	pub line_cap: LineCap,
 	pub line_join: LineJoin,
-    // TODO: add dash style here
-
-	//pub initial_end_cap: LineCap,
-	//pub terminal_end_cap: LineCap,
-	//pub initial_dash_cap: LineCap,
-	//pub terminal_dash_cap: LineCap,
+    // The lengths of the dashes and the gaps between them, alternating and starting with a dash.
+    // If there are an odd number of lengths, they are repeated to make an even number, like SVG.
+    // The line is solid if the pattern is empty, has a negative length, or adds up to zero.
+    pub dash_pattern: Vec<f32>,
+    // How far into the dash pattern each subpath starts
+    pub dash_offset: f32,
+
+    // The caps at the start and end of each open subpath, which take precedence over `line_cap`
+	pub initial_end_cap: Option<LineCap>,
+	pub terminal_end_cap: Option<LineCap>,
+    // The caps at the start and end of each dash, which take precedence over `line_cap`, except
+    // where a dash starts or ends at the start or end of an open subpath, which get the end caps
+	pub initial_dash_cap: Option<LineCap>,
+	pub terminal_dash_cap: Option<LineCap>,
 }
 
 impl StrokeStyle {
@@ -84,6 +285,12 @@ impl StrokeStyle {
             width: 1.0,
 			line_cap: LineCap::Flat,
 			line_join: LineJoin::Miter(4.0),
+            dash_pattern: vec![],
+            dash_offset: 0.0,
+			initial_end_cap: None,
+			terminal_end_cap: None,
+			initial_dash_cap: None,
+			terminal_dash_cap: None,
 		}
 	}
 
@@ -91,6 +298,27 @@ impl StrokeStyle {
         Self { width, ..Self::new() }
     }
 
+    // Returns the caps at the start and end of each open subpath.
+    pub fn end_caps(&self) -> (LineCap, LineCap) {
+        (self.initial_end_cap.unwrap_or(self.line_cap),
+         self.terminal_end_cap.unwrap_or(self.line_cap))
+    }
+
+    // Returns the caps at the start and end of each dash.
+    pub fn dash_caps(&self) -> (LineCap, LineCap) {
+        (self.initial_dash_cap.unwrap_or(self.line_cap),
+         self.terminal_dash_cap.unwrap_or(self.line_cap))
+    }
+
+    // Sets the end caps and the dash caps all to `cap`, like the one line cap in SVG and PDF.
+    pub fn set_line_cap(&mut self, cap: LineCap) {
+        self.line_cap = cap;
+        self.initial_end_cap = None;
+        self.terminal_end_cap = None;
+        self.initial_dash_cap = None;
+        self.terminal_dash_cap = None;
+    }
+
 }
 
 impl Default for StrokeStyle {
@@ -99,6 +327,50 @@ impl Default for StrokeStyle {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathParseErrorKind {
+    // A command letter was expected but something else was found.
+    ExpectedCommand,
+    ExpectedNumber,
+    // An SVG arc flag, which must be `0` or `1`.
+    ExpectedFlag,
+    // The path draws something before setting a current point with a move.
+    NoCurrentPoint,
+    UnknownOperator,
+    // A PostScript or PDF operator was given too many or too few operands.
+    WrongOperandCount,
+}
+
+// The error returned when a path string can't be parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PathParseError {
+    pub kind: PathParseErrorKind,
+    // The byte offset in the string of the first token that couldn't be parsed.
+    pub offset: usize,
+}
+
+impl PathParseError {
+    fn new(kind: PathParseErrorKind, offset: usize) -> Self {
+        Self { kind, offset }
+    }
+}
+
+impl fmt::Display for PathParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match self.kind {
+            PathParseErrorKind::ExpectedCommand => "expected a command",
+            PathParseErrorKind::ExpectedNumber => "expected a number",
+            PathParseErrorKind::ExpectedFlag => "expected a flag (0 or 1)",
+            PathParseErrorKind::NoCurrentPoint => "no current point",
+            PathParseErrorKind::UnknownOperator => "unknown operator",
+            PathParseErrorKind::WrongOperandCount => "wrong number of operands",
+        };
+        write!(f, "{} at byte offset {}", msg, self.offset)
+    }
+}
+
+impl error::Error for PathParseError {}
+
 // It may make sense to separate out stuff into another type: BakedStrokedPath or BakedStroke. When
 // you create it, you specify:
 // - the stroke width
@@ -116,6 +388,17 @@ pub struct PathBuf {
 	// stored in separate arrays for memory efficiency
     seg_types: Vec<PathSegmentType>,
     seg_data: Vec<f32>,
+    // Zero after the segments change, and set to a value no path has had before the next time
+    // it's read, so that building a path doesn't touch the shared counter for every segment
+    generation: AtomicU64,
+}
+
+// The next value of `PathBuf::generation`, which is shared by all paths so that a path replaced
+// with a new one doesn't reuse a generation the old one had
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+fn next_generation() -> u64 {
+    NEXT_GENERATION.fetch_add(1, Ordering::Relaxed)
 }
 
 pub struct Path<'a> {
@@ -127,13 +410,9 @@ pub struct Path<'a> {
 impl<'a> Path<'a> {
 
     // See https://www.khronos.org/registry/OpenGL/extensions/NV/NV_path_rendering.txt
-    // 6.X.4. Path Object Geometric Queries for description of mask.
-
-	// pub fn is_point_in_fill(&self, pt: Point, mask: Option<u32>) -> bool;
-	// pub fn is_point_in_stroke(&self, pt: Point) -> bool;
-
-	// pub fn length(segments: Range<usize>) -> f32;
-	// pub fn point_at_distance(segments: Range<usize>, distance: f32) -> (Point, f32, f32);
+    // 6.X.4. Path Object Geometric Queries for description of mask. `is_point_in_fill()` and
+    // `is_point_in_stroke()` are in hit_test.rs, and `length()` and `point_at_distance()` are in
+    // measure.rs.
 
 }
 
@@ -155,6 +434,7 @@ impl PathBuf {
 		PathBuf {
 			seg_types: vec![],
 			seg_data: vec![],
+			generation: AtomicU64::new(0),
 		}
 	}
 
@@ -174,13 +454,23 @@ impl PathBuf {
         }
     }
 
+    // Called after the segments change, so that the next `generation()` is a new one.
+    fn segments_changed(&mut self) {
+        *self.generation.get_mut() = 0;
+    }
+
 	pub fn move_to(&mut self, pt: Point2<f32>) {
         self.seg_types.push(PathSegmentType::Move);
         self.seg_data.push(pt.x);
         self.seg_data.push(pt.y);
+        self.segments_changed();
 	}
 
+	// If there is no current point, `pt` is relative to the origin, like the first command of an
+	// SVG path.
 	pub fn rel_move_to(&mut self, pt: Point2<f32>) {
+        let base = self.current_point().unwrap_or(origin());
+        self.move_to(base + pt.to_vector());
 	}
 
 	pub fn line_to(&mut self, pt: Point2<f32>) {
@@ -188,9 +478,12 @@ impl PathBuf {
         self.seg_types.push(PathSegmentType::Line);
         self.seg_data.push(pt.x);
         self.seg_data.push(pt.y);
+        self.segments_changed();
 	}
 
 	pub fn rel_line_to(&mut self, pt: Point2<f32>) {
+        let base = self.current_point().expect("rel_line_to requires a current point");
+        self.line_to(base + pt.to_vector());
 	}
 
 	pub fn quad_curve_to(&mut self, pt1: Point2<f32>, pt2: Point2<f32>) {
@@ -200,62 +493,230 @@ impl PathBuf {
         self.seg_data.push(pt1.y);
         self.seg_data.push(pt2.x);
         self.seg_data.push(pt2.y);
+        self.segments_changed();
 	}
 
 	pub fn rel_quad_curve_to(&mut self, pt1: Point2<f32>, pt2: Point2<f32>) {
+        let base = self.current_point().expect("rel_quad_curve_to requires a current point");
+        self.quad_curve_to(base + pt1.to_vector(), base + pt2.to_vector());
 	}
 
 	pub fn cubic_curve_to(&mut self, pt1: Point2<f32>, pt2: Point2<f32>, pt3: Point2<f32>) {
+        self.current_point().expect("cubic_curve_to requires a current point");
+        self.seg_types.push(PathSegmentType::CubicCurve);
+        self.seg_data.push(pt1.x);
+        self.seg_data.push(pt1.y);
+        self.seg_data.push(pt2.x);
+        self.seg_data.push(pt2.y);
+        self.seg_data.push(pt3.x);
+        self.seg_data.push(pt3.y);
+        self.segments_changed();
 	}
 
 	pub fn rel_cubic_curve_to(&mut self, pt1: Point2<f32>, pt2: Point2<f32>, pt3: Point2<f32>) {
+        let base = self.current_point().expect("rel_cubic_curve_to requires a current point");
+        self.cubic_curve_to(base + pt1.to_vector(), base + pt2.to_vector(), base + pt3.to_vector());
 	}
 
-	// `angle1` and `angle2` are clockwise from the X axis (of course).
+	// `angle1` and `angle2` are clockwise from the X axis (of course) and in radians.
 	// see arc and arct in
 	// http://www.adobe.com/products/postscript/pdfs/PLRM.pdf
+	//
+	// The arc goes from `angle1` to `angle2`, so it is drawn clockwise if `angle2` is greater and
+	// counterclockwise if it is smaller. Like PostScript's arc, if there is a current point, a
+	// line is added from it to the start of the arc. Otherwise, the arc starts a new subpath.
 	pub fn arc_to(&mut self, center_pt: Point2<f32>,
 	              x_radius: f32, y_radius: f32, // use a Size for these?
 	              angle1: f32, angle2: f32) {
+        let start_pt = center_pt + Vector2::new(x_radius * angle1.cos(), y_radius * angle1.sin());
+        match self.current_point() {
+            Some(pt) if pt.approx_eq(&start_pt) => {},
+            Some(_) => self.line_to(start_pt),
+            None => self.move_to(start_pt),
+        }
+        self.push_arc(center_pt, x_radius, y_radius, 0.0, angle1, angle2);
+	}
+
+	// Adds an arc segment that must start at the current point.
+	fn push_arc(&mut self, center_pt: Point2<f32>, x_radius: f32, y_radius: f32, rotation: f32,
+	            angle1: f32, angle2: f32) {
+        self.seg_types.push(PathSegmentType::Arc);
+        self.seg_data.push(center_pt.x);
+        self.seg_data.push(center_pt.y);
+        self.seg_data.push(x_radius);
+        self.seg_data.push(y_radius);
+        self.seg_data.push(rotation);
+        self.seg_data.push(angle1);
+        self.seg_data.push(angle2);
+        self.segments_changed();
 	}
 
+	// Adds an arc that is tangent to the line from the current point to `pt1` and to the line from
+	// `pt1` to `pt2`, like PostScript's arct. A line is added from the current point to the first
+	// tangent point, and the current point is left at the second tangent point. If the lines are
+	// collinear or a radius is zero, only a line to `pt1` is added.
 	pub fn tangent_arc_to(&mut self, pt1: Point2<f32>, pt2: Point2<f32>,
 	                      x_radius: f32, y_radius: f32) {
+        let pt0 = self.current_point().expect("tangent_arc_to requires a current point");
+        if x_radius == 0.0 || y_radius == 0.0 {
+            self.line_to(pt1);
+            return;
+        }
+        // Scale the y axis so that the ellipse becomes a circle, then find the arc tangent to
+        // both lines. Scaling back afterward keeps it tangent, and the angles stay the same.
+        let y_scale = x_radius / y_radius;
+        let to_circle = |pt: Point2<f32>| Point2::new(pt.x, pt.y * y_scale);
+        let (pt0, pt1, pt2) = (to_circle(pt0), to_circle(pt1), to_circle(pt2));
+
+        let u = pt0 - pt1;
+        let v = pt2 - pt1;
+        let cross = (pt1 - pt0).cross(&v).x;
+        if u.norm_squared() == 0.0 || v.norm_squared() == 0.0 || cross == 0.0 {
+            self.line_to(Point2::new(pt1.x, pt1.y / y_scale));
+            return;
+        }
+        let (u, v) = (u.normalize(), v.normalize());
+        // the angle between the two lines at `pt1`
+        let half_angle = u.dot(&v).max(-1.0).min(1.0).acos() * 0.5;
+        let tangent_dist = x_radius / half_angle.tan();
+        let center = pt1 + (u + v).normalize() * (x_radius / half_angle.sin());
+        let tangent_pt = pt1 + u * tangent_dist;
+
+        let angle1 = (tangent_pt.y - center.y).atan2(tangent_pt.x - center.x);
+        // A positive cross product means the path turns clockwise at `pt1`.
+        let sweep = (PI - half_angle * 2.0).copysign(cross);
+        self.arc_to(Point2::new(center.x, center.y / y_scale), x_radius, y_radius,
+                    angle1, angle1 + sweep);
+	}
+
+	// Adds an arc from the current point to `end_pt`, like SVG's elliptical arc command. The arc
+	// is part of an ellipse with its x axis rotated by `rotation`. Of the four arcs that fit,
+	// `large_arc` chooses one of the two longer ones, and `sweep` chooses one of the two drawn
+	// clockwise. Radii too small to reach `end_pt` are scaled up until they do, and if a radius is
+	// zero, a line is added instead.
+	// https://www.w3.org/TR/SVG11/implnote.html#ArcImplementationNotes
+	pub fn endpoint_arc_to(&mut self, x_radius: f32, y_radius: f32, rotation: f32,
+	                       large_arc: bool, sweep: bool, end_pt: Point2<f32>) {
+        let start_pt = self.current_point().expect("endpoint_arc_to requires a current point");
+        if start_pt == end_pt {
+            return;
+        }
+        let (mut rx, mut ry) = (x_radius.abs(), y_radius.abs());
+        if rx == 0.0 || ry == 0.0 {
+            self.line_to(end_pt);
+            return;
+        }
+
+        let (sin_rot, cos_rot) = rotation.sin_cos();
+        let half_diff = (start_pt - end_pt) * 0.5;
+        let x1 = cos_rot * half_diff.x + sin_rot * half_diff.y;
+        let y1 = -sin_rot * half_diff.x + cos_rot * half_diff.y;
+
+        // Scale up radii that are too small to reach from one point to the other.
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1.0 {
+            let scale = lambda.sqrt();
+            rx *= scale;
+            ry *= scale;
+        }
+
+        let (rx2, ry2) = (rx * rx, ry * ry);
+        let num = rx2 * ry2 - rx2 * y1 * y1 - ry2 * x1 * x1;
+        let denom = rx2 * y1 * y1 + ry2 * x1 * x1;
+        let mut coef = (num / denom).max(0.0).sqrt();
+        if large_arc == sweep {
+            coef = -coef;
+        }
+        let cx1 = coef * rx * y1 / ry;
+        let cy1 = -coef * ry * x1 / rx;
+        let mid_pt = start_pt + (end_pt - start_pt) * 0.5;
+        let center = mid_pt + Vector2::new(cos_rot * cx1 - sin_rot * cy1,
+                                           sin_rot * cx1 + cos_rot * cy1);
+
+        let angle_of = |v: Vector2<f32>| v.y.atan2(v.x);
+        let u = Vector2::new((x1 - cx1) / rx, (y1 - cy1) / ry);
+        let v = Vector2::new((-x1 - cx1) / rx, (-y1 - cy1) / ry);
+        let angle1 = angle_of(u);
+        let mut sweep_angle = (angle_of(v) - angle1) % (2.0 * PI);
+        if sweep && sweep_angle < 0.0 {
+            sweep_angle += 2.0 * PI;
+        } else if !sweep && sweep_angle > 0.0 {
+            sweep_angle -= 2.0 * PI;
+        }
+        self.push_arc(center, rx, ry, rotation, angle1, angle1 + sweep_angle);
 	}
 
 	pub fn close(&mut self) {
         self.seg_types.push(PathSegmentType::Close);
+        self.segments_changed();
 	}
 
+	// After a `close`, the current point is the start of the subpath that was closed, like
+	// PostScript's closepath.
 	pub fn current_point(&self) -> Option<Point2<f32>> {
 	    if self.seg_types.is_empty() {
 	        return None;
 	    }
 		let dlen = self.seg_data.len();
 	    match self.seg_types[self.seg_types.len() - 1] {
-	        PathSegmentType::Close => None,
+	        PathSegmentType::Close => self.subpath_start_point(),
 	        PathSegmentType::Arc => {
-				let mut center = Point2::new(self.seg_data[dlen - 6], self.seg_data[dlen - 5]);
-				// https://en.wikipedia.org/wiki/Ellipse#Equations
-				let angle2 = self.seg_data[dlen - 1];
-				center += Vector2::new(self.seg_data[dlen - 4] * angle2.cos(),
-				                       self.seg_data[dlen - 3] * angle2.sin());
-				Some(center)
+				let d = &self.seg_data[dlen - 7..];
+				let arc = ArcSegment::new(Point2::new(d[0], d[1]), d[2], d[3], d[4], d[5], d[6]);
+				Some(arc.end_point())
 			},
 	        _ => Some(Point2::new(self.seg_data[dlen - 2], self.seg_data[dlen - 1]))
 	    }
 	}
-	// pub fn flatten(float tolerance) -> Iterator {
-	// }
 
-	// pub fn from_svg_path_string(path_string: &str);
-	// pub fn from_postscript_path_string(path_string: &str);
+	// Returns the point of the last move, which is where the current subpath started.
+	fn subpath_start_point(&self) -> Option<Point2<f32>> {
+        let mut di = self.seg_data.len();
+        for ty in self.seg_types.iter().rev() {
+            di -= ty.data_len();
+            if *ty == PathSegmentType::Move {
+                return Some(Point2::new(self.seg_data[di], self.seg_data[di + 1]));
+            }
+        }
+        None
+	}
+	// Returns an iterator of moves, lines, and closes that approximate the path. See
+	// `Path::flatten`.
+	pub fn flatten(&self, tolerance: f32) -> Flatten<'_> {
+        self.as_path().flatten(tolerance)
+	}
 
 	// pub fn mask(&self) -> u32;
 	// pub fn set_mask(&mut self, mask: u32);
 
 }
 
+impl Extend<PathSegment> for PathBuf {
+    fn extend<I: IntoIterator<Item = PathSegment>>(&mut self, iter: I) {
+        for seg in iter {
+            match seg {
+                PathSegment::Move(pt) => self.move_to(pt),
+                PathSegment::Line(pt) => self.line_to(pt),
+                PathSegment::QuadCurve(pt1, pt2) => self.quad_curve_to(pt1, pt2),
+                PathSegment::CubicCurve(pt1, pt2, pt3) => self.cubic_curve_to(pt1, pt2, pt3),
+                PathSegment::Arc(arc) => {
+                    self.push_arc(arc.center_pt, arc.x_radius, arc.y_radius, arc.rotation,
+                                  arc.angle1, arc.angle2)
+                },
+                PathSegment::Close => self.close(),
+            }
+        }
+    }
+}
+
+impl FromIterator<PathSegment> for PathBuf {
+    fn from_iter<I: IntoIterator<Item = PathSegment>>(iter: I) -> Self {
+        let mut path = PathBuf::new();
+        path.extend(iter);
+        path
+    }
+}
+
 // Returns the point at which the two specified lines intersect. The first line passes
 // through `pt0` with the slope of `vec0`, and the second line passes through `pt1` with the
 // slope of `vec1`.
@@ -355,10 +816,11 @@ impl<'a> Iterator for PathIter<'a> {
 					center_pt: Point2::new(self.data[di], self.data[di + 1]),
 					x_radius: self.data[di + 2],
 					y_radius: self.data[di + 3],
-					angle1: self.data[di + 4],
-					angle2: self.data[di + 5],
+					rotation: self.data[di + 4],
+					angle1: self.data[di + 5],
+					angle2: self.data[di + 6],
 				});
-				self.data_index += 6;
+				self.data_index += 7;
 				s
 			},
 			PathSegmentType::Close => {
@@ -368,3 +830,166 @@ impl<'a> Iterator for PathIter<'a> {
 		})
 	}
 }
+
+#[test]
+fn test_builder() {
+    let mut path = PathBuf::new();
+    path.rel_move_to(Point2::new(10.0, 20.0));
+    path.rel_line_to(Point2::new(5.0, 0.0));
+    path.rel_quad_curve_to(Point2::new(5.0, 5.0), Point2::new(0.0, 10.0));
+    path.cubic_curve_to(Point2::new(10.0, 30.0), Point2::new(5.0, 35.0), Point2::new(0.0, 30.0));
+    path.rel_cubic_curve_to(Point2::new(-1.0, 0.0), Point2::new(-2.0, -1.0),
+                            Point2::new(-3.0, -2.0));
+    path.close();
+    assert_eq!(path.current_point(), Some(Point2::new(10.0, 20.0)));
+    path.rel_move_to(Point2::new(1.0, 1.0));
+    assert_eq!(path.current_point(), Some(Point2::new(11.0, 21.0)));
+
+    let segs: Vec<_> = path.path_iter().collect();
+    assert_eq!(segs, vec![
+        PathSegment::Move(Point2::new(10.0, 20.0)),
+        PathSegment::Line(Point2::new(15.0, 20.0)),
+        PathSegment::QuadCurve(Point2::new(20.0, 25.0), Point2::new(15.0, 30.0)),
+        PathSegment::CubicCurve(Point2::new(10.0, 30.0), Point2::new(5.0, 35.0),
+                                Point2::new(0.0, 30.0)),
+        PathSegment::CubicCurve(Point2::new(-1.0, 30.0), Point2::new(-2.0, 29.0),
+                                Point2::new(-3.0, 28.0)),
+        PathSegment::Close,
+        PathSegment::Move(Point2::new(11.0, 21.0)),
+    ]);
+}
+
+#[test]
+fn test_arc_to() {
+    let mut path = PathBuf::new();
+    path.arc_to(Point2::new(50.0, 50.0), 20.0, 10.0, 0.0, PI * 0.5);
+    assert_approx_eq!(path.current_point().unwrap(), Point2::new(50.0, 60.0));
+    // a line is added to the start of the second arc
+    path.arc_to(Point2::new(0.0, 0.0), 10.0, 10.0, PI, 0.0);
+    assert_approx_eq!(path.current_point().unwrap(), Point2::new(10.0, 0.0));
+
+    let segs: Vec<_> = path.path_iter().collect();
+    assert_eq!(segs.len(), 4);
+    assert_eq!(segs[0], PathSegment::Move(Point2::new(70.0, 50.0)));
+    match segs[2] {
+        PathSegment::Line(pt) => assert_approx_eq!(pt, Point2::new(-10.0, 0.0)),
+        _ => panic!("expected a line"),
+    }
+    match segs[3] {
+        PathSegment::Arc(arc) => assert_eq!((arc.angle1, arc.angle2), (PI, 0.0)),
+        _ => panic!("expected an arc"),
+    }
+}
+
+#[test]
+fn test_arc_to_cubics() {
+    use self::curve::Curve;
+
+    let arc = ArcSegment::new(Point2::new(0.0, 0.0), 100.0, 100.0, 0.0, 0.0, PI * 2.0);
+    assert_eq!(arc.to_cubics(f32::INFINITY).len(), 4);
+    let mut prev_count = 0;
+    for &tolerance in &[1.0, 0.1, 0.01, 0.001] {
+        let cubics = arc.to_cubics(tolerance);
+        assert!(cubics.len() >= prev_count);
+        prev_count = cubics.len();
+        for bez in &cubics {
+            let curve = Curve::Cubic(*bez);
+            for i in 0..=20 {
+                let dist = (curve.point_at(i as f32 / 20.0).to_vector().norm() - 100.0).abs();
+                assert!(dist <= tolerance);
+            }
+        }
+    }
+    assert!(arc.to_cubics(0.001).len() > arc.to_cubics(0.1).len());
+
+    // The curves are joined end to end and end exactly where the arc does, in either direction.
+    let arc = ArcSegment::new(Point2::new(10.0, 20.0), 30.0, 15.0, 0.4, PI * 1.5, -0.5);
+    let cubics = arc.to_cubics(0.01);
+    assert_eq!(cubics[0].p0, arc.start_point());
+    for pair in cubics.windows(2) {
+        assert_eq!(pair[0].p3, pair[1].p0);
+    }
+    assert_eq!(cubics[cubics.len() - 1].p3, arc.end_point());
+    for bez in &cubics {
+        let curve = Curve::Cubic(*bez);
+        for i in 0..=20 {
+            // Undo the rotation and scale the ellipse to a unit circle.
+            let v = curve.point_at(i as f32 / 20.0) - arc.center_pt;
+            let (sin_rot, cos_rot) = (-0.4f32).sin_cos();
+            let v = Vector2::new((cos_rot * v.x - sin_rot * v.y) / 30.0,
+                                 (sin_rot * v.x + cos_rot * v.y) / 15.0);
+            assert_approx_eq_eps!(v.norm(), 1.0, 0.01 / 15.0);
+        }
+    }
+}
+
+#[test]
+fn test_tangent_arc_to() {
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.tangent_arc_to(Point2::new(100.0, 0.0), Point2::new(100.0, 100.0), 10.0, 10.0);
+    assert_approx_eq!(path.current_point().unwrap(), Point2::new(100.0, 10.0));
+
+    let segs: Vec<_> = path.path_iter().collect();
+    assert_eq!(segs.len(), 3);
+    match segs[1] {
+        PathSegment::Line(pt) => assert_approx_eq!(pt, Point2::new(90.0, 0.0)),
+        _ => panic!("expected a line"),
+    }
+    match segs[2] {
+        PathSegment::Arc(arc) => {
+            assert_approx_eq!(arc.center_pt, Point2::new(90.0, 10.0));
+            assert_approx_eq!(arc.angle1, -PI * 0.5);
+            assert_approx_eq!(arc.angle2, 0.0);
+        },
+        _ => panic!("expected an arc"),
+    }
+
+    // turning the other way with an elliptical arc
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.tangent_arc_to(Point2::new(100.0, 0.0), Point2::new(100.0, -100.0), 10.0, 5.0);
+    assert_approx_eq!(path.current_point().unwrap(), Point2::new(100.0, -5.0));
+
+    // collinear lines only add a line
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.tangent_arc_to(Point2::new(10.0, 0.0), Point2::new(20.0, 0.0), 10.0, 10.0);
+    assert_eq!(path.current_point(), Some(Point2::new(10.0, 0.0)));
+}
+
+#[test]
+fn test_endpoint_arc_to() {
+    // a rotated ellipse through both points, with the center the large arc and sweep choose
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.endpoint_arc_to(20.0, 10.0, PI / 6.0, true, false, Point2::new(10.0, 10.0));
+    let arc = match path.path_iter().nth(1).unwrap() {
+        PathSegment::Arc(arc) => arc,
+        _ => panic!("expected an arc"),
+    };
+    assert_approx_eq!(arc.rotation, PI / 6.0);
+    assert_approx_eq_eps!(arc.start_point(), Point2::new(0.0, 0.0), 1e-4);
+    assert_approx_eq_eps!(arc.end_point(), Point2::new(10.0, 10.0), 1e-4);
+    assert!(arc.angle2 - arc.angle1 < -PI);
+
+    // Radii that are too small are scaled up so the arc is half of the ellipse.
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.endpoint_arc_to(5.0, 5.0, 1.0, false, true, Point2::new(0.0, 30.0));
+    match path.path_iter().nth(1).unwrap() {
+        PathSegment::Arc(arc) => {
+            assert_approx_eq_eps!(arc.center_pt, Point2::new(0.0, 15.0), 1e-2);
+            assert_approx_eq!(arc.x_radius, 15.0);
+            assert_approx_eq_eps!(arc.angle2 - arc.angle1, PI, 1e-3);
+        },
+        _ => panic!("expected an arc"),
+    }
+
+    // A zero radius gives a line, and an arc to the current point is left out.
+    path.endpoint_arc_to(0.0, 5.0, 0.0, false, true, Point2::new(10.0, 30.0));
+    path.endpoint_arc_to(5.0, 5.0, 0.0, false, true, Point2::new(10.0, 30.0));
+    let segs: Vec<_> = path.path_iter().collect();
+    assert_eq!(segs.len(), 3);
+    assert_eq!(segs[2], PathSegment::Line(Point2::new(10.0, 30.0)));
+}
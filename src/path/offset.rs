@@ -0,0 +1,123 @@
+use crate::painter::AsPathIter;
+use super::{BooleanOp, FillRule, LineJoin, Path, PathBuf, PathSegment, StrokeStyle};
+
+impl<'a> Path<'a> {
+    // Returns a path filling the area within `distance` of the area this path fills with the
+    // nonzero rule if `distance` is positive, or the area farther than `-distance` inside it if
+    // it's negative. Subpaths are treated as closed, like when filling. `line_join` is used at
+    // the corners that are pushed outward, and curves are offset to within `tolerance`.
+    //
+    // The result comes from combining the area with its stroke outline, so parts that would
+    // overlap or turn inside out where an inset is wider than the area are removed. Like
+    // `boolean_op()`, the subpaths don't overlap and are clockwise when y points down.
+    pub fn offset(&self, distance: f32, line_join: LineJoin, tolerance: f32) -> PathBuf {
+        let closed = closed_subpaths(self);
+        let closed = closed.as_path();
+        if distance == 0.0 {
+            return closed.boolean_op(FillRule::NonZero, &PathBuf::new().as_path(),
+                                     FillRule::NonZero, BooleanOp::Union);
+        }
+        let mut style = StrokeStyle::with_width(distance.abs() * 2.0);
+        style.line_join = line_join;
+        let outline = closed.stroke_outline(&style, tolerance);
+        let op = if distance > 0.0 { BooleanOp::Union } else { BooleanOp::Difference };
+        closed.boolean_op(FillRule::NonZero, &outline.as_path(), FillRule::NonZero, op)
+    }
+}
+
+// Returns a copy of the path with every subpath closed.
+fn closed_subpaths(path: &Path) -> PathBuf {
+    let mut closed = PathBuf::new();
+    let mut is_open = false;
+    for seg in path.path_iter() {
+        match seg {
+            PathSegment::Move(_) if is_open => closed.close(),
+            _ => {},
+        }
+        is_open = match seg {
+            PathSegment::Move(_) | PathSegment::Close => false,
+            _ => true,
+        };
+        closed.extend(Some(seg));
+    }
+    if is_open {
+        closed.close();
+    }
+    closed
+}
+
+#[test]
+fn test_offset() {
+    use nalgebra::ApproxEq;
+    use crate::{Point2, Rect};
+
+    let square = PathBuf::rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+    let square = square.as_path();
+    let mask = FillRule::NonZero.mask();
+
+    let outset = square.offset(10.0, LineJoin::Miter(4.0), 0.1);
+    assert_approx_eq_eps!(outset.as_path().bounding_box(), Rect::new(-10.0, -10.0, 120.0, 120.0),
+                          1e-3);
+    assert!(outset.as_path().is_point_in_fill(Point2::new(-9.0, -9.0), mask));
+    let outset = square.offset(10.0, LineJoin::Round, 0.1);
+    assert_approx_eq_eps!(outset.as_path().bounding_box(), Rect::new(-10.0, -10.0, 120.0, 120.0),
+                          1e-3);
+    assert!(!outset.as_path().is_point_in_fill(Point2::new(-9.0, -9.0), mask));
+    assert!(outset.as_path().is_point_in_fill(Point2::new(-6.0, -6.0), mask));
+    assert!(outset.as_path().is_point_in_fill(Point2::new(50.0, 50.0), mask));
+
+    let inset = square.offset(-10.0, LineJoin::Round, 0.1);
+    assert_approx_eq_eps!(inset.as_path().bounding_box(), Rect::new(10.0, 10.0, 80.0, 80.0),
+                          1e-3);
+    // Insetting by more than half the width leaves nothing.
+    assert_eq!(square.offset(-60.0, LineJoin::Round, 0.1).path_iter().count(), 0);
+
+    // An open subpath is closed first.
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(100.0, 0.0));
+    path.line_to(Point2::new(100.0, 100.0));
+    let outset = path.as_path().offset(5.0, LineJoin::Bevel, 0.1);
+    assert!(outset.as_path().is_point_in_fill(Point2::new(60.0, 40.0), mask));
+    assert!(outset.as_path().is_point_in_fill(Point2::new(47.0, 53.0), mask));
+    assert!(!outset.as_path().is_point_in_fill(Point2::new(45.0, 57.0), mask));
+}
+
+#[test]
+fn test_offset_curves() {
+    use crate::{Point2, Rect};
+
+    let circle = PathBuf::circle(Point2::new(0.0, 0.0), 50.0);
+    let mask = FillRule::NonZero.mask();
+    for &distance in &[10.0, -10.0] {
+        let offset = circle.as_path().offset(distance, LineJoin::Round, 0.01);
+        let offset = offset.as_path();
+        for i in 0..16 {
+            let angle = i as f32 * 0.4;
+            let dir = Point2::new(angle.cos(), angle.sin()).to_vector();
+            let radius = 50.0 + distance;
+            assert!(offset.is_point_in_fill(Point2::new(0.0, 0.0) + dir * (radius - 0.1), mask));
+            assert!(!offset.is_point_in_fill(Point2::new(0.0, 0.0) + dir * (radius + 0.1), mask));
+        }
+        // The curves stay curves.
+        assert!(offset.path_iter().count() < 30);
+        let moves = offset.path_iter().filter(|seg| match *seg {
+            PathSegment::Move(_) => true,
+            _ => false,
+        });
+        assert_eq!(moves.count(), 1);
+    }
+
+    // Two squares joined by a narrow bridge come apart when inset by more than half its width.
+    let mut path = PathBuf::rect(Rect::new(0.0, 0.0, 40.0, 40.0));
+    path.extend(PathBuf::rect(Rect::new(40.0, 15.0, 20.0, 10.0)).path_iter());
+    path.extend(PathBuf::rect(Rect::new(60.0, 0.0, 40.0, 40.0)).path_iter());
+    let inset = path.as_path().offset(-6.0, LineJoin::Miter(4.0), 0.1);
+    let moves = inset.path_iter().filter(|seg| match *seg {
+        PathSegment::Move(_) => true,
+        _ => false,
+    });
+    assert_eq!(moves.count(), 2);
+    assert!(!inset.as_path().is_point_in_fill(Point2::new(50.0, 20.0), mask));
+    assert!(inset.as_path().is_point_in_fill(Point2::new(20.0, 20.0), mask));
+}
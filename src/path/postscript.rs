@@ -0,0 +1,318 @@
+use nalgebra::Vector2;
+use smallvec::SmallVec;
+
+use crate::Point2;
+use super::{PathBuf, PathParseError, PathParseErrorKind, PathSegmentType};
+
+// PostScript operators are described in the PostScript Language Reference Manual:
+// http://www.adobe.com/products/postscript/pdfs/PLRM.pdf
+// and the PDF operators in section 8.5.2 of the PDF 1.7 reference.
+
+fn is_whitespace(c: u8) -> bool {
+    match c {
+        b' ' | b'\t' | b'\r' | b'\n' | b'\x0C' | b'\0' => true,
+        _ => false,
+    }
+}
+
+fn is_delimiter(c: u8) -> bool {
+    match c {
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%' => true,
+        _ => false,
+    }
+}
+
+// Splits the string into tokens, skipping whitespace and comments. Returns the offset of each
+// token along with it.
+struct Tokenizer<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<(usize, &'a str)> {
+        let bytes = self.s.as_bytes();
+        loop {
+            while self.pos < bytes.len() && is_whitespace(bytes[self.pos]) {
+                self.pos += 1;
+            }
+            if self.pos < bytes.len() && bytes[self.pos] == b'%' {
+                while self.pos < bytes.len() && bytes[self.pos] != b'\n' && bytes[self.pos] != b'\r' {
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+        if self.pos == bytes.len() {
+            return None;
+        }
+        let start = self.pos;
+        if is_delimiter(bytes[self.pos]) {
+            self.pos += 1;
+        } else {
+            while self.pos < bytes.len() && !is_whitespace(bytes[self.pos])
+                    && !is_delimiter(bytes[self.pos]) {
+                self.pos += 1;
+            }
+        }
+        Some((start, &self.s[start..self.pos]))
+    }
+}
+
+// Parses a PostScript number, which may be an integer, a real, or a radix number like 16#FF.
+// Returns `None` if the token is an operator. PDF numbers are a subset of PostScript's.
+fn parse_number(token: &str, offset: usize) -> Result<Option<f32>, PathParseError> {
+    let first = token.as_bytes()[0];
+    if !(first.is_ascii_digit() || first == b'-' || first == b'+' || first == b'.') {
+        return Ok(None);
+    }
+    let error = PathParseError::new(PathParseErrorKind::ExpectedNumber, offset);
+    if let Some(hash_index) = token.find('#') {
+        let radix = token[..hash_index].parse::<u32>().map_err(|_| error)?;
+        if radix < 2 || radix > 36 {
+            return Err(error);
+        }
+        let num = u32::from_str_radix(&token[hash_index + 1..], radix).map_err(|_| error)?;
+        return Ok(Some(num as f32));
+    }
+    // Rust's float syntax is close enough to PostScript's, but it also accepts "inf" and "NaN".
+    if token.bytes().any(|c| c.is_ascii_alphabetic() && c != b'e' && c != b'E') {
+        return Err(error);
+    }
+    token.parse::<f32>().map(Some).map_err(|_| error)
+}
+
+// Normalizes the angles for PostScript's arc, which always goes counterclockwise in PostScript
+// coordinates (that is, toward larger angles), and arcn, which goes the other way.
+fn arc_angles(angle1: f32, mut angle2: f32, negative: bool) -> (f32, f32) {
+    if negative {
+        while angle2 > angle1 {
+            angle2 -= 360.0;
+        }
+    } else {
+        while angle2 < angle1 {
+            angle2 += 360.0;
+        }
+    }
+    (angle1.to_radians(), angle2.to_radians())
+}
+
+// Closes the current subpath unless it is already closed or there isn't one, like closepath.
+fn close_subpath(path: &mut PathBuf) {
+    match path.seg_types.last() {
+        Some(PathSegmentType::Close) | None => {},
+        Some(_) => path.close(),
+    }
+}
+
+impl PathBuf {
+    // Parses PostScript path construction operators (moveto, rmoveto, lineto, rlineto, curveto,
+    // rcurveto, arc, arcn, arct, and closepath) and the path operators from PDF content streams
+    // (m, l, c, v, y, h, and re).
+    //
+    // Coordinates are used as they are, so the path is upside down compared to the PostScript or
+    // PDF page, where the y axis points up. Painting and clipping operators (like stroke, fill, S,
+    // f, and n) and newpath are accepted so that the paths of a whole drawing can be loaded, and
+    // those paths are concatenated into the one returned. Like in PostScript and PDF, painting a
+    // path or newpath leaves no current point, so the next path has to start with a moveto. Any
+    // other operator is an error.
+    pub fn from_postscript_path_string(path_string: &str) -> Result<PathBuf, PathParseError> {
+        let mut path = PathBuf::new();
+        let mut operands: SmallVec<[f32; 8]> = SmallVec::new();
+        let mut operands_offset = 0;
+        // Set after a path is painted, when `path` still has the current point of the last one
+        let mut path_ended = false;
+
+        for (offset, token) in (Tokenizer { s: path_string, pos: 0 }) {
+            if let Some(num) = parse_number(token, offset)? {
+                if operands.is_empty() {
+                    operands_offset = offset;
+                }
+                operands.push(num);
+                continue;
+            }
+
+            let operand_count = match token {
+                "moveto" | "rmoveto" | "lineto" | "rlineto" | "m" | "l" => 2,
+                "curveto" | "rcurveto" | "c" => 6,
+                "v" | "y" | "re" => 4,
+                "arc" | "arcn" | "arct" => 5,
+                "closepath" | "h" | "newpath" | "stroke" | "fill" | "eofill" | "clip" | "eoclip"
+                    | "S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" | "n" | "W" | "W*" => 0,
+                _ => return Err(PathParseError::new(PathParseErrorKind::UnknownOperator, offset)),
+            };
+            if operands.len() != operand_count {
+                return Err(PathParseError::new(PathParseErrorKind::WrongOperandCount, offset));
+            }
+            let ops = &operands;
+            let pt = |i: usize| Point2::new(ops[i], ops[i + 1]);
+
+            let current_pt = if path_ended { None } else { path.current_point() };
+            let needs_current_pt = match token {
+                "moveto" | "m" | "re" | "arc" | "arcn" => false,
+                // Closing or painting an empty path does nothing.
+                _ if operand_count == 0 => false,
+                _ => true,
+            };
+            if needs_current_pt && current_pt.is_none() {
+                return Err(PathParseError::new(PathParseErrorKind::NoCurrentPoint, offset));
+            }
+
+            match token {
+                "moveto" | "m" => path.move_to(pt(0)),
+                "rmoveto" => path.rel_move_to(pt(0)),
+                "lineto" | "l" => path.line_to(pt(0)),
+                "rlineto" => path.rel_line_to(pt(0)),
+                "curveto" | "c" => path.cubic_curve_to(pt(0), pt(2), pt(4)),
+                "rcurveto" => path.rel_cubic_curve_to(pt(0), pt(2), pt(4)),
+                "v" => path.cubic_curve_to(current_pt.unwrap(), pt(0), pt(2)),
+                "y" => path.cubic_curve_to(pt(0), pt(2), pt(2)),
+                "re" => {
+                    let (x, y, width, height) = (ops[0], ops[1], ops[2], ops[3]);
+                    path.move_to(Point2::new(x, y));
+                    path.line_to(Point2::new(x + width, y));
+                    path.line_to(Point2::new(x + width, y + height));
+                    path.line_to(Point2::new(x, y + height));
+                    path.close();
+                },
+                "arc" | "arcn" => {
+                    let (angle1, angle2) = arc_angles(ops[3], ops[4], token == "arcn");
+                    // `arc_to()` would add a line from the end of a path that was painted.
+                    if path_ended {
+                        path.move_to(pt(0) + Vector2::new(ops[2] * angle1.cos(),
+                                                          ops[2] * angle1.sin()));
+                    }
+                    path.arc_to(pt(0), ops[2], ops[2], angle1, angle2);
+                },
+                "arct" => path.tangent_arc_to(pt(0), pt(2), ops[4], ops[4]),
+                // The painting operators s, b, and b* close the path before painting it.
+                "closepath" | "h" | "s" | "b" | "b*" if !path_ended => close_subpath(&mut path),
+                _ => {},
+            }
+            path_ended = match token {
+                "newpath" | "stroke" | "fill" | "eofill" | "S" | "s" | "f" | "F" | "f*" | "B"
+                    | "B*" | "b" | "b*" | "n" => true,
+                // Clipping uses the path without ending it, and closing keeps it ended.
+                _ if operand_count == 0 => path_ended,
+                _ => false,
+            };
+            operands.clear();
+        }
+
+        if !operands.is_empty() {
+            return Err(PathParseError::new(PathParseErrorKind::ExpectedCommand, operands_offset));
+        }
+        Ok(path)
+    }
+}
+
+#[test]
+fn test_from_postscript_path_string() {
+    use std::f32::consts::PI;
+    use nalgebra::ApproxEq;
+    use super::{ArcSegment, PathSegment};
+
+    let path = PathBuf::from_postscript_path_string("
+        % a comment
+        newpath
+        10 20 moveto 5 -5 rlineto
+        1 2 3 4 5 6 curveto 1 1 2 2 3 3 rcurveto
+        closepath
+        16#10 0 rmoveto
+        0 0 10 0 90 arc
+        stroke
+    ").unwrap();
+    let segs: Vec<_> = path.path_iter().collect();
+    assert_eq!(&segs[..6], &[
+        PathSegment::Move(Point2::new(10.0, 20.0)),
+        PathSegment::Line(Point2::new(15.0, 15.0)),
+        PathSegment::CubicCurve(Point2::new(1.0, 2.0), Point2::new(3.0, 4.0),
+                                Point2::new(5.0, 6.0)),
+        PathSegment::CubicCurve(Point2::new(6.0, 7.0), Point2::new(7.0, 8.0),
+                                Point2::new(8.0, 9.0)),
+        PathSegment::Close,
+        PathSegment::Move(Point2::new(26.0, 20.0)),
+    ]);
+    assert_eq!(segs[6], PathSegment::Line(Point2::new(10.0, 0.0)));
+    assert_eq!(segs[7], PathSegment::Arc(ArcSegment {
+        center_pt: Point2::new(0.0, 0.0),
+        x_radius: 10.0,
+        y_radius: 10.0,
+        rotation: 0.0,
+        angle1: 0.0,
+        angle2: PI * 0.5,
+    }));
+
+    // arcn goes toward smaller angles
+    let path = PathBuf::from_postscript_path_string("0 0 10 90 0 arcn").unwrap();
+    match path.path_iter().nth(1).unwrap() {
+        PathSegment::Arc(arc) => {
+            assert_approx_eq!(arc.angle1, PI * 0.5);
+            assert_approx_eq!(arc.angle2, 0.0);
+        },
+        _ => panic!("expected an arc"),
+    }
+    let path = PathBuf::from_postscript_path_string("0 0 10 0 -90 arc").unwrap();
+    match path.path_iter().nth(1).unwrap() {
+        PathSegment::Arc(arc) => assert_approx_eq!(arc.angle2, PI * 1.5),
+        _ => panic!("expected an arc"),
+    }
+
+    let path = PathBuf::from_postscript_path_string("0 0 moveto 100 0 100 100 10 arct").unwrap();
+    assert_approx_eq!(path.current_point().unwrap(), Point2::new(100.0, 10.0));
+}
+
+#[test]
+fn test_from_postscript_path_string_pdf() {
+    use super::PathSegment;
+
+    let path = PathBuf::from_postscript_path_string(
+        "10 20 m 30 20 l 1 2 3 4 v 5 6 7 8 y h S 0 0 5 5 re f").unwrap();
+    let segs: Vec<_> = path.path_iter().collect();
+    assert_eq!(segs, vec![
+        PathSegment::Move(Point2::new(10.0, 20.0)),
+        PathSegment::Line(Point2::new(30.0, 20.0)),
+        PathSegment::CubicCurve(Point2::new(30.0, 20.0), Point2::new(1.0, 2.0),
+                                Point2::new(3.0, 4.0)),
+        PathSegment::CubicCurve(Point2::new(5.0, 6.0), Point2::new(7.0, 8.0),
+                                Point2::new(7.0, 8.0)),
+        PathSegment::Close,
+        PathSegment::Move(Point2::new(0.0, 0.0)),
+        PathSegment::Line(Point2::new(5.0, 0.0)),
+        PathSegment::Line(Point2::new(5.0, 5.0)),
+        PathSegment::Line(Point2::new(0.0, 5.0)),
+        PathSegment::Close,
+    ]);
+
+    // After a path is painted, closing does nothing and an arc starts a new subpath.
+    let path = PathBuf::from_postscript_path_string("0 0 m 5 0 l S h 0 0 10 0 90 arc").unwrap();
+    let segs: Vec<_> = path.path_iter().collect();
+    assert_eq!(&segs[..3], &[
+        PathSegment::Move(Point2::new(0.0, 0.0)),
+        PathSegment::Line(Point2::new(5.0, 0.0)),
+        PathSegment::Move(Point2::new(10.0, 0.0)),
+    ]);
+    assert!(matches!(segs[3], PathSegment::Arc(_)));
+    assert_eq!(segs.len(), 4);
+}
+
+#[test]
+fn test_from_postscript_path_string_errors() {
+    let err = |s: &str| PathBuf::from_postscript_path_string(s).err().unwrap();
+    assert_eq!(err("10 10 lineto"), PathParseError::new(PathParseErrorKind::NoCurrentPoint, 6));
+    assert_eq!(err("0 0 moveto 5 lineto"),
+               PathParseError::new(PathParseErrorKind::WrongOperandCount, 13));
+    assert_eq!(err("0 0 moveto 1 1 gsave"),
+               PathParseError::new(PathParseErrorKind::UnknownOperator, 15));
+    assert_eq!(err("0 0 moveto 1.2.3 1 lineto"),
+               PathParseError::new(PathParseErrorKind::ExpectedNumber, 11));
+    assert_eq!(err("0 0 moveto 5 5"), PathParseError::new(PathParseErrorKind::ExpectedCommand, 11));
+    // Painting a path leaves no current point.
+    assert_eq!(err("0 0 m 10 0 l S 5 5 l"),
+               PathParseError::new(PathParseErrorKind::NoCurrentPoint, 19));
+    assert_eq!(err("0 0 moveto 10 0 lineto stroke 5 5 rlineto"),
+               PathParseError::new(PathParseErrorKind::NoCurrentPoint, 34));
+}
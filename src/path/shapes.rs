@@ -0,0 +1,198 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use nalgebra::Vector2;
+
+use crate::{CornerRadii2, Point2, Rect};
+use super::PathBuf;
+
+// All the shapes are closed subpaths drawn clockwise (with the y axis pointing down), starting at
+// the top.
+impl PathBuf {
+    pub fn rect(rect: Rect<f32>) -> PathBuf {
+        let rect = rect.normalize();
+        let mut path = PathBuf::new();
+        path.move_to(rect.top_left());
+        path.line_to(rect.top_right());
+        path.line_to(rect.bottom_right());
+        path.line_to(rect.bottom_left());
+        path.close();
+        path
+    }
+
+    // Like CSS's border-radius, if the radii of two corners add up to more than the length of the
+    // side between them, all the radii are scaled down until they fit. Negative radii are treated
+    // as zero.
+    pub fn rounded_rect(rect: Rect<f32>, radii: CornerRadii2<f32>) -> PathBuf {
+        let rect = rect.normalize();
+        let mut tl = radii.top_left.max(0.0);
+        let mut tr = radii.top_right.max(0.0);
+        let mut br = radii.bottom_right.max(0.0);
+        let mut bl = radii.bottom_left.max(0.0);
+        let scale = [(rect.width, tl + tr), (rect.height, tr + br),
+                     (rect.width, br + bl), (rect.height, bl + tl)]
+            .iter()
+            .filter(|&&(_, sum)| sum > 0.0)
+            .fold(1.0f32, |scale, &(length, sum)| scale.min(length / sum));
+        tl *= scale;
+        tr *= scale;
+        br *= scale;
+        bl *= scale;
+
+        let mut path = PathBuf::new();
+        path.move_to(Point2::new(rect.x + tl, rect.y));
+        // The corners go clockwise, each starting where the one before it ended.
+        let corners = [
+            (rect.top_right(), tr, Vector2::new(-1.0, 1.0), -FRAC_PI_2),
+            (rect.bottom_right(), br, Vector2::new(-1.0, -1.0), 0.0),
+            (rect.bottom_left(), bl, Vector2::new(1.0, -1.0), FRAC_PI_2),
+            (rect.top_left(), tl, Vector2::new(1.0, 1.0), PI),
+        ];
+        for &(corner_pt, radius, to_center, angle) in &corners {
+            if radius > 0.0 {
+                path.arc_to(corner_pt + to_center * radius, radius, radius, angle,
+                            angle + FRAC_PI_2);
+            } else {
+                path.line_to(corner_pt);
+            }
+        }
+        path.close();
+        path
+    }
+
+    // Returns the ellipse that fits in the rectangle.
+    pub fn ellipse(rect: Rect<f32>) -> PathBuf {
+        let rect = rect.normalize();
+        let center_pt = Point2::new(rect.x + rect.width * 0.5, rect.y + rect.height * 0.5);
+        let mut path = PathBuf::new();
+        path.arc_to(center_pt, rect.width * 0.5, rect.height * 0.5, -FRAC_PI_2, PI * 1.5);
+        path.close();
+        path
+    }
+
+    pub fn circle(center_pt: Point2<f32>, radius: f32) -> PathBuf {
+        let mut path = PathBuf::new();
+        path.arc_to(center_pt, radius, radius, -FRAC_PI_2, PI * 1.5);
+        path.close();
+        path
+    }
+
+    // Returns a polygon with its vertices evenly spaced on the circle, with one at the top.
+    pub fn regular_polygon(center_pt: Point2<f32>, radius: f32, side_count: u32) -> PathBuf {
+        assert!(side_count >= 3, "a polygon needs at least 3 sides");
+        let mut path = PathBuf::new();
+        for i in 0..side_count {
+            let angle = -FRAC_PI_2 + PI * 2.0 * (i as f32 / side_count as f32);
+            let pt = center_pt + Vector2::new(angle.cos(), angle.sin()) * radius;
+            if i == 0 {
+                path.move_to(pt);
+            } else {
+                path.line_to(pt);
+            }
+        }
+        path.close();
+        path
+    }
+
+    // Returns a star with `point_count` points at `outer_radius` from the center, with one at the
+    // top, and the vertices between them at `inner_radius`.
+    pub fn star(center_pt: Point2<f32>, outer_radius: f32, inner_radius: f32,
+                point_count: u32) -> PathBuf {
+        assert!(point_count >= 2, "a star needs at least 2 points");
+        let mut path = PathBuf::new();
+        for i in 0..point_count * 2 {
+            let angle = -FRAC_PI_2 + PI * (i as f32 / point_count as f32);
+            let radius = if i % 2 == 0 { outer_radius } else { inner_radius };
+            let pt = center_pt + Vector2::new(angle.cos(), angle.sin()) * radius;
+            if i == 0 {
+                path.move_to(pt);
+            } else {
+                path.line_to(pt);
+            }
+        }
+        path.close();
+        path
+    }
+}
+
+#[test]
+fn test_rect() {
+    use super::PathSegment;
+
+    let path = PathBuf::rect(Rect::new(30.0, 20.0, -20.0, 10.0));
+    assert_eq!(path.path_iter().collect::<Vec<_>>(), [
+        PathSegment::Move(Point2::new(10.0, 20.0)),
+        PathSegment::Line(Point2::new(30.0, 20.0)),
+        PathSegment::Line(Point2::new(30.0, 30.0)),
+        PathSegment::Line(Point2::new(10.0, 30.0)),
+        PathSegment::Close,
+    ]);
+}
+
+#[test]
+fn test_rounded_rect() {
+    use nalgebra::ApproxEq;
+    use super::FillRule;
+
+    let rect = Rect::new(10.0, 20.0, 100.0, 50.0);
+    let path = PathBuf::rounded_rect(rect, CornerRadii2::new(10.0, 0.0, 20.0, 5.0));
+    let path = path.as_path();
+    assert_approx_eq!(path.bounding_box(), rect);
+    let mask = FillRule::NonZero.mask();
+    assert!(path.is_point_in_fill(Point2::new(12.0, 40.0), mask));
+    assert!(!path.is_point_in_fill(Point2::new(11.0, 21.0), mask));
+    assert!(path.is_point_in_fill(Point2::new(109.9, 20.1), mask));
+    assert!(!path.is_point_in_fill(Point2::new(108.0, 68.0), mask));
+    assert!(!path.is_point_in_fill(Point2::new(10.5, 69.5), mask));
+    assert!(path.is_point_in_fill(Point2::new(10.5, 65.0), mask));
+    assert_approx_eq_eps!(path.length(..), 300.0 - 35.0 * 2.0 + 35.0 * FRAC_PI_2, 1e-3);
+
+    // The radii are too big for the height, so they're scaled down to fit.
+    let path = PathBuf::rounded_rect(rect, CornerRadii2::uniform(50.0));
+    let path = path.as_path();
+    assert_approx_eq!(path.bounding_box(), rect);
+    assert_approx_eq_eps!(path.length(..), 100.0 + 25.0 * PI * 2.0, 1e-3);
+    assert!(!path.is_point_in_fill(Point2::new(12.0, 22.0), mask));
+}
+
+#[test]
+fn test_ellipse_and_circle() {
+    use nalgebra::ApproxEq;
+
+    let rect = Rect::new(10.0, 20.0, 100.0, 50.0);
+    let path = PathBuf::ellipse(rect);
+    assert_approx_eq!(path.as_path().bounding_box(), rect);
+    assert_approx_eq_eps!(path.current_point().unwrap(), Point2::new(60.0, 20.0), 1e-4);
+
+    let path = PathBuf::circle(Point2::new(10.0, 20.0), 5.0);
+    assert_approx_eq!(path.as_path().bounding_box(), Rect::new(5.0, 15.0, 10.0, 10.0));
+    assert_approx_eq_eps!(path.as_path().length(..), 10.0 * PI, 1e-3);
+}
+
+#[test]
+fn test_regular_polygon_and_star() {
+    use nalgebra::ApproxEq;
+    use crate::painter::AsPathIter;
+    use super::{FillRule, PathSegment};
+
+    let path = PathBuf::regular_polygon(Point2::new(0.0, 0.0), 10.0, 4);
+    let segs: Vec<_> = path.path_iter().collect();
+    assert_eq!(segs.len(), 5);
+    let pts = [(0.0, -10.0), (10.0, 0.0), (0.0, 10.0), (-10.0, 0.0)];
+    for (seg, &(x, y)) in segs.iter().zip(&pts) {
+        match *seg {
+            PathSegment::Move(pt) | PathSegment::Line(pt) => {
+                assert_approx_eq_eps!(pt, Point2::new(x, y), 1e-5)
+            },
+            _ => panic!("expected a move or line"),
+        }
+    }
+    assert_eq!(segs[4], PathSegment::Close);
+
+    let path = PathBuf::star(Point2::new(0.0, 0.0), 10.0, 4.0, 5);
+    let path = path.as_path();
+    assert_eq!(path.path_iter().count(), 11);
+    let mask = FillRule::NonZero.mask();
+    assert!(path.is_point_in_fill(Point2::new(0.0, -9.0), mask));
+    assert!(path.is_point_in_fill(Point2::new(0.0, 3.9), mask));
+    assert!(!path.is_point_in_fill(Point2::new(0.0, 4.1), mask));
+}
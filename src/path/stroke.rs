@@ -0,0 +1,452 @@
+use std::f32::consts::PI;
+
+use nalgebra::{origin, Dot, Norm, Vector2};
+
+use crate::{CubicBezier, Point2};
+use super::curve::Curve;
+use super::dash::dash_subpaths;
+use super::{LineCap, LineJoin, Path, PathBuf, StrokeStyle};
+
+// Limits how many times a curve is split in half while approximating its offset, since the offset
+// of a cusp can't be approximated well by any number of pieces.
+const MAX_DEPTH: u32 = 10;
+
+impl<'a> Path<'a> {
+    // Returns a path that covers the same area as stroking this path with `style` when it is
+    // filled with the nonzero fill rule. Every part of the outline winds the same way, so where
+    // the stroke overlaps itself, it is still filled. Lines and circular arcs are offset exactly,
+    // and the offsets of other curves are approximated with cubic curves that are within
+    // `tolerance` of them.
+    pub fn stroke_outline(&self, style: &StrokeStyle, tolerance: f32) -> PathBuf {
+        let mut stroker = Stroker {
+            style,
+            half_width: style.width * 0.5,
+            tolerance,
+            path: PathBuf::new(),
+            current_pt: origin(),
+        };
+        for mut subpath in dash_subpaths(self, &style.dash_pattern, style.dash_offset) {
+            if subpath.closed {
+                let end_pt = subpath.curves.last().map_or(subpath.start_pt, |c| c.end_point());
+                subpath.curves.push(Curve::Line(end_pt, subpath.start_pt));
+            }
+            let (initial_end_cap, terminal_end_cap) = style.end_caps();
+            let (initial_dash_cap, terminal_dash_cap) = style.dash_caps();
+            let start_cap = if subpath.dash_start { initial_dash_cap } else { initial_end_cap };
+            let end_cap = if subpath.dash_end { terminal_dash_cap } else { terminal_end_cap };
+            stroker.add_subpath(&subpath.curves, subpath.closed, subpath.start_pt, start_cap,
+                                end_cap);
+        }
+        stroker.path
+    }
+}
+
+// Builds the outline of each subpath from the offsets of its curves on both sides. The offset
+// is always taken on the side of `perpendicular()` of the direction of the curve, and the other
+// side is made by offsetting the reversed curves.
+struct Stroker<'s> {
+    style: &'s StrokeStyle,
+    half_width: f32,
+    tolerance: f32,
+    path: PathBuf,
+    current_pt: Point2<f32>,
+}
+
+impl<'s> Stroker<'s> {
+    fn add_subpath(&mut self, curves: &[Curve], closed: bool, start_pt: Point2<f32>,
+                   start_cap: LineCap, end_cap: LineCap) {
+        let half_width = self.half_width;
+        // Curves with no length have no direction for joins or caps.
+        let curves: Vec<Curve> = curves.iter()
+                                       .filter(|curve| curve.tangent_at(0.0).norm_squared() > 0.0)
+                                       .cloned()
+                                       .collect();
+        if curves.is_empty() {
+            // A zero length subpath is drawn as just its caps, which are aligned to the axes. It
+            // is outlined like a line of no length going to the right.
+            if start_cap != LineCap::Flat || end_cap != LineCap::Flat {
+                self.move_to(start_pt + Vector2::new(0.0, half_width));
+                self.add_cap(start_pt, Vector2::new(1.0, 0.0), end_cap);
+                self.add_cap(start_pt, Vector2::new(-1.0, 0.0), start_cap);
+                self.path.close();
+            }
+            return;
+        }
+
+        let reversed: Vec<Curve> = curves.iter().rev().map(|curve| curve.reversed()).collect();
+        let first = curves[0];
+        let last = curves[curves.len() - 1];
+        if closed {
+            // One side is outlined going forward and the other going backward, so the area
+            // between them is filled, and the area inside of both isn't.
+            for side in &[&curves, &reversed] {
+                let side_first = side[0];
+                let side_last = side[side.len() - 1];
+                self.move_to(side_first.point_at(0.0) +
+                             perpendicular(side_first.tangent_at(0.0)) * half_width);
+                self.add_side(side);
+                self.add_join(side_first.point_at(0.0), side_last.tangent_at(1.0),
+                              side_first.tangent_at(0.0));
+                self.path.close();
+            }
+        } else {
+            self.move_to(first.point_at(0.0) + perpendicular(first.tangent_at(0.0)) * half_width);
+            self.add_side(&curves);
+            self.add_cap(last.end_point(), last.tangent_at(1.0), end_cap);
+            self.add_side(&reversed);
+            self.add_cap(first.point_at(0.0), -first.tangent_at(0.0), start_cap);
+            self.path.close();
+        }
+    }
+
+    // Adds the offsets of the curves with joins between them. The current point must be at the
+    // start of the first curve's offset.
+    fn add_side(&mut self, curves: &[Curve]) {
+        for (i, curve) in curves.iter().enumerate() {
+            if i > 0 {
+                let prev = &curves[i - 1];
+                self.add_join(curve.point_at(0.0), prev.tangent_at(1.0), curve.tangent_at(0.0));
+            }
+            self.add_offset(curve);
+        }
+    }
+
+    fn add_offset(&mut self, curve: &Curve) {
+        let half_width = self.half_width;
+        match *curve {
+            Curve::Line(_, pt1) => {
+                let offset = perpendicular(curve.tangent_at(0.0)) * half_width;
+                self.line_to(pt1 + offset);
+            },
+            Curve::Arc(ref arc) if arc.x_radius == arc.y_radius => {
+                // The offset of a circular arc is an arc with the same center. The side being
+                // offset is toward the center if the angle is increasing.
+                let sweep_angle = arc.angle2 - arc.angle1;
+                let radius = arc.x_radius - half_width.copysign(sweep_angle);
+                if radius > 0.0 {
+                    self.path.push_arc(arc.center_pt, radius, radius, arc.rotation, arc.angle1,
+                                       arc.angle2);
+                    self.current_pt = curve.end_point() +
+                                      perpendicular(curve.tangent_at(1.0)) * half_width;
+                } else {
+                    // The offset crosses over to the other side of the center. Going around it
+                    // would wind the opposite way from the rest of the stroke, so go around it
+                    // backward between trips to the center instead.
+                    let end_pt = curve.end_point() +
+                                 perpendicular(curve.tangent_at(1.0)) * half_width;
+                    self.line_to(arc.center_pt);
+                    self.line_to(end_pt);
+                    if radius < 0.0 {
+                        self.path.push_arc(arc.center_pt, -radius, -radius, arc.rotation,
+                                           arc.angle2 + PI, arc.angle1 + PI);
+                        self.current_pt = self.path.current_point().unwrap();
+                        self.line_to(arc.center_pt);
+                        self.line_to(end_pt);
+                    }
+                }
+            },
+            _ => self.add_offset_recursive(curve, 0.0, 1.0, MAX_DEPTH),
+        }
+    }
+
+    // Approximates the offset of the curve from t0 to t1 with a cubic curve that has the same
+    // end points and directions and goes through the middle of the offset. If that isn't close
+    // enough, the range is split in half.
+    fn add_offset_recursive(&mut self, curve: &Curve, t0: f32, t1: f32, depth: u32) {
+        let half_width = self.half_width;
+        let offset_at = |t: f32| {
+            curve.point_at(t) + perpendicular(curve.tangent_at(t)) * half_width
+        };
+        let (pt0, pt3) = (offset_at(t0), offset_at(t1));
+        let (dir0, dir1) = (curve.tangent_at(t0), curve.tangent_at(t1));
+        let mid_pt = offset_at((t0 + t1) * 0.5);
+
+        // The middle of a cubic curve is (p0 + 3 * p1 + 3 * p2 + p3) / 8, which gives two
+        // equations for the lengths of the control point vectors.
+        let v = (mid_pt.to_vector() * 8.0 - pt0.to_vector() * 4.0 - pt3.to_vector() * 4.0) / 3.0;
+        let det = dir1.x * dir0.y - dir0.x * dir1.y;
+        let chord_length = (pt3 - pt0).norm();
+        let (mut length0, mut length1) = if det.abs() > 1e-3 {
+            ((dir1.x * v.y - v.x * dir1.y) / det, (dir0.x * v.y - dir0.y * v.x) / det)
+        } else {
+            (-1.0, -1.0)
+        };
+        if !(length0 >= 0.0 && length1 >= 0.0 &&
+             length0 <= chord_length * 2.0 && length1 <= chord_length * 2.0) {
+            // The directions are parallel or the curve bends too much for the equations to have
+            // a sensible answer.
+            length0 = chord_length / 3.0;
+            length1 = chord_length / 3.0;
+        }
+        let bez = CubicBezier::new(pt0, pt0 + dir0 * length0, pt3 - dir1 * length1, pt3);
+
+        let approx = Curve::Cubic(bez);
+        let is_close = [0.25, 0.5, 0.75].iter().all(|&s| {
+            let t = t0 + (t1 - t0) * s;
+            (approx.point_at(s) - offset_at(t)).norm() <= self.tolerance
+        });
+        if depth == 0 || is_close {
+            self.path.cubic_curve_to(bez.p1, bez.p2, bez.p3);
+            self.current_pt = pt3;
+            return;
+        }
+        let t_mid = (t0 + t1) * 0.5;
+        self.add_offset_recursive(curve, t0, t_mid, depth - 1);
+        self.add_offset_recursive(curve, t_mid, t1, depth - 1);
+    }
+
+    // Connects the offsets of two curves that meet at `vertex`. `dir0` and `dir1` are unit vectors
+    // in the direction of the curves coming into and going out of it.
+    fn add_join(&mut self, vertex: Point2<f32>, dir0: Vector2<f32>, dir1: Vector2<f32>) {
+        let half_width = self.half_width;
+        let normal0 = perpendicular(dir0);
+        let normal1 = perpendicular(dir1);
+        let end_pt = vertex + normal1 * half_width;
+        if (end_pt - self.current_pt).norm() <= self.tolerance {
+            // The curves are close enough to going the same direction that no join is needed.
+            self.line_to(end_pt);
+            return;
+        }
+        let cross = dir0.x * dir1.y - dir0.y * dir1.x;
+        let cos = normal0.dot(&normal1);
+        if cross > 0.0 {
+            // This is the inside of the corner. Going through the vertex keeps every part of the
+            // outline winding the same way, and that area is covered by the other side anyway.
+            self.line_to(vertex);
+            self.line_to(end_pt);
+            return;
+        }
+        match self.style.line_join {
+            LineJoin::Round => {
+                // If the curves double back, the join goes around the front of the first one.
+                let angle1 = normal0.y.atan2(normal0.x);
+                let sweep_angle = if cross == 0.0 { -PI } else { cross.atan2(cos) };
+                self.path.push_arc(vertex, half_width, half_width, 0.0, angle1,
+                                   angle1 + sweep_angle);
+                self.current_pt = end_pt;
+            },
+            LineJoin::None => {
+                // Like the inside of a corner, which leaves the notch unfilled.
+                self.line_to(vertex);
+                self.line_to(end_pt);
+            },
+            LineJoin::Bevel => self.line_to(end_pt),
+            join @ LineJoin::Miter(_) | join @ LineJoin::MiterRevert(_) |
+            join @ LineJoin::MiterTruncate(_) => {
+                for pt in join.miter_points(vertex, dir0, dir1, normal0, normal1, half_width) {
+                    self.line_to(pt);
+                }
+                self.line_to(end_pt);
+            },
+        }
+    }
+
+    // Goes around the end of a curve from its offset to the offset of the reversed curve. `dir`
+    // is a unit vector pointing away from the end of the curve.
+    fn add_cap(&mut self, end_pt: Point2<f32>, dir: Vector2<f32>, cap: LineCap) {
+        let half_width = self.half_width;
+        let normal = perpendicular(dir);
+        match cap {
+            LineCap::Flat => {},
+            LineCap::Square => {
+                self.line_to(end_pt + (dir + normal) * half_width);
+                self.line_to(end_pt + (dir - normal) * half_width);
+            },
+            LineCap::Round => {
+                let angle1 = normal.y.atan2(normal.x);
+                self.path.push_arc(end_pt, half_width, half_width, 0.0, angle1, angle1 - PI);
+                self.current_pt = end_pt - normal * half_width;
+            },
+            LineCap::Triangular => self.line_to(end_pt + dir * half_width),
+        }
+        self.line_to(end_pt - normal * half_width);
+    }
+
+    fn move_to(&mut self, pt: Point2<f32>) {
+        self.path.move_to(pt);
+        self.current_pt = pt;
+    }
+
+    // Lines with no length are left out.
+    fn line_to(&mut self, pt: Point2<f32>) {
+        if pt != self.current_pt {
+            self.path.line_to(pt);
+            self.current_pt = pt;
+        }
+    }
+}
+
+fn perpendicular(v: Vector2<f32>) -> Vector2<f32> {
+    Vector2::new(-v.y, v.x)
+}
+
+// Checks that filling the outline covers the same points as stroking the path, except near the
+// edge of the stroke, where the outline is allowed to be off by the tolerance.
+#[cfg(test)]
+fn check_stroke_outline(path: &Path, style: &StrokeStyle) {
+    use super::FillRule;
+
+    let tolerance = 0.05;
+    let outline = path.stroke_outline(style, tolerance);
+    let outline = outline.as_path();
+    let mut thinner = style.clone();
+    thinner.width -= tolerance * 4.0;
+    let mut thicker = style.clone();
+    thicker.width += tolerance * 4.0;
+    let bounds = path.stroke_bounds(&thicker);
+    for i in 0..=40 {
+        for j in 0..=40 {
+            let pt = Point2::new(bounds.x + bounds.width * (i as f32 / 40.0),
+                                 bounds.y + bounds.height * (j as f32 / 40.0));
+            let in_stroke = path.is_point_in_stroke(pt, &thinner, 0.01);
+            if in_stroke != path.is_point_in_stroke(pt, &thicker, 0.01) {
+                continue;
+            }
+            assert_eq!(outline.is_point_in_fill(pt, FillRule::NonZero.mask()), in_stroke,
+                       "point {:?} with {:?}", pt, style);
+        }
+    }
+}
+
+#[test]
+fn test_stroke_outline() {
+    use nalgebra::ApproxEq;
+    use super::PathSegment;
+
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(100.0, 0.0));
+    let style = StrokeStyle::with_width(10.0);
+    let outline = path.as_path().stroke_outline(&style, 0.1);
+    let segs: Vec<_> = outline.path_iter().collect();
+    assert_eq!(segs.len(), 6);
+    let pts = [(0.0, 5.0), (100.0, 5.0), (100.0, -5.0), (0.0, -5.0), (0.0, 5.0)];
+    for (seg, &(x, y)) in segs.iter().zip(&pts) {
+        match *seg {
+            PathSegment::Move(pt) | PathSegment::Line(pt) => {
+                assert_approx_eq!(pt, Point2::new(x, y))
+            },
+            _ => panic!("expected a move or line"),
+        }
+    }
+    assert_eq!(segs[5], PathSegment::Close);
+    // Each round cap is a single arc that ends where the other side starts.
+    let mut style = StrokeStyle::with_width(10.0);
+    style.line_cap = LineCap::Round;
+    let outline = path.as_path().stroke_outline(&style, 0.1);
+    let segs: Vec<_> = outline.path_iter().collect();
+    assert_eq!(segs.len(), 6);
+    match (segs[2], segs[4]) {
+        (PathSegment::Arc(arc0), PathSegment::Arc(arc1)) => {
+            assert_approx_eq!(arc0.end_point(), Point2::new(100.0, -5.0));
+            assert_approx_eq!(arc1.end_point(), Point2::new(0.0, 5.0));
+        },
+        segs => panic!("expected arcs but got {:?}", segs),
+    }
+
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(100.0, 0.0));
+    path.line_to(Point2::new(100.0, 100.0));
+    path.line_to(Point2::new(20.0, 50.0));
+    let path = path.as_path();
+    let mut style = StrokeStyle::with_width(20.0);
+    for &line_cap in &[LineCap::Flat, LineCap::Square, LineCap::Round, LineCap::Triangular] {
+        for &line_join in &[LineJoin::None, LineJoin::Bevel, LineJoin::Round,
+                            LineJoin::Miter(1.5), LineJoin::Miter(4.0),
+                            LineJoin::MiterRevert(1.5), LineJoin::MiterTruncate(1.5),
+                            LineJoin::MiterTruncate(4.0)] {
+            style.line_cap = line_cap;
+            style.line_join = line_join;
+            check_stroke_outline(&path, &style);
+        }
+    }
+    style.initial_end_cap = Some(LineCap::Round);
+    style.terminal_end_cap = Some(LineCap::Triangular);
+    check_stroke_outline(&path, &style);
+}
+
+#[test]
+fn test_stroke_outline_curves() {
+    use super::FillRule;
+
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.cubic_curve_to(Point2::new(0.0, 80.0), Point2::new(100.0, -40.0),
+                        Point2::new(100.0, 40.0));
+    path.quad_curve_to(Point2::new(150.0, 100.0), Point2::new(50.0, 100.0));
+    path.arc_to(Point2::new(20.0, 100.0), 30.0, 15.0, 0.0, PI);
+    path.close();
+    // a circle with a radius smaller than half the width
+    path.move_to(Point2::new(205.0, 0.0));
+    path.arc_to(Point2::new(200.0, 0.0), 5.0, 5.0, 0.0, PI * 2.0);
+    path.close();
+    let path = path.as_path();
+    let mut style = StrokeStyle::with_width(16.0);
+    style.line_join = LineJoin::Round;
+    check_stroke_outline(&path, &style);
+    style.line_cap = LineCap::Round;
+    style.line_join = LineJoin::Miter(10.0);
+    check_stroke_outline(&path, &style);
+
+    // a curve that bends more tightly than half the width
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.cubic_curve_to(Point2::new(100.0, 0.0), Point2::new(100.0, 10.0), Point2::new(0.0, 10.0));
+    check_stroke_outline(&path.as_path(), &style);
+
+    // zero length subpaths
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(10.0, 10.0));
+    path.line_to(Point2::new(10.0, 10.0));
+    path.move_to(Point2::new(50.0, 10.0));
+    path.close();
+    let path = path.as_path();
+    style.line_cap = LineCap::Square;
+    let outline = path.stroke_outline(&style, 0.1);
+    let mask = FillRule::NonZero.mask();
+    assert!(outline.as_path().is_point_in_fill(Point2::new(17.0, 17.0), mask));
+    assert!(outline.as_path().is_point_in_fill(Point2::new(43.0, 3.0), mask));
+    style.line_cap = LineCap::Round;
+    let outline = path.stroke_outline(&style, 0.1);
+    assert!(!outline.as_path().is_point_in_fill(Point2::new(17.0, 17.0), mask));
+    assert!(outline.as_path().is_point_in_fill(Point2::new(17.0, 10.0), mask));
+    style.line_cap = LineCap::Flat;
+    assert!(path.stroke_outline(&style, 0.1).path_iter().next().is_none());
+}
+
+#[test]
+fn test_stroke_outline_dashes() {
+    use super::FillRule;
+
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(100.0, 0.0));
+    path.line_to(Point2::new(100.0, 100.0));
+    let path = path.as_path();
+    let mut style = StrokeStyle::with_width(10.0);
+    style.dash_pattern = vec![20.0, 10.0];
+    style.dash_offset = 5.0;
+    style.initial_dash_cap = Some(LineCap::Round);
+    style.terminal_dash_cap = Some(LineCap::Square);
+    check_stroke_outline(&path, &style);
+
+    // The ends of the path get the end caps, and the ends of dashes get the dash caps.
+    let outline = path.stroke_outline(&style, 0.1);
+    let outline = outline.as_path();
+    let mask = FillRule::NonZero.mask();
+    assert!(!outline.is_point_in_fill(Point2::new(-1.0, 0.0), mask));
+    assert!(outline.is_point_in_fill(Point2::new(19.0, 4.0), mask));
+    assert!(!outline.is_point_in_fill(Point2::new(20.5, 4.0), mask));
+    assert!(outline.is_point_in_fill(Point2::new(21.0, 0.0), mask));
+
+    // Dashes of zero length are drawn as dots when the dash caps default to a round `line_cap`.
+    let mut style = StrokeStyle::with_width(10.0);
+    style.line_cap = LineCap::Round;
+    style.dash_pattern = vec![0.0, 20.0];
+    let outline = path.stroke_outline(&style, 0.1);
+    let outline = outline.as_path();
+    assert!(outline.is_point_in_fill(Point2::new(20.0, 4.0), mask));
+    assert!(outline.is_point_in_fill(Point2::new(16.0, 0.0), mask));
+    assert!(!outline.is_point_in_fill(Point2::new(10.0, 0.0), mask));
+}
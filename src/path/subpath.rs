@@ -0,0 +1,322 @@
+use nalgebra::{origin, Norm};
+
+use crate::painter::AsPathIter;
+use crate::Point2;
+use super::curve::Curve;
+use super::{Path, PathBuf, PathSegment, PathSegmentType};
+
+// An iterator over the subpaths of a path. Each one is a view of the segments from a move up to the
+// next move. If a subpath continues after a close without a move, the part after is in the same
+// subpath, starting where the closed part did.
+pub struct Subpaths<'a> {
+    types: &'a [PathSegmentType],
+    data: &'a [f32],
+}
+
+impl<'a> Iterator for Subpaths<'a> {
+    type Item = Path<'a>;
+
+    fn next(&mut self) -> Option<Path<'a>> {
+        if self.types.is_empty() {
+            return None;
+        }
+        let mut type_len = 1;
+        let mut data_len = self.types[0].data_len();
+        for ty in &self.types[1..] {
+            if *ty == PathSegmentType::Move {
+                break;
+            }
+            type_len += 1;
+            data_len += ty.data_len();
+        }
+        let subpath = Path {
+            seg_types: &self.types[..type_len],
+            seg_data: &self.data[..data_len],
+        };
+        self.types = &self.types[type_len..];
+        self.data = &self.data[data_len..];
+        Some(subpath)
+    }
+}
+
+// One piece of a path that can be closed, which a subpath has more than one of if it continues
+// after a close without a move. If it's closed, `curves` doesn't include the line back to the
+// start.
+pub(crate) struct Contour {
+    pub(crate) start_pt: Point2<f32>,
+    pub(crate) curves: Vec<Curve>,
+    pub(crate) closed: bool,
+}
+
+impl Contour {
+    // Returns the signed area, counting the contour as closed like when it is filled.
+    fn signed_area(&self) -> f32 {
+        let end_pt = self.curves.last().map_or(self.start_pt, |curve| curve.end_point());
+        let area: f32 = self.curves.iter().map(|curve| curve.signed_area()).sum();
+        area + Curve::Line(end_pt, self.start_pt).signed_area()
+    }
+
+    fn winding_number(&self, pt: Point2<f32>) -> i32 {
+        let end_pt = self.curves.last().map_or(self.start_pt, |curve| curve.end_point());
+        let winding: i32 = self.curves.iter().map(|curve| curve.winding_number(pt)).sum();
+        winding + Curve::Line(end_pt, self.start_pt).winding_number(pt)
+    }
+
+    fn push_to(&self, path: &mut PathBuf) {
+        path.move_to(self.start_pt);
+        for curve in &self.curves {
+            path.push_curve(curve);
+        }
+        if self.closed {
+            path.close();
+        }
+    }
+
+    fn reversed(&self) -> Contour {
+        Contour {
+            start_pt: self.curves.last().map_or(self.start_pt, |curve| curve.end_point()),
+            curves: self.curves.iter().rev().map(|curve| curve.reversed()).collect(),
+            closed: self.closed,
+        }
+    }
+}
+
+pub(crate) fn contours(path: &Path) -> Vec<Contour> {
+    let mut contours = vec![];
+    let mut current: Option<Contour> = None;
+    let mut current_pt = origin();
+    let mut subpath_start_pt = origin();
+    for seg in path.path_iter() {
+        match seg {
+            PathSegment::Move(pt) => {
+                contours.extend(current.take());
+                current = Some(Contour { start_pt: pt, curves: vec![], closed: false });
+                subpath_start_pt = pt;
+                current_pt = pt;
+            },
+            PathSegment::Close => {
+                let mut contour = current.take().unwrap_or_else(|| {
+                    Contour { start_pt: subpath_start_pt, curves: vec![], closed: false }
+                });
+                contour.closed = true;
+                contours.push(contour);
+                current_pt = subpath_start_pt;
+            },
+            _ => {
+                let curve = Curve::from_segment(current_pt, &seg).unwrap();
+                // After a close, a new contour starts without a move.
+                current.get_or_insert_with(|| {
+                    Contour { start_pt: current_pt, curves: vec![], closed: false }
+                }).curves.push(curve);
+                current_pt = curve.end_point();
+            },
+        }
+    }
+    contours.extend(current);
+    contours
+}
+
+impl<'a> Path<'a> {
+    pub fn subpaths(&self) -> Subpaths<'a> {
+        Subpaths {
+            types: self.seg_types,
+            data: self.seg_data,
+        }
+    }
+
+    // Returns the path with the direction of each subpath reversed. The subpaths stay in the same
+    // order and open ones stay open, but each starts where it used to end.
+    pub fn reversed(&self) -> PathBuf {
+        let mut path = PathBuf::new();
+        for contour in contours(self) {
+            contour.reversed().push_to(&mut path);
+        }
+        path
+    }
+
+    // Returns the area the path encloses, with subpaths counted as closed like when filling. The
+    // area of a subpath is positive if it goes clockwise when y points down, and negative if it
+    // goes counterclockwise. Areas that subpaths wind around more than once are counted more than
+    // once. To get the area of one subpath, call this on the items of `subpaths()`.
+    pub fn signed_area(&self) -> f32 {
+        contours(self).iter().map(|contour| contour.signed_area()).sum()
+    }
+
+    // Returns whether the signed area is positive, which for a single subpath means it goes
+    // clockwise when y points down.
+    pub fn is_clockwise(&self) -> bool {
+        self.signed_area() > 0.0
+    }
+
+    // Returns the path with its outer subpaths going clockwise (when y points down) and the holes
+    // in them going counterclockwise, the holes in those holes clockwise, and so on. Then the path
+    // fills the same area with either fill rule, which is the area between each subpath and the
+    // holes in it. The subpaths must not cross each other or themselves, which `boolean_op()` can
+    // be used to fix first.
+    pub fn normalize_orientation(&self) -> PathBuf {
+        let contours = contours(self);
+        let mut path = PathBuf::new();
+        for (i, contour) in contours.iter().enumerate() {
+            // How deep a contour is nested is how many other contours one of its points is in. A
+            // point in the middle of a curve isn't where another contour could touch it.
+            let pt = match contour.curves.iter().find(|curve| {
+                curve.tangent_at(0.5).norm_squared() > 0.0
+            }) {
+                Some(curve) => curve.point_at(0.5),
+                None => {
+                    contour.push_to(&mut path);
+                    continue;
+                },
+            };
+            let depth = contours.iter()
+                                .enumerate()
+                                .filter(|&(j, other)| j != i && other.winding_number(pt) != 0)
+                                .count();
+            let area = contour.signed_area();
+            if area != 0.0 && (area > 0.0) != (depth % 2 == 0) {
+                contour.reversed().push_to(&mut path);
+            } else {
+                contour.push_to(&mut path);
+            }
+        }
+        path
+    }
+}
+
+#[test]
+fn test_subpaths() {
+    use std::f32::consts::PI;
+
+    let mut path = PathBuf::new();
+    path.arc_to(Point2::new(0.0, 0.0), 10.0, 10.0, 0.0, PI);
+    path.move_to(Point2::new(20.0, 0.0));
+    path.line_to(Point2::new(30.0, 0.0));
+    path.close();
+    path.line_to(Point2::new(20.0, 10.0));
+    path.move_to(Point2::new(50.0, 0.0));
+    let path = path.as_path();
+    let subpaths: Vec<_> = path.subpaths().map(|subpath| {
+        subpath.path_iter().collect::<Vec<_>>()
+    }).collect();
+    assert_eq!(subpaths.len(), 3);
+    assert_eq!(subpaths[0].len(), 2);
+    assert_eq!(subpaths[1], [
+        PathSegment::Move(Point2::new(20.0, 0.0)),
+        PathSegment::Line(Point2::new(30.0, 0.0)),
+        PathSegment::Close,
+        PathSegment::Line(Point2::new(20.0, 10.0)),
+    ]);
+    assert_eq!(subpaths[2], [PathSegment::Move(Point2::new(50.0, 0.0))]);
+    assert_eq!(PathBuf::new().as_path().subpaths().count(), 0);
+}
+
+#[test]
+fn test_reversed() {
+    use std::f32::consts::PI;
+    use nalgebra::ApproxEq;
+    use super::ArcSegment;
+
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(10.0, 0.0));
+    path.quad_curve_to(Point2::new(20.0, 0.0), Point2::new(20.0, 10.0));
+    path.close();
+    path.line_to(Point2::new(0.0, 20.0));
+    path.move_to(Point2::new(50.0, 0.0));
+    path.arc_to(Point2::new(50.0, 10.0), 10.0, 5.0, 0.0, PI);
+    let reversed = path.as_path().reversed();
+    let segs: Vec<_> = reversed.path_iter().collect();
+    assert_eq!(segs[..6], [
+        PathSegment::Move(Point2::new(20.0, 10.0)),
+        PathSegment::QuadCurve(Point2::new(20.0, 0.0), Point2::new(10.0, 0.0)),
+        PathSegment::Line(Point2::new(0.0, 0.0)),
+        PathSegment::Close,
+        PathSegment::Move(Point2::new(0.0, 20.0)),
+        PathSegment::Line(Point2::new(0.0, 0.0)),
+    ]);
+    match segs[6] {
+        PathSegment::Move(pt) => assert_approx_eq_eps!(pt, Point2::new(40.0, 10.0), 1e-4),
+        ref seg => panic!("expected a move but got {:?}", seg),
+    }
+    assert_eq!(segs[7..], [
+        PathSegment::Arc(ArcSegment::new(Point2::new(50.0, 10.0), 10.0, 5.0, 0.0, PI, 0.0)),
+        PathSegment::Line(Point2::new(50.0, 0.0)),
+    ]);
+
+    // Reversing twice gives the same path, except the subpath after the close gets a move.
+    let twice = reversed.as_path().reversed();
+    assert_eq!(twice.path_iter().count(), path.path_iter().count() + 1);
+    assert_eq!(twice.path_iter().nth(5), Some(PathSegment::Line(Point2::new(0.0, 20.0))));
+}
+
+#[test]
+fn test_signed_area() {
+    use std::f32::consts::PI;
+    use nalgebra::ApproxEq;
+    use crate::{CornerRadii2, Rect};
+
+    let square = PathBuf::rect(Rect::new(10.0, 20.0, 30.0, 30.0));
+    assert_approx_eq!(square.as_path().signed_area(), 900.0);
+    assert!(square.as_path().is_clockwise());
+    assert_approx_eq!(square.as_path().reversed().as_path().signed_area(), -900.0);
+    assert!(!square.as_path().reversed().as_path().is_clockwise());
+
+    // An open subpath counts as closed.
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(0.0, 10.0));
+    path.line_to(Point2::new(-10.0, 10.0));
+    assert_approx_eq!(path.as_path().signed_area(), 50.0);
+
+    let ellipse = PathBuf::ellipse(Rect::new(10.0, 20.0, 60.0, 40.0));
+    assert_approx_eq_eps!(ellipse.as_path().signed_area(), PI * 30.0 * 20.0, 1e-2);
+    let rounded = PathBuf::rounded_rect(Rect::new(10.0, 20.0, 60.0, 40.0),
+                                        CornerRadii2::uniform(10.0));
+    assert_approx_eq_eps!(rounded.as_path().signed_area(), 2400.0 - (400.0 - PI * 100.0),
+                          1e-2);
+
+    // The curves' areas match what flattening them gives.
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.quad_curve_to(Point2::new(50.0, -20.0), Point2::new(40.0, 30.0));
+    path.cubic_curve_to(Point2::new(30.0, 60.0), Point2::new(-20.0, 0.0), Point2::new(10.0, 40.0));
+    // The arc starts at angle 0, which is along its rotated x axis.
+    let center_pt = Point2::new(10.0 - 10.0 * 0.5f32.cos(), 40.0 - 10.0 * 0.5f32.sin());
+    path.push_arc(center_pt, 10.0, 20.0, 0.5, 0.0, 2.0);
+    let mut flat_area = 0.0;
+    let mut flattened = PathBuf::new();
+    flattened.extend(path.flatten(0.001));
+    for subpath in flattened.as_path().subpaths() {
+        flat_area += subpath.signed_area();
+    }
+    assert_approx_eq_eps!(path.as_path().signed_area(), flat_area, 0.5);
+}
+
+#[test]
+fn test_normalize_orientation() {
+    use crate::Rect;
+    use super::FillRule;
+
+    // A square with a square hole, in which there's a smaller square, and a separate square. The
+    // first two go clockwise and the last two counterclockwise, so all but the first are flipped.
+    let mut path = PathBuf::rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+    path.extend(PathBuf::rect(Rect::new(20.0, 20.0, 60.0, 60.0)).path_iter());
+    path.extend(PathBuf::rect(Rect::new(40.0, 40.0, 20.0, 20.0)).as_path().reversed().path_iter());
+    path.extend(PathBuf::rect(Rect::new(200.0, 0.0, 10.0, 10.0)).as_path().reversed().path_iter());
+    let normalized = path.as_path().normalize_orientation();
+    let clockwise: Vec<_> = normalized.as_path().subpaths().map(|subpath| {
+        subpath.is_clockwise()
+    }).collect();
+    assert_eq!(clockwise, [true, false, true, true]);
+
+    let nonzero = FillRule::NonZero.mask();
+    let even_odd = FillRule::EvenOdd.mask();
+    let normalized = normalized.as_path();
+    for &(x, y) in &[(10.0, 10.0), (30.0, 30.0), (50.0, 50.0), (205.0, 5.0)] {
+        let pt = Point2::new(x, y);
+        assert_eq!(normalized.is_point_in_fill(pt, nonzero),
+                   normalized.is_point_in_fill(pt, even_odd));
+        assert_eq!(normalized.is_point_in_fill(pt, nonzero),
+                   path.as_path().is_point_in_fill(pt, even_odd));
+    }
+}
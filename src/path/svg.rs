@@ -0,0 +1,538 @@
+use std::f32::consts::PI;
+
+use nalgebra::{origin, Vector2};
+
+use crate::painter::AsPathIter;
+use crate::Point2;
+use super::{Path, PathBuf, PathParseError, PathParseErrorKind, PathSegment};
+
+// The grammar is at https://www.w3.org/TR/SVG11/paths.html#PathDataBNF
+
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: usize,
+    // whether the last separator skipped was a comma
+    after_comma: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.pos).cloned()
+    }
+
+    fn skip_wsp(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\r') | Some(b'\n') | Some(b'\x0C') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comma_wsp(&mut self) {
+        self.skip_wsp();
+        self.after_comma = self.peek() == Some(b',');
+        if self.after_comma {
+            self.pos += 1;
+            self.skip_wsp();
+        }
+    }
+
+    fn is_number_start(&self) -> bool {
+        match self.peek() {
+            Some(b'0'..=b'9') | Some(b'.') | Some(b'-') | Some(b'+') => true,
+            _ => false,
+        }
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let start = self.pos;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    // Numbers can be packed together without separators as long as it's unambiguous, like
+    // "1-2" or "0.5.5".
+    fn number(&mut self) -> Result<f32, PathParseError> {
+        let start = self.pos;
+        let error = Err(PathParseError::new(PathParseErrorKind::ExpectedNumber, start));
+        if let Some(b'-') | Some(b'+') = self.peek() {
+            self.pos += 1;
+        }
+        let mut digit_count = self.skip_digits();
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            digit_count += self.skip_digits();
+        }
+        if digit_count == 0 {
+            self.pos = start;
+            return error;
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            let mantissa_end = self.pos;
+            self.pos += 1;
+            if let Some(b'-') | Some(b'+') = self.peek() {
+                self.pos += 1;
+            }
+            if self.skip_digits() == 0 {
+                // The "e" isn't part of the number, so leave it for the caller to complain about.
+                self.pos = mantissa_end;
+            }
+        }
+        // The slice only contains ASCII, so it is valid UTF-8.
+        let num_str = std::str::from_utf8(&self.s[start..self.pos]).unwrap();
+        let num = num_str.parse::<f32>().or(error)?;
+        self.skip_comma_wsp();
+        Ok(num)
+    }
+
+    fn flag(&mut self) -> Result<bool, PathParseError> {
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(PathParseError::new(PathParseErrorKind::ExpectedFlag, self.pos)),
+        };
+        self.pos += 1;
+        self.skip_comma_wsp();
+        Ok(flag)
+    }
+
+    fn point(&mut self) -> Result<Point2<f32>, PathParseError> {
+        let x = self.number()?;
+        let y = self.number()?;
+        Ok(Point2::new(x, y))
+    }
+}
+
+// The control point of the previous curve, used to reflect the first control point of S and T
+// commands.
+#[derive(Clone, Copy)]
+enum PrevControl {
+    None,
+    Cubic(Point2<f32>),
+    Quad(Point2<f32>),
+}
+
+fn reflect(ctrl_pt: Point2<f32>, about_pt: Point2<f32>) -> Point2<f32> {
+    about_pt + (about_pt - ctrl_pt)
+}
+
+impl PathBuf {
+    // Parses the data of an SVG path element's `d` attribute. Unlike SVG renderers, which draw the
+    // path up to the first error, this returns an error if any of the string is invalid.
+    pub fn from_svg_path_string(path_string: &str) -> Result<PathBuf, PathParseError> {
+        let mut parser = Parser { s: path_string.as_bytes(), pos: 0, after_comma: false };
+        let mut path = PathBuf::new();
+        let mut prev_cmd = None;
+        let mut prev_ctrl = PrevControl::None;
+
+        parser.skip_wsp();
+        while let Some(c) = parser.peek() {
+            let cmd_offset = parser.pos;
+            let cmd = if c.is_ascii_alphabetic() {
+                parser.pos += 1;
+                parser.skip_wsp();
+                parser.after_comma = false;
+                c
+            } else {
+                // A command can be repeated by only giving more arguments. Extra pairs of
+                // arguments after a move are lines.
+                match prev_cmd {
+                    Some(b'M') if parser.is_number_start() => b'L',
+                    Some(b'm') if parser.is_number_start() => b'l',
+                    Some(b'Z') | Some(b'z') | None => {
+                        return Err(PathParseError::new(PathParseErrorKind::ExpectedCommand,
+                                                       cmd_offset));
+                    },
+                    Some(prev_cmd) if parser.is_number_start() => prev_cmd,
+                    Some(_) => {
+                        return Err(PathParseError::new(PathParseErrorKind::ExpectedCommand,
+                                                       cmd_offset));
+                    },
+                }
+            };
+
+            let current_pt = path.current_point();
+            if current_pt.is_none() && cmd != b'M' && cmd != b'm' {
+                let kind = match cmd.to_ascii_uppercase() {
+                    b'L' | b'H' | b'V' | b'C' | b'S' | b'Q' | b'T' | b'A' | b'Z' =>
+                        PathParseErrorKind::NoCurrentPoint,
+                    _ => PathParseErrorKind::ExpectedCommand,
+                };
+                return Err(PathParseError::new(kind, cmd_offset));
+            }
+            let current_pt = current_pt.unwrap_or(origin());
+            // relative commands are lowercase
+            let base = if cmd.is_ascii_lowercase() {
+                current_pt.to_vector()
+            } else {
+                Vector2::new(0.0, 0.0)
+            };
+
+            match cmd.to_ascii_uppercase() {
+                b'M' => {
+                    path.move_to(parser.point()? + base);
+                    prev_ctrl = PrevControl::None;
+                },
+                b'L' => {
+                    path.line_to(parser.point()? + base);
+                    prev_ctrl = PrevControl::None;
+                },
+                b'H' => {
+                    path.line_to(Point2::new(parser.number()? + base.x, current_pt.y));
+                    prev_ctrl = PrevControl::None;
+                },
+                b'V' => {
+                    path.line_to(Point2::new(current_pt.x, parser.number()? + base.y));
+                    prev_ctrl = PrevControl::None;
+                },
+                b'C' => {
+                    let pt1 = parser.point()? + base;
+                    let pt2 = parser.point()? + base;
+                    let pt3 = parser.point()? + base;
+                    path.cubic_curve_to(pt1, pt2, pt3);
+                    prev_ctrl = PrevControl::Cubic(pt2);
+                },
+                b'S' => {
+                    let pt1 = match prev_ctrl {
+                        PrevControl::Cubic(ctrl_pt) => reflect(ctrl_pt, current_pt),
+                        _ => current_pt,
+                    };
+                    let pt2 = parser.point()? + base;
+                    let pt3 = parser.point()? + base;
+                    path.cubic_curve_to(pt1, pt2, pt3);
+                    prev_ctrl = PrevControl::Cubic(pt2);
+                },
+                b'Q' => {
+                    let pt1 = parser.point()? + base;
+                    let pt2 = parser.point()? + base;
+                    path.quad_curve_to(pt1, pt2);
+                    prev_ctrl = PrevControl::Quad(pt1);
+                },
+                b'T' => {
+                    let pt1 = match prev_ctrl {
+                        PrevControl::Quad(ctrl_pt) => reflect(ctrl_pt, current_pt),
+                        _ => current_pt,
+                    };
+                    let pt2 = parser.point()? + base;
+                    path.quad_curve_to(pt1, pt2);
+                    prev_ctrl = PrevControl::Quad(pt1);
+                },
+                b'A' => {
+                    let x_radius = parser.number()?;
+                    let y_radius = parser.number()?;
+                    let rotation = parser.number()?.to_radians();
+                    let large_arc = parser.flag()?;
+                    let sweep = parser.flag()?;
+                    let end_pt = parser.point()? + base;
+                    path.endpoint_arc_to(x_radius, y_radius, rotation, large_arc, sweep, end_pt);
+                    prev_ctrl = PrevControl::None;
+                },
+                b'Z' => {
+                    path.close();
+                    prev_ctrl = PrevControl::None;
+                },
+                _ => return Err(PathParseError::new(PathParseErrorKind::ExpectedCommand, cmd_offset)),
+            }
+
+            // A comma can separate repeated arguments, but it can't come before a command.
+            if parser.after_comma && !parser.is_number_start() {
+                return Err(PathParseError::new(PathParseErrorKind::ExpectedNumber, parser.pos));
+            }
+            prev_cmd = Some(cmd);
+        }
+        Ok(path)
+    }
+
+    // Writes the path as SVG path data. See `Path::to_svg_path_string`.
+    pub fn to_svg_path_string(&self) -> String {
+        self.as_path().to_svg_path_string()
+    }
+}
+
+// Writes numbers and commands with as few separators as possible.
+struct SvgWriter {
+    out: String,
+    prev_cmd: Option<char>,
+    // whether a separator is needed before a number starting with a digit or a period
+    digit_sep_needed: bool,
+    period_sep_needed: bool,
+}
+
+impl SvgWriter {
+    fn command(&mut self, cmd: char) {
+        // Repeated commands can be left out, and so can a line command after a move.
+        let implicit = match (self.prev_cmd, cmd) {
+            (Some('M'), 'L') => true,
+            (Some(prev_cmd), cmd) => prev_cmd == cmd && cmd != 'M' && cmd != 'Z',
+            (None, _) => false,
+        };
+        if !implicit {
+            self.out.push(cmd);
+            self.digit_sep_needed = false;
+            self.period_sep_needed = false;
+        }
+        self.prev_cmd = Some(cmd);
+    }
+
+    fn number(&mut self, num: f32) {
+        // Rust prints the shortest string that parses back to the same f32, and it never uses
+        // an exponent.
+        let mut num_str = format!("{}", num);
+        if num_str == "-0" {
+            num_str = "0".to_owned();
+        } else if num_str.starts_with("0.") {
+            num_str.remove(0);
+        } else if num_str.starts_with("-0.") {
+            num_str.remove(1);
+        }
+        let sep_needed = match num_str.as_bytes()[0] {
+            b'.' => self.period_sep_needed,
+            b'-' => false,
+            _ => self.digit_sep_needed,
+        };
+        if sep_needed {
+            self.out.push(' ');
+        }
+        self.out.push_str(&num_str);
+        self.digit_sep_needed = true;
+        // A second period starts a new number, as in ".5.5".
+        self.period_sep_needed = !num_str.contains('.');
+    }
+
+    fn point(&mut self, pt: Point2<f32>) {
+        self.number(pt.x);
+        self.number(pt.y);
+    }
+}
+
+impl<'a> Path<'a> {
+    // Writes the path as compact SVG path data for a `d` attribute. Only absolute commands are
+    // used so that no error accumulates, but H, V, S, and T are used when they are shorter.
+    // Arcs that are a full turn or longer are split because an SVG arc can't be a full ellipse.
+    pub fn to_svg_path_string(&self) -> String {
+        let mut writer = SvgWriter {
+            out: String::new(),
+            prev_cmd: None,
+            digit_sep_needed: false,
+            period_sep_needed: false,
+        };
+        let mut current_pt: Point2<f32> = origin();
+        let mut subpath_start_pt: Point2<f32> = origin();
+        let mut prev_ctrl = PrevControl::None;
+
+        for seg in self.path_iter() {
+            let mut ctrl = PrevControl::None;
+            match seg {
+                PathSegment::Move(pt) => {
+                    writer.command('M');
+                    writer.point(pt);
+                    subpath_start_pt = pt;
+                    current_pt = pt;
+                },
+                PathSegment::Line(pt) => {
+                    if pt.y == current_pt.y && pt.x != current_pt.x {
+                        writer.command('H');
+                        writer.number(pt.x);
+                    } else if pt.x == current_pt.x && pt.y != current_pt.y {
+                        writer.command('V');
+                        writer.number(pt.y);
+                    } else {
+                        writer.command('L');
+                        writer.point(pt);
+                    }
+                    current_pt = pt;
+                },
+                PathSegment::QuadCurve(pt1, pt2) => {
+                    let reflected = match prev_ctrl {
+                        PrevControl::Quad(ctrl_pt) => reflect(ctrl_pt, current_pt),
+                        _ => current_pt,
+                    };
+                    if pt1 == reflected {
+                        writer.command('T');
+                    } else {
+                        writer.command('Q');
+                        writer.point(pt1);
+                    }
+                    writer.point(pt2);
+                    ctrl = PrevControl::Quad(pt1);
+                    current_pt = pt2;
+                },
+                PathSegment::CubicCurve(pt1, pt2, pt3) => {
+                    let reflected = match prev_ctrl {
+                        PrevControl::Cubic(ctrl_pt) => reflect(ctrl_pt, current_pt),
+                        _ => current_pt,
+                    };
+                    if pt1 == reflected {
+                        writer.command('S');
+                    } else {
+                        writer.command('C');
+                        writer.point(pt1);
+                    }
+                    writer.point(pt2);
+                    writer.point(pt3);
+                    ctrl = PrevControl::Cubic(pt2);
+                    current_pt = pt3;
+                },
+                PathSegment::Arc(arc) => {
+                    let sweep_angle = arc.angle2 - arc.angle1;
+                    let piece_count = if sweep_angle.abs() < 2.0 * PI {
+                        1
+                    } else {
+                        (sweep_angle.abs() / PI).ceil() as u32
+                    };
+                    for i in 1..=piece_count {
+                        let angle = arc.angle1 + sweep_angle * (i as f32 / piece_count as f32);
+                        let end_pt = arc.point_at_angle(angle);
+                        writer.command('A');
+                        writer.number(arc.x_radius);
+                        writer.number(arc.y_radius);
+                        writer.number(arc.rotation.to_degrees());
+                        let piece_sweep_angle = sweep_angle / piece_count as f32;
+                        writer.number(if piece_sweep_angle.abs() > PI { 1.0 } else { 0.0 });
+                        writer.number(if piece_sweep_angle > 0.0 { 1.0 } else { 0.0 });
+                        writer.point(end_pt);
+                        current_pt = end_pt;
+                    }
+                },
+                PathSegment::Close => {
+                    writer.command('Z');
+                    current_pt = subpath_start_pt;
+                },
+            }
+            prev_ctrl = ctrl;
+        }
+        writer.out
+    }
+}
+
+#[test]
+fn test_from_svg_path_string() {
+    let path = PathBuf::from_svg_path_string(
+        "M10,20 l5-5h10V0 C0 0 1 1 2 2s3 3 4 4 Q5 5 6 6 t1 1 Z m1 1 2 2").unwrap();
+    let segs: Vec<_> = path.path_iter().collect();
+    assert_eq!(segs, vec![
+        PathSegment::Move(Point2::new(10.0, 20.0)),
+        PathSegment::Line(Point2::new(15.0, 15.0)),
+        PathSegment::Line(Point2::new(25.0, 15.0)),
+        PathSegment::Line(Point2::new(25.0, 0.0)),
+        PathSegment::CubicCurve(Point2::new(0.0, 0.0), Point2::new(1.0, 1.0),
+                                Point2::new(2.0, 2.0)),
+        PathSegment::CubicCurve(Point2::new(3.0, 3.0), Point2::new(5.0, 5.0),
+                                Point2::new(6.0, 6.0)),
+        PathSegment::QuadCurve(Point2::new(5.0, 5.0), Point2::new(6.0, 6.0)),
+        PathSegment::QuadCurve(Point2::new(7.0, 7.0), Point2::new(7.0, 7.0)),
+        PathSegment::Close,
+        PathSegment::Move(Point2::new(11.0, 21.0)),
+        PathSegment::Line(Point2::new(13.0, 23.0)),
+    ]);
+
+    // compact numbers
+    let path = PathBuf::from_svg_path_string("M.5.5-1e1-2E-1L+3.,0").unwrap();
+    let segs: Vec<_> = path.path_iter().collect();
+    assert_eq!(segs, vec![
+        PathSegment::Move(Point2::new(0.5, 0.5)),
+        PathSegment::Line(Point2::new(-10.0, -0.2)),
+        PathSegment::Line(Point2::new(3.0, 0.0)),
+    ]);
+
+    assert_eq!(PathBuf::from_svg_path_string("").unwrap().path_iter().count(), 0);
+}
+
+#[test]
+fn test_from_svg_path_string_arcs() {
+    use nalgebra::ApproxEq;
+
+    // a half circle drawn clockwise, through the top
+    let path = PathBuf::from_svg_path_string("M0 0A10 10 0 0 1 20 0").unwrap();
+    match path.path_iter().nth(1).unwrap() {
+        PathSegment::Arc(arc) => {
+            assert_approx_eq!(arc.center_pt, Point2::new(10.0, 0.0));
+            assert_approx_eq!(arc.angle1, PI);
+            assert_approx_eq!(arc.angle2, 2.0 * PI);
+        },
+        _ => panic!("expected an arc"),
+    }
+    assert_approx_eq_eps!(path.current_point().unwrap(), Point2::new(20.0, 0.0), 1e-4);
+
+    // The radii are too small, so they are scaled up. Flags don't need separators.
+    let path = PathBuf::from_svg_path_string("M0 0a2 1 0 1020 0").unwrap();
+    match path.path_iter().nth(1).unwrap() {
+        PathSegment::Arc(arc) => {
+            assert_approx_eq!(arc.center_pt, Point2::new(10.0, 0.0));
+            assert_approx_eq!(arc.x_radius, 10.0);
+            assert_approx_eq!(arc.y_radius, 5.0);
+            assert_approx_eq!(arc.angle2 - arc.angle1, -PI);
+        },
+        _ => panic!("expected an arc"),
+    }
+
+    // a rotated ellipse
+    let path = PathBuf::from_svg_path_string("M0 0A20 10 30 1 1 10 10").unwrap();
+    match path.path_iter().nth(1).unwrap() {
+        PathSegment::Arc(arc) => {
+            assert_approx_eq!(arc.x_radius, 20.0);
+            assert_approx_eq!(arc.y_radius, 10.0);
+            assert_approx_eq!(arc.rotation, PI / 6.0);
+            assert!(arc.angle2 - arc.angle1 > PI);
+        },
+        _ => panic!("expected an arc"),
+    }
+    assert_approx_eq_eps!(path.current_point().unwrap(), Point2::new(10.0, 10.0), 1e-4);
+}
+
+#[test]
+fn test_from_svg_path_string_errors() {
+    let err = |s: &str| PathBuf::from_svg_path_string(s).err().unwrap();
+    assert_eq!(err("L10 10"), PathParseError::new(PathParseErrorKind::NoCurrentPoint, 0));
+    assert_eq!(err("M10 10 L5"), PathParseError::new(PathParseErrorKind::ExpectedNumber, 9));
+    assert_eq!(err("M10 10 X5"), PathParseError::new(PathParseErrorKind::ExpectedCommand, 7));
+    assert_eq!(err("M10 10 Z 5"), PathParseError::new(PathParseErrorKind::ExpectedCommand, 9));
+    assert_eq!(err("M10 10, L5 5"), PathParseError::new(PathParseErrorKind::ExpectedNumber, 8));
+    assert_eq!(err("M1 1 A1 1 0 2 0 5 5"),
+               PathParseError::new(PathParseErrorKind::ExpectedFlag, 12));
+    assert_eq!(err("M1 1e"), PathParseError::new(PathParseErrorKind::ExpectedCommand, 4));
+}
+
+#[test]
+fn test_to_svg_path_string() {
+    use nalgebra::ApproxEq;
+
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(10.0, 20.5));
+    path.line_to(Point2::new(0.5, -0.25));
+    path.line_to(Point2::new(0.5, 3.0));
+    path.line_to(Point2::new(8.0, 3.0));
+    path.line_to(Point2::new(9.0, 4.0));
+    path.cubic_curve_to(Point2::new(10.0, 5.0), Point2::new(1.0, 1.0), Point2::new(2.0, 2.0));
+    path.cubic_curve_to(Point2::new(3.0, 3.0), Point2::new(5.0, 5.0), Point2::new(6.0, 6.0));
+    path.quad_curve_to(Point2::new(5.0, 5.0), Point2::new(6.0, 6.0));
+    path.close();
+    let svg = path.to_svg_path_string();
+    assert_eq!(svg, "M10 20.5.5-.25V3H8L9 4C10 5 1 1 2 2S5 5 6 6Q5 5 6 6Z");
+
+    let parsed = PathBuf::from_svg_path_string(&svg).unwrap();
+    assert!(parsed.path_iter().eq(path.path_iter()));
+
+    // A full circle needs two arcs.
+    let mut path = PathBuf::new();
+    path.arc_to(Point2::new(0.0, 0.0), 10.0, 10.0, 0.0, 2.0 * PI);
+    let parsed = PathBuf::from_svg_path_string(&path.to_svg_path_string()).unwrap();
+    let segs: Vec<_> = parsed.path_iter().collect();
+    assert_eq!(segs.len(), 3);
+    for seg in &segs[1..] {
+        match *seg {
+            PathSegment::Arc(arc) => assert_approx_eq!(arc.angle2 - arc.angle1, PI),
+            _ => panic!("expected an arc"),
+        }
+    }
+
+    // The rotation is written in degrees.
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.endpoint_arc_to(20.0, 10.0, PI / 4.0, false, true, Point2::new(10.0, 10.0));
+    let parsed = PathBuf::from_svg_path_string(&path.to_svg_path_string()).unwrap();
+    match parsed.path_iter().nth(1).unwrap() {
+        PathSegment::Arc(arc) => assert_approx_eq!(arc.rotation, PI / 4.0),
+        _ => panic!("expected an arc"),
+    }
+}
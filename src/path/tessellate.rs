@@ -0,0 +1,749 @@
+use nalgebra::{Dot, Norm, Vector2};
+use smallvec::SmallVec;
+
+use crate::{CubicBezier, Point2, QuadBezier, Rect};
+use super::curve::Curve;
+use super::{BooleanOp, FillRule, Path, PathBuf};
+
+// Curves are split in half at most this many times to keep their triangles from overlapping.
+const MAX_SPLIT_ROUNDS: u32 = 10;
+
+// The klm coordinates of the vertices of triangles inside the path. k³ - lm is negative everywhere
+// in them, so they're completely filled.
+const SOLID_KLM: [f32; 3] = [0.0, 1.0, 1.0];
+
+// A vertex of a tessellated path. A pixel is filled where k³ - lm < 0, using the klm coordinates
+// interpolated across the triangle, as in Loop and Blinn's paper. Triangles inside the path have
+// `SOLID_KLM` at every vertex. Triangles on a curve cover the convex hull of its control points,
+// and k³ - lm is zero along the curve and positive on the side that isn't filled. For a quadratic
+// curve, klm is (u, v, u), which makes k³ - lm = u(u² - v) with the usual uv coordinates.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FillVertex {
+    pub pt: Point2<f32>,
+    pub klm: [f32; 3],
+}
+
+// A path tessellated into an indexed triangle list that fills it without approximating its
+// quadratic and cubic curves. The triangles don't overlap, and all of them are clockwise when y
+// points down, so it can be drawn without a stencil buffer.
+#[derive(Clone, Debug)]
+pub struct BakedFilledPath {
+    vertices: Vec<FillVertex>,
+    indices: Vec<u32>,
+}
+
+impl BakedFilledPath {
+    // Tessellates the area `path` fills with `fill_rule`. Arcs are converted to cubic curves that
+    // are within `tolerance` of them, but other curves are kept exactly.
+    pub fn new(path: &Path, fill_rule: FillRule, tolerance: f32) -> BakedFilledPath {
+        // Resolving the fill rule first leaves subpaths that don't cross, with the filled area on
+        // their right when y points down.
+        let area = path.boolean_op(fill_rule, &PathBuf::new().as_path(), FillRule::NonZero,
+                                   BooleanOp::Union);
+        let mut contours: Vec<Vec<Curve>> = area.as_path().subpaths().map(|subpath| {
+            let mut curves = vec![];
+            for (_, curve) in subpath.curves() {
+                match curve {
+                    Curve::Line(pt0, pt1) if pt0 == pt1 => {},
+                    Curve::Cubic(ref bez) => push_convex_cubics(bez, &mut curves),
+                    Curve::Arc(ref arc) => {
+                        for bez in arc.to_cubics(tolerance) {
+                            push_convex_cubics(&bez, &mut curves);
+                        }
+                    },
+                    curve => curves.push(curve),
+                }
+            }
+            curves
+        }).collect();
+        let bounds = area.as_path().bounding_box();
+        separate_hulls(&mut contours, bounds.width.max(bounds.height) * 1e-5);
+
+        let mut baked = BakedFilledPath { vertices: vec![], indices: vec![] };
+        let polygons: Vec<_> = contours.iter().map(|curves| baked.add_contour(curves)).collect();
+        baked.add_polygons(polygons, bounds.width.max(bounds.height) * 1e-5);
+        baked
+    }
+
+    pub fn vertices(&self) -> &[FillVertex] {
+        &self.vertices
+    }
+
+    // Returns the indices of the vertices of the triangles, three per triangle.
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    // Adds the triangles of the curves in the contour and returns the polygon that's left to fill
+    // inside them. A curve bulging out of the filled area is cut off by the line between its end
+    // points, and one bulging into it is cut off by its control points.
+    fn add_contour(&mut self, curves: &[Curve]) -> Vec<Point2<f32>> {
+        let mut polygon = vec![];
+        for curve in curves {
+            let control_pts = control_points(curve);
+            let start_pt = control_pts[0];
+            let klm = match *curve {
+                Curve::Line(..) => None,
+                Curve::Quad(ref bez) => quad_klm(bez),
+                Curve::Cubic(ref bez) => cubic_klm(bez),
+                Curve::Arc(_) => unreachable!("arcs are converted to cubic curves"),
+            };
+            let [k, l, m] = match klm {
+                Some(klm) => orient_klm(klm, curve),
+                None => {
+                    // The curve is straight.
+                    polygon.push(start_pt);
+                    continue;
+                },
+            };
+            let chord = control_pts[control_pts.len() - 1] - start_pt;
+            let inner_pts = &control_pts[1..control_pts.len() - 1];
+            let bulge: f32 = inner_pts.iter().map(|pt| cross(chord, *pt - start_pt)).sum();
+            polygon.push(start_pt);
+            if bulge > 0.0 {
+                polygon.extend_from_slice(inner_pts);
+            }
+
+            let first_index = self.vertices.len() as u32;
+            for &pt in &control_pts {
+                self.vertices.push(FillVertex {
+                    pt,
+                    klm: [evaluate(k, pt), evaluate(l, pt), evaluate(m, pt)],
+                });
+            }
+            for i in 1..control_pts.len() as u32 - 1 {
+                self.add_triangle(first_index, first_index + i, first_index + i + 1);
+            }
+        }
+        polygon
+    }
+
+    // Adds a triangle, reversing it if needed to make it clockwise. Triangles without any area
+    // are left out.
+    fn add_triangle(&mut self, i0: u32, i1: u32, i2: u32) {
+        let pt0 = self.pt(i0);
+        let area = cross(self.pt(i1) - pt0, self.pt(i2) - pt0);
+        if area > 0.0 {
+            self.indices.extend_from_slice(&[i0, i1, i2]);
+        } else if area < 0.0 {
+            self.indices.extend_from_slice(&[i0, i2, i1]);
+        }
+    }
+
+    // Adds solid triangles filling the polygons. Polygons with a positive area are filled, and
+    // ones with a negative area are holes in the smallest polygon around them.
+    fn add_polygons(&mut self, polygons: Vec<Vec<Point2<f32>>>, tolerance: f32) {
+        let mut outers = vec![];
+        let mut holes = vec![];
+        for mut polygon in polygons {
+            polygon.dedup();
+            while polygon.len() > 1 && polygon[0] == polygon[polygon.len() - 1] {
+                polygon.pop();
+            }
+            if polygon.len() < 3 {
+                continue;
+            }
+            let area = polygon_area(&polygon);
+            if area > 0.0 {
+                outers.push((area, polygon, vec![]));
+            } else if area < 0.0 {
+                holes.push(polygon);
+            }
+        }
+        for hole in holes {
+            // Holes can touch the polygon around them at a vertex, but not in the middle of an
+            // edge.
+            let pt = hole[0] + (hole[1] - hole[0]) * 0.5;
+            let outer = outers.iter_mut()
+                              .filter(|outer| is_point_in_polygon(pt, &outer.1))
+                              .min_by(|outer0, outer1| outer0.0.partial_cmp(&outer1.0).unwrap());
+            if let Some(outer) = outer {
+                outer.2.push(hole);
+            }
+        }
+
+        for (_, outer, mut holes) in outers {
+            let mut polygon = self.add_solid_vertices(&outer);
+            // Joining the holes from right to left means a later one can't block an earlier one's
+            // bridge.
+            holes.sort_by(|hole0, hole1| {
+                max_x(hole1).partial_cmp(&max_x(hole0)).unwrap()
+            });
+            for hole in holes {
+                let hole = self.add_solid_vertices(&hole);
+                self.join_hole(&mut polygon, &hole);
+            }
+            self.clip_ears(polygon, tolerance * tolerance);
+        }
+    }
+
+    fn add_solid_vertices(&mut self, polygon: &[Point2<f32>]) -> Vec<u32> {
+        let first_index = self.vertices.len() as u32;
+        self.vertices.extend(polygon.iter().map(|&pt| FillVertex { pt, klm: SOLID_KLM }));
+        (first_index..self.vertices.len() as u32).collect()
+    }
+
+    // Joins a hole to the polygon around it with a bridge from its rightmost vertex to a vertex of
+    // the polygon it can see, going there and back along the bridge. This is the approach in David
+    // Eberly's "Triangulation by Ear Clipping".
+    fn join_hole(&self, polygon: &mut Vec<u32>, hole: &[u32]) {
+        let pt = |index: u32| self.pt(index);
+        let hole_start = (0..hole.len()).max_by(|&i, &j| {
+            pt(hole[i]).x.partial_cmp(&pt(hole[j]).x).unwrap()
+        }).unwrap();
+        let hole_pt = pt(hole[hole_start]);
+
+        // Find the closest edge to the right of the hole's vertex.
+        let mut closest: Option<(usize, f32)> = None;
+        for i in 0..polygon.len() {
+            let pt0 = pt(polygon[i]);
+            let pt1 = pt(polygon[(i + 1) % polygon.len()]);
+            if (pt0.y > hole_pt.y) == (pt1.y > hole_pt.y) {
+                continue;
+            }
+            let x = pt0.x + (hole_pt.y - pt0.y) / (pt1.y - pt0.y) * (pt1.x - pt0.x);
+            if x >= hole_pt.x && closest.map_or(true, |(_, closest_x)| x < closest_x) {
+                closest = Some((i, x));
+            }
+        }
+        let (edge_index, x) = match closest {
+            Some(closest) => closest,
+            None => return,
+        };
+        let next_index = (edge_index + 1) % polygon.len();
+        let mut bridge_index = if pt(polygon[edge_index]).x > pt(polygon[next_index]).x {
+            edge_index
+        } else {
+            next_index
+        };
+
+        // A vertex inside the triangle between the hole's vertex, the edge's vertex, and where the
+        // edge was hit could block the bridge. Of those, the one at the smallest angle can't be.
+        let hit_pt = Point2::new(x, hole_pt.y);
+        let bridge_pt = pt(polygon[bridge_index]);
+        let mut best_angle = f32::INFINITY;
+        for (i, &index) in polygon.iter().enumerate() {
+            let vertex_pt = pt(index);
+            if vertex_pt == bridge_pt || vertex_pt == hole_pt ||
+               !is_point_in_triangle(vertex_pt, hole_pt, hit_pt, bridge_pt) {
+                continue;
+            }
+            let angle = (vertex_pt.y - hole_pt.y).abs().atan2(vertex_pt.x - hole_pt.x);
+            if angle < best_angle {
+                best_angle = angle;
+                bridge_index = i;
+            }
+        }
+
+        let mut joined = polygon[..bridge_index + 1].to_vec();
+        joined.extend_from_slice(&hole[hole_start..]);
+        joined.extend_from_slice(&hole[..hole_start + 1]);
+        joined.extend_from_slice(&polygon[bridge_index..]);
+        *polygon = joined;
+    }
+
+    // Triangulates a polygon with a positive area by repeatedly cutting off a convex vertex that
+    // has no other vertex inside its triangle.
+    fn clip_ears(&mut self, mut polygon: Vec<u32>, area_tolerance: f32) {
+        let mut i = 0;
+        let mut misses = 0;
+        while polygon.len() > 3 {
+            let len = polygon.len();
+            let prev = polygon[(i + len - 1) % len];
+            let next = polygon[(i + 1) % len];
+            let corner = [prev, polygon[i], next];
+            let pts = [self.pt(prev), self.pt(polygon[i]), self.pt(next)];
+            let turn = cross(pts[1] - pts[0], pts[2] - pts[1]);
+            let is_ear = turn > 0.0 && polygon.iter().all(|&index| {
+                let pt = self.pt(index);
+                pts.contains(&pt) || !is_point_in_triangle(pt, pts[0], pts[1], pts[2])
+            });
+            // If no vertex is an ear because of rounding error, cut off any convex one.
+            if turn.abs() <= area_tolerance || is_ear || (misses > len && turn > 0.0) {
+                if turn.abs() > area_tolerance {
+                    self.add_triangle(corner[0], corner[1], corner[2]);
+                }
+                polygon.remove(i);
+                i %= polygon.len();
+                misses = 0;
+            } else {
+                i = (i + 1) % len;
+                misses += 1;
+                if misses > len * 2 {
+                    return;
+                }
+            }
+        }
+        if polygon.len() == 3 {
+            self.add_triangle(polygon[0], polygon[1], polygon[2]);
+        }
+    }
+
+    fn pt(&self, index: u32) -> Point2<f32> {
+        self.vertices[index as usize].pt
+    }
+}
+
+// How Loop and Blinn classify a cubic curve, with the parameters of the points its k, l, and m
+// coordinates are based on. Each parameter is a pair (s, w) that stands for t = s / w, so that one
+// at infinity, where w is zero, can be used the same way as the others.
+enum CubicClass {
+    Line,
+    // a quadratic curve that was degree elevated
+    Quad,
+    // A serpentine or cusp has inflections at both parameters, which are the same for a cusp.
+    Serpentine([f32; 2], [f32; 2]),
+    // The curve crosses itself at both parameters.
+    Loop([f32; 2], [f32; 2]),
+}
+
+// Returns the coefficients of t³, t², and t when the curve is written as a polynomial, divided by
+// the same factor to keep them near 1.
+fn power_basis(bez: &CubicBezier<f32>) -> [Vector2<f32>; 3] {
+    let v1 = bez.p1 - bez.p0;
+    let v2 = bez.p2 - bez.p0;
+    let v3 = bez.p3 - bez.p0;
+    let scale = v1.norm().max(v2.norm()).max(v3.norm());
+    if scale == 0.0 {
+        return [Vector2::new(0.0, 0.0); 3];
+    }
+    [(v1 * 3.0 - v2 * 3.0 + v3) / scale, (v2 * 3.0 - v1 * 6.0) / scale, v1 * 3.0 / scale]
+}
+
+fn classify_cubic(bez: &CubicBezier<f32>) -> CubicClass {
+    let [a, b, c] = power_basis(bez);
+    if a.norm() <= 1e-5 {
+        return CubicClass::Quad;
+    }
+    let (cross_ab, cross_ac, cross_bc) = (cross(a, b), cross(a, c), cross(b, c));
+    if cross_ab.abs() <= 1e-6 && cross_ac.abs() <= 1e-6 {
+        return CubicClass::Line;
+    }
+    // The curve's inflections are where its first and second derivatives are parallel, which is
+    // where 3(a × b)t² + 3(a × c)t + b × c = 0.
+    let (quad_a, quad_b, quad_c) = (3.0 * cross_ab, 3.0 * cross_ac, cross_bc);
+    if quad_b * quad_b - 4.0 * quad_a * quad_c >= 0.0 {
+        let (t0, t1) = homogeneous_roots(quad_a, quad_b, quad_c);
+        CubicClass::Serpentine(t0, t1)
+    } else {
+        // Where P(s) = P(t), a(s² + st + t²) + b(s + t) + c = 0, and taking the cross product of
+        // that with a and with b gives the sum and product of s and t, so they're the roots of
+        // t² - (s + t)t + st.
+        let (t0, t1) = homogeneous_roots(cross_ab * cross_ab, cross_ab * cross_ac,
+                                         cross_ac * cross_ac - cross_ab * cross_bc);
+        CubicClass::Loop(t0, t1)
+    }
+}
+
+// Returns the roots of at² + bt + c as (s, w) pairs, normalized so s² + w² = 1. The discriminant
+// must not be negative.
+fn homogeneous_roots(a: f32, b: f32, c: f32) -> ([f32; 2], [f32; 2]) {
+    let root = (b * b - 4.0 * a * c).max(0.0).sqrt();
+    // This form avoids cancellation, and it gives w = 0 for a root at infinity when a is zero.
+    let q = -0.5 * (b + if b < 0.0 { -root } else { root });
+    let normalize = |s: f32, w: f32| {
+        let len = s.hypot(w);
+        if len == 0.0 { [1.0, 0.0] } else { [s / len, w / len] }
+    };
+    (normalize(q, a), normalize(c, q))
+}
+
+// Adds the cubic curve to `curves`, split into pieces whose control points form a convex polygon.
+// Each piece has no inflection or loop in it, and it stays on one side of the line between its end
+// points, so its triangles are all on that side too.
+fn push_convex_cubics(bez: &CubicBezier<f32>, curves: &mut Vec<Curve>) {
+    let mut ts: SmallVec<[f32; 4]> = match classify_cubic(bez) {
+        CubicClass::Serpentine(t0, t1) | CubicClass::Loop(t0, t1) => {
+            [t0, t1].iter().filter(|t| t[1] != 0.0).map(|t| t[0] / t[1]).collect()
+        },
+        _ => SmallVec::new(),
+    };
+    ts.retain(|t| *t > 1e-4 && *t < 1.0 - 1e-4);
+    ts.sort_by(|t0, t1| t0.partial_cmp(t1).unwrap());
+    ts.dedup();
+    let curve = Curve::Cubic(*bez);
+    let mut t0 = 0.0;
+    for t1 in ts.into_iter().chain(Some(1.0)) {
+        if let Curve::Cubic(piece) = curve.subcurve(t0, t1) {
+            push_halves_until_convex(&piece, 6, curves);
+        }
+        t0 = t1;
+    }
+}
+
+fn push_halves_until_convex(bez: &CubicBezier<f32>, depth: u32, curves: &mut Vec<Curve>) {
+    let pts = [bez.p0, bez.p1, bez.p2, bez.p3];
+    if pts.iter().all(|pt| *pt == bez.p0) {
+        return;
+    }
+    let scale = (bez.p1 - bez.p0).norm() + (bez.p2 - bez.p1).norm() + (bez.p3 - bez.p2).norm();
+    let tolerance = scale * scale * 1e-6;
+    let turns: SmallVec<[f32; 4]> = (0..4).map(|i| {
+        cross(pts[(i + 1) % 4] - pts[i], pts[(i + 2) % 4] - pts[(i + 1) % 4])
+    }).collect();
+    let is_convex = turns.iter().all(|turn| *turn >= -tolerance) ||
+                    turns.iter().all(|turn| *turn <= tolerance);
+    if is_convex || depth == 0 {
+        curves.push(Curve::Cubic(*bez));
+    } else {
+        let (first, second) = bez.split(0.5);
+        push_halves_until_convex(&first, depth - 1, curves);
+        push_halves_until_convex(&second, depth - 1, curves);
+    }
+}
+
+// Returns the lines that give the k, l, and m coordinates of a cubic curve's vertices, or None if
+// it's straight. Each line is the coefficients of x, y, and 1. Along the curve, k, l, and m are
+// cubic polynomials in t made from the factors (s - wt) that are zero at the class's parameters,
+// so k³ = lm, and their values at the control points are the polynomials' Bernstein coefficients.
+fn cubic_klm(bez: &CubicBezier<f32>) -> Option<[[f32; 3]; 3]> {
+    let (t0, t1, is_loop) = match classify_cubic(bez) {
+        CubicClass::Line => return None,
+        CubicClass::Quad => {
+            let ctrl_pt = bez.p0 + (bez.p1 - bez.p0) * 1.5;
+            return quad_klm(&QuadBezier::new(bez.p0, ctrl_pt, bez.p3));
+        },
+        CubicClass::Serpentine(t0, t1) => (t0, t1, false),
+        CubicClass::Loop(t0, t1) => (t0, t1, true),
+    };
+    let factor0 = [t0[0], -t0[1]];
+    let factor1 = [t1[0], -t1[1]];
+    let k = poly_mul(&factor0, &factor1);
+    // For a serpentine, l and m are tangent at the inflections. For a loop, they're tangent at
+    // the two parameters of the point where the curve crosses itself, so they go through it too.
+    let (l, m) = if is_loop {
+        (poly_mul(&k, &factor0), poly_mul(&k, &factor1))
+    } else {
+        (poly_mul(&poly_mul(&factor0, &factor0), &factor0),
+         poly_mul(&poly_mul(&factor1, &factor1), &factor1))
+    };
+
+    // The lines are fit to the three control points that make the biggest triangle.
+    let pts = [bez.p0, bez.p1, bez.p2, bez.p3];
+    let triples = [[0, 1, 2], [0, 1, 3], [0, 2, 3], [1, 2, 3]];
+    let triple = triples.iter().max_by(|i, j| {
+        let area = |t: &[usize; 3]| cross(pts[t[1]] - pts[t[0]], pts[t[2]] - pts[t[0]]).abs();
+        area(i).partial_cmp(&area(j)).unwrap()
+    }).unwrap();
+    let fit = |poly: &[f32]| {
+        let values = bernstein_coefficients(poly);
+        line_through_values([pts[triple[0]], pts[triple[1]], pts[triple[2]]],
+                            [values[triple[0]], values[triple[1]], values[triple[2]]])
+    };
+    Some([fit(&k)?, fit(&l)?, fit(&m)?])
+}
+
+// Multiplies two polynomials given by their coefficients, starting with the constant term.
+fn poly_mul(p: &[f32], q: &[f32]) -> SmallVec<[f32; 4]> {
+    let mut product = SmallVec::from_elem(0.0, p.len() + q.len() - 1);
+    for (i, p_coeff) in p.iter().enumerate() {
+        for (j, q_coeff) in q.iter().enumerate() {
+            product[i + j] += p_coeff * q_coeff;
+        }
+    }
+    product
+}
+
+// Returns the coefficients of a polynomial of degree 3 or less in the cubic Bernstein basis.
+fn bernstein_coefficients(poly: &[f32]) -> [f32; 4] {
+    let coeff = |i: usize| poly.get(i).cloned().unwrap_or(0.0);
+    let (c0, c1, c2, c3) = (coeff(0), coeff(1), coeff(2), coeff(3));
+    [c0, c0 + c1 / 3.0, c0 + c1 * (2.0 / 3.0) + c2 / 3.0, c0 + c1 + c2 + c3]
+}
+
+// Returns the lines that give the k, l, and m coordinates of a quadratic curve's vertices, which
+// are (u, v, u) with u and v going from (0, 0) to (0.5, 0) to (1, 1) at its control points.
+fn quad_klm(bez: &QuadBezier<f32>) -> Option<[[f32; 3]; 3]> {
+    let pts = [bez.p0, bez.p1, bez.p2];
+    let u = line_through_values(pts, [0.0, 0.5, 1.0])?;
+    Some([u, line_through_values(pts, [0.0, 0.0, 1.0])?, u])
+}
+
+// Returns the linear function of x and y that has the given values at the points, or None if
+// they're on a line.
+fn line_through_values(pts: [Point2<f32>; 3], values: [f32; 3]) -> Option<[f32; 3]> {
+    let v1 = pts[1] - pts[0];
+    let v2 = pts[2] - pts[0];
+    let det = cross(v1, v2);
+    if det.abs() <= v1.norm() * v2.norm() * 1e-6 {
+        return None;
+    }
+    let (value1, value2) = (values[1] - values[0], values[2] - values[0]);
+    let x = (value1 * v2.y - value2 * v1.y) / det;
+    let y = (v1.x * value2 - v2.x * value1) / det;
+    Some([x, y, values[0] - x * pts[0].x - y * pts[0].y])
+}
+
+// Flips the signs of k and l if needed so that k³ - lm is negative on the right side of the curve,
+// which is the side that's filled.
+fn orient_klm(klm: [[f32; 3]; 3], curve: &Curve) -> [[f32; 3]; 3] {
+    let [k, l, m] = klm;
+    let pt = curve.point_at(0.5);
+    let tangent = curve.tangent_at(0.5);
+    let right = Vector2::new(-tangent.y, tangent.x);
+    let gradient = |line: [f32; 3]| Vector2::new(line[0], line[1]);
+    let k_value = evaluate(k, pt);
+    let f_gradient = gradient(k) * (3.0 * k_value * k_value) - gradient(l) * evaluate(m, pt) -
+                     gradient(m) * evaluate(l, pt);
+    if f_gradient.dot(&right) > 0.0 {
+        [[-k[0], -k[1], -k[2]], [-l[0], -l[1], -l[2]], m]
+    } else {
+        klm
+    }
+}
+
+fn evaluate(line: [f32; 3], pt: Point2<f32>) -> f32 {
+    line[0] * pt.x + line[1] * pt.y + line[2]
+}
+
+// Splits curves in half until the convex hulls of their control points don't overlap each other or
+// any line, so that none of the triangles will overlap.
+fn separate_hulls(contours: &mut [Vec<Curve>], tolerance: f32) {
+    for _ in 0..MAX_SPLIT_ROUNDS {
+        let hulls: Vec<_> = contours.iter().enumerate().flat_map(|(contour_index, curves)| {
+            curves.iter().enumerate().map(move |(curve_index, curve)| {
+                let pts = control_points(curve);
+                let bounds = pts[1..].iter().fold(Rect::new(pts[0].x, pts[0].y, 0.0, 0.0),
+                                                  |bounds, pt| {
+                    bounds.union(Rect::new(pt.x, pt.y, 0.0, 0.0))
+                });
+                (contour_index, curve_index, pts, bounds)
+            })
+        }).collect();
+        let mut split: Vec<Vec<bool>> = contours.iter().map(|curves| {
+            vec![false; curves.len()]
+        }).collect();
+        let mut any_split = false;
+        for (i, hull0) in hulls.iter().enumerate() {
+            for hull1 in &hulls[i + 1..] {
+                let is_line0 = hull0.2.len() == 2;
+                let is_line1 = hull1.2.len() == 2;
+                if (is_line0 && is_line1) ||
+                   hull0.3.x > hull1.3.right() || hull1.3.x > hull0.3.right() ||
+                   hull0.3.y > hull1.3.bottom() || hull1.3.y > hull0.3.bottom() ||
+                   !hulls_overlap(&hull0.2, &hull1.2, tolerance) {
+                    continue;
+                }
+                split[hull0.0][hull0.1] |= !is_line0;
+                split[hull1.0][hull1.1] |= !is_line1;
+                any_split = true;
+            }
+        }
+        if !any_split {
+            return;
+        }
+        for (curves, split) in contours.iter_mut().zip(split) {
+            let mut split_curves = vec![];
+            for (curve, split) in curves.iter().zip(split) {
+                if split {
+                    split_curves.push(curve.subcurve(0.0, 0.5));
+                    split_curves.push(curve.subcurve(0.5, 1.0));
+                } else {
+                    split_curves.push(*curve);
+                }
+            }
+            *curves = split_curves;
+        }
+    }
+}
+
+fn control_points(curve: &Curve) -> SmallVec<[Point2<f32>; 4]> {
+    match *curve {
+        Curve::Line(pt0, pt1) => SmallVec::from_slice(&[pt0, pt1]),
+        Curve::Quad(ref bez) => SmallVec::from_slice(&[bez.p0, bez.p1, bez.p2]),
+        Curve::Cubic(ref bez) => SmallVec::from_slice(&[bez.p0, bez.p1, bez.p2, bez.p3]),
+        Curve::Arc(_) => unreachable!("arcs are converted to cubic curves"),
+    }
+}
+
+// Returns whether two convex polygons overlap by more than `tolerance`, so ones that only touch
+// don't. A polygon with two points is a line segment.
+fn hulls_overlap(hull0: &[Point2<f32>], hull1: &[Point2<f32>], tolerance: f32) -> bool {
+    !has_separating_edge(hull0, hull1, tolerance) && !has_separating_edge(hull1, hull0, tolerance)
+}
+
+fn has_separating_edge(hull: &[Point2<f32>], other: &[Point2<f32>], tolerance: f32) -> bool {
+    (0..hull.len()).any(|i| {
+        let edge = hull[(i + 1) % hull.len()] - hull[i];
+        if edge.norm() == 0.0 {
+            return false;
+        }
+        let normal = Vector2::new(-edge.y, edge.x).normalize();
+        let range = |pts: &[Point2<f32>]| {
+            pts.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), pt| {
+                let dist = pt.to_vector().dot(&normal);
+                (min.min(dist), max.max(dist))
+            })
+        };
+        let (min0, max0) = range(hull);
+        let (min1, max1) = range(other);
+        max0 <= min1 + tolerance || max1 <= min0 + tolerance
+    })
+}
+
+fn cross(v0: Vector2<f32>, v1: Vector2<f32>) -> f32 {
+    v0.x * v1.y - v0.y * v1.x
+}
+
+// Returns the signed area of the polygon, which is positive if it's clockwise when y points down.
+fn polygon_area(polygon: &[Point2<f32>]) -> f32 {
+    let origin = polygon[0];
+    (1..polygon.len() - 1).map(|i| cross(polygon[i] - origin, polygon[i + 1] - origin))
+                          .sum::<f32>() * 0.5
+}
+
+fn max_x(polygon: &[Point2<f32>]) -> f32 {
+    polygon.iter().map(|pt| pt.x).fold(f32::NEG_INFINITY, f32::max)
+}
+
+fn is_point_in_polygon(pt: Point2<f32>, polygon: &[Point2<f32>]) -> bool {
+    let mut inside = false;
+    for i in 0..polygon.len() {
+        let pt0 = polygon[i];
+        let pt1 = polygon[(i + 1) % polygon.len()];
+        if (pt0.y > pt.y) != (pt1.y > pt.y) &&
+           pt.x < pt0.x + (pt.y - pt0.y) / (pt1.y - pt0.y) * (pt1.x - pt0.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+// Returns whether the point is inside the triangle or on its edge, whichever way the triangle goes.
+fn is_point_in_triangle(pt: Point2<f32>, pt0: Point2<f32>, pt1: Point2<f32>, pt2: Point2<f32>)
+                        -> bool {
+    let side0 = cross(pt1 - pt0, pt - pt0);
+    let side1 = cross(pt2 - pt1, pt - pt1);
+    let side2 = cross(pt0 - pt2, pt - pt2);
+    (side0 >= 0.0 && side1 >= 0.0 && side2 >= 0.0) || (side0 <= 0.0 && side1 <= 0.0 && side2 <= 0.0)
+}
+
+// Returns how many triangles the point is in and how many of those fill it.
+#[cfg(test)]
+fn coverage(baked: &BakedFilledPath, pt: Point2<f32>) -> (usize, usize) {
+    let mut covered = 0;
+    let mut filled = 0;
+    for triangle in baked.indices().chunks(3) {
+        let v: SmallVec<[FillVertex; 3]> = triangle.iter()
+                                                   .map(|&i| baked.vertices()[i as usize])
+                                                   .collect();
+        let area = cross(v[1].pt - v[0].pt, v[2].pt - v[0].pt);
+        assert!(area > 0.0);
+        let weights = [cross(v[2].pt - v[1].pt, pt - v[1].pt) / area,
+                       cross(v[0].pt - v[2].pt, pt - v[2].pt) / area,
+                       cross(v[1].pt - v[0].pt, pt - v[0].pt) / area];
+        if weights.iter().any(|w| *w < 0.0) {
+            continue;
+        }
+        covered += 1;
+        let klm: SmallVec<[f32; 3]> = (0..3).map(|i| {
+            v[0].klm[i] * weights[0] + v[1].klm[i] * weights[1] + v[2].klm[i] * weights[2]
+        }).collect();
+        if klm[0] * klm[0] * klm[0] - klm[1] * klm[2] < 0.0 {
+            filled += 1;
+        }
+    }
+    (covered, filled)
+}
+
+// Checks that the tessellation fills the same points as the path at points on a grid, skipping
+// points close to an edge, and that no point is in more than one triangle.
+#[cfg(test)]
+fn check_tessellation(path: &Path, fill_rule: FillRule) -> BakedFilledPath {
+    let baked = BakedFilledPath::new(path, fill_rule, 0.01);
+    let bounds = path.bounding_box();
+    for y in -2..=42 {
+        for x in -2..=42 {
+            let pt = Point2::new(bounds.x + bounds.width * (x as f32 + 0.137) / 40.0,
+                                 bounds.y + bounds.height * (y as f32 + 0.391) / 40.0);
+            if path.project_point(pt).map_or(false, |(_, _, _, dist)| dist < 0.5) {
+                continue;
+            }
+            let (covered, filled) = coverage(&baked, pt);
+            assert!(covered <= 1, "triangles overlap at {:?}", pt);
+            assert_eq!(filled == 1, path.is_point_in_fill(pt, fill_rule.mask()), "at {:?}", pt);
+        }
+    }
+    baked
+}
+
+#[test]
+fn test_tessellate_polygons() {
+    let square = PathBuf::rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+    let baked = check_tessellation(&square.as_path(), FillRule::NonZero);
+    assert_eq!(baked.vertices().len(), 4);
+    assert_eq!(baked.indices().len(), 6);
+    assert!(baked.vertices().iter().all(|v| v.klm == SOLID_KLM));
+
+    // A square with a square hole, and a second square beside it
+    let mut path = PathBuf::rect(Rect::new(0.0, 0.0, 100.0, 100.0));
+    path.extend(PathBuf::rect(Rect::new(25.0, 25.0, 50.0, 50.0)).path_iter());
+    path.extend(PathBuf::rect(Rect::new(150.0, 40.0, 20.0, 20.0)).path_iter());
+    let baked = check_tessellation(&path.as_path(), FillRule::EvenOdd);
+    assert_eq!(baked.indices().len(), 3 * 10);
+    assert_eq!(coverage(&baked, Point2::new(50.0, 50.0)), (0, 0));
+
+    // A star crosses itself, and its middle is only filled with the nonzero rule.
+    let mut star = PathBuf::new();
+    star.move_to(Point2::new(50.0, 0.0));
+    star.line_to(Point2::new(80.0, 90.0));
+    star.line_to(Point2::new(0.0, 35.0));
+    star.line_to(Point2::new(100.0, 35.0));
+    star.line_to(Point2::new(20.0, 90.0));
+    star.close();
+    let baked = check_tessellation(&star.as_path(), FillRule::NonZero);
+    assert_eq!(coverage(&baked, Point2::new(50.0, 50.0)), (1, 1));
+    let baked = check_tessellation(&star.as_path(), FillRule::EvenOdd);
+    assert_eq!(coverage(&baked, Point2::new(50.0, 50.0)), (0, 0));
+}
+
+#[test]
+fn test_tessellate_curves() {
+    // Quadratic curves bulging out of and into the filled area are both exact.
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.quad_curve_to(Point2::new(50.0, -30.0), Point2::new(100.0, 0.0));
+    path.line_to(Point2::new(100.0, 100.0));
+    path.quad_curve_to(Point2::new(50.0, 60.0), Point2::new(0.0, 100.0));
+    path.close();
+    let baked = check_tessellation(&path.as_path(), FillRule::NonZero);
+    let bezs = [QuadBezier::new(Point2::new(0.0, 0.0), Point2::new(50.0, -30.0),
+                                Point2::new(100.0, 0.0)),
+                QuadBezier::new(Point2::new(100.0, 100.0), Point2::new(50.0, 60.0),
+                                Point2::new(0.0, 100.0))];
+    for bez in &bezs {
+        let curve = Curve::Quad(*bez);
+        for i in 1..10 {
+            let t = i as f32 * 0.1;
+            let tangent = curve.tangent_at(t);
+            let right = Vector2::new(-tangent.y, tangent.x) * 0.01;
+            assert_eq!(coverage(&baked, curve.point_at(t) + right), (1, 1));
+            assert_eq!(coverage(&baked, curve.point_at(t) - right).1, 0);
+        }
+    }
+
+    // an S-shaped cubic curve with an inflection
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.cubic_curve_to(Point2::new(40.0, -40.0), Point2::new(60.0, 40.0),
+                        Point2::new(100.0, 0.0));
+    path.line_to(Point2::new(100.0, 100.0));
+    path.line_to(Point2::new(0.0, 100.0));
+    path.close();
+    check_tessellation(&path.as_path(), FillRule::NonZero);
+
+    // a cubic curve with a loop
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.cubic_curve_to(Point2::new(150.0, 100.0), Point2::new(-50.0, 100.0),
+                        Point2::new(100.0, 0.0));
+    path.close();
+    check_tessellation(&path.as_path(), FillRule::NonZero);
+    check_tessellation(&path.as_path(), FillRule::EvenOdd);
+
+    // A ring made of arcs keeps its curves, so it needs few triangles.
+    let mut ring = PathBuf::circle(Point2::new(50.0, 50.0), 50.0);
+    ring.extend(PathBuf::circle(Point2::new(50.0, 50.0), 30.0).path_iter());
+    let baked = check_tessellation(&ring.as_path(), FillRule::EvenOdd);
+    assert!(baked.indices().len() < 3 * 100);
+}
@@ -0,0 +1,215 @@
+use std::f32::consts::PI;
+
+use glam::{Affine2, Vec2};
+
+use crate::Point2;
+use super::{ArcSegment, PathBuf, PathIter, PathSegment};
+
+fn transform_point(transform: &Affine2, pt: Point2<f32>) -> Point2<f32> {
+    let pt = transform.transform_point2(Vec2::new(pt.x, pt.y));
+    Point2::new(pt.x, pt.y)
+}
+
+// Returns the arc transformed, if it can still be represented as an `ArcSegment`. That is the case
+// when the transform is only a uniform scale, rotation, reflection, and translation, or when it
+// only scales an unrotated ellipse along its axes.
+fn transform_arc(transform: &Affine2, arc: &ArcSegment) -> Option<ArcSegment> {
+    let x_axis = transform.matrix2.x_axis;
+    let y_axis = transform.matrix2.y_axis;
+    let center_pt = transform_point(transform, arc.center_pt);
+    if arc.rotation == 0.0 && x_axis.y == 0.0 && y_axis.x == 0.0 {
+        // Flipping an axis mirrors the angles across the other axis.
+        let map_angle = |angle: f32| match (x_axis.x < 0.0, y_axis.y < 0.0) {
+            (false, false) => angle,
+            (true, false) => PI - angle,
+            (false, true) => -angle,
+            (true, true) => angle + PI,
+        };
+        return Some(ArcSegment {
+            center_pt,
+            x_radius: arc.x_radius * x_axis.x.abs(),
+            y_radius: arc.y_radius * y_axis.y.abs(),
+            rotation: 0.0,
+            angle1: map_angle(arc.angle1),
+            angle2: map_angle(arc.angle2),
+        });
+    }
+    // Allow for the rounding error in a rotation matrix.
+    let epsilon = 1e-6 * x_axis.length_squared().max(y_axis.length_squared());
+    if x_axis.dot(y_axis).abs() > epsilon ||
+       (x_axis.length_squared() - y_axis.length_squared()).abs() > epsilon {
+        return None;
+    }
+    let scale = x_axis.length();
+    let rotation = x_axis.y.atan2(x_axis.x);
+    // A reflection is a rotation after flipping the y axis, which negates the angles and the
+    // arc's rotation.
+    let (rotation, angle1, angle2) = if x_axis.perp_dot(y_axis) < 0.0 {
+        (rotation - arc.rotation, -arc.angle1, -arc.angle2)
+    } else {
+        (rotation + arc.rotation, arc.angle1, arc.angle2)
+    };
+    Some(ArcSegment {
+        center_pt,
+        x_radius: arc.x_radius * scale,
+        y_radius: arc.y_radius * scale,
+        rotation,
+        angle1,
+        angle2,
+    })
+}
+
+// An iterator over the segments of a path with an affine transform applied to them. Arcs that
+// the transform rotates or skews are replaced by cubic curves.
+pub struct Transformed<'a> {
+    iter: PathIter<'a>,
+    transform: Affine2,
+    // the cubic curves replacing an arc, in reverse order so they can be popped off
+    segs: Vec<PathSegment>,
+}
+
+impl<'a> Iterator for Transformed<'a> {
+    type Item = PathSegment;
+
+    fn next(&mut self) -> Option<PathSegment> {
+        if let Some(seg) = self.segs.pop() {
+            return Some(seg);
+        }
+        let transform = &self.transform;
+        Some(match self.iter.next()? {
+            PathSegment::Move(pt) => PathSegment::Move(transform_point(transform, pt)),
+            PathSegment::Line(pt) => PathSegment::Line(transform_point(transform, pt)),
+            PathSegment::QuadCurve(pt1, pt2) => {
+                PathSegment::QuadCurve(transform_point(transform, pt1),
+                                       transform_point(transform, pt2))
+            },
+            PathSegment::CubicCurve(pt1, pt2, pt3) => {
+                PathSegment::CubicCurve(transform_point(transform, pt1),
+                                        transform_point(transform, pt2),
+                                        transform_point(transform, pt3))
+            },
+            PathSegment::Arc(ref arc) => {
+                if let Some(arc) = transform_arc(transform, arc) {
+                    return Some(PathSegment::Arc(arc));
+                }
+                // An affine transform of a Bezier curve is the curve of its transformed points.
+                self.segs.extend(arc.to_cubics(f32::INFINITY).iter().rev().map(|bez| {
+                    PathSegment::CubicCurve(transform_point(transform, bez.p1),
+                                            transform_point(transform, bez.p2),
+                                            transform_point(transform, bez.p3))
+                }));
+                self.segs.pop().unwrap()
+            },
+            PathSegment::Close => PathSegment::Close,
+        })
+    }
+}
+
+impl<'a> PathIter<'a> {
+    pub fn transformed(self, transform: Affine2) -> Transformed<'a> {
+        Transformed {
+            iter: self,
+            transform,
+            segs: Vec::new(),
+        }
+    }
+}
+
+impl PathBuf {
+    pub fn transform(&mut self, transform: Affine2) {
+        let transformed: PathBuf = self.path_iter().transformed(transform).collect();
+        self.seg_types = transformed.seg_types;
+        self.seg_data = transformed.seg_data;
+        self.segments_changed();
+    }
+}
+
+#[test]
+fn test_transform() {
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(10.0, 0.0));
+    path.quad_curve_to(Point2::new(20.0, 0.0), Point2::new(20.0, 10.0));
+    path.cubic_curve_to(Point2::new(20.0, 30.0), Point2::new(0.0, 0.0), Point2::new(0.0, 30.0));
+    path.close();
+    path.transform(Affine2::from_scale_angle_translation(Vec2::new(2.0, 3.0), 0.0,
+                                                         Vec2::new(5.0, 0.0)));
+    let segs: Vec<_> = path.path_iter().collect();
+    let pt = |x: f32, y: f32| Point2::new(5.0 + x * 2.0, y * 3.0);
+    assert_eq!(segs, [
+        PathSegment::Move(pt(0.0, 0.0)),
+        PathSegment::Line(pt(10.0, 0.0)),
+        PathSegment::QuadCurve(pt(20.0, 0.0), pt(20.0, 10.0)),
+        PathSegment::CubicCurve(pt(20.0, 30.0), pt(0.0, 0.0), pt(0.0, 30.0)),
+        PathSegment::Close,
+    ]);
+}
+
+#[test]
+fn test_transform_arcs() {
+    use std::f32::consts::FRAC_PI_2;
+    use nalgebra::{ApproxEq, Norm, Vector2};
+    use crate::painter::AsPathIter;
+    use super::curve::Curve;
+
+    let mut path = PathBuf::new();
+    path.arc_to(Point2::new(10.0, 20.0), 30.0, 15.0, -0.5, PI * 1.5);
+    let path = path.as_path();
+    let start_pt = Point2::new(10.0 + 30.0 * (-0.5f32).cos(), 20.0 + 15.0 * (-0.5f32).sin());
+    let end_pt = Point2::new(10.0, 5.0);
+
+    // Only the skew can't be represented as an arc.
+    let transforms = [
+        (Affine2::from_scale_angle_translation(Vec2::new(2.0, -3.0), 0.0, Vec2::new(5.0, 0.0)),
+         true),
+        (Affine2::from_scale_angle_translation(Vec2::new(-2.0, -3.0), 0.0, Vec2::new(5.0, 0.0)),
+         true),
+        (Affine2::from_angle(0.3), true),
+        (Affine2::from_angle(0.3) * Affine2::from_scale(Vec2::new(2.0, -2.0)), true),
+        (Affine2::from_cols_array(&[1.0, 0.5, 0.0, 1.0, 0.0, 0.0]), false),
+    ];
+    for &(ref transform, is_arc) in &transforms {
+        let transformed: PathBuf = path.path_iter().transformed(*transform).collect();
+        let transformed = transformed.as_path();
+        let arc_count = transformed.path_iter().filter(|seg| match *seg {
+            PathSegment::Arc(_) => true,
+            _ => false,
+        }).count();
+        assert_eq!(arc_count, if is_arc { 1 } else { 0 });
+
+        // Every point of the new curves is on the transformed ellipse.
+        let inverse = transform.inverse();
+        let curves: Vec<_> = transformed.curves().map(|(_, curve)| curve).collect();
+        assert_approx_eq_eps!(curves[0].point_at(0.0), transform_point(transform, start_pt),
+                              1e-3);
+        assert_approx_eq_eps!(curves.last().unwrap().end_point(),
+                              transform_point(transform, end_pt), 1e-3);
+        for curve in &curves {
+            if let Curve::Line(..) = *curve {
+                continue;
+            }
+            for i in 0..=10 {
+                let pt = transform_point(&inverse, curve.point_at(i as f32 / 10.0));
+                let v = Vector2::new((pt.x - 10.0) / 30.0, (pt.y - 20.0) / 15.0);
+                assert_approx_eq_eps!(v.norm(), 1.0, 1e-3);
+            }
+        }
+    }
+
+    // A circle stays an arc when rotated and reflected.
+    let mut path = PathBuf::new();
+    path.arc_to(Point2::new(0.0, 0.0), 10.0, 10.0, 0.0, FRAC_PI_2);
+    let transform = Affine2::from_angle(0.3) * Affine2::from_scale(Vec2::new(2.0, -2.0));
+    path.transform(transform);
+    match path.path_iter().nth(1) {
+        Some(PathSegment::Arc(arc)) => {
+            assert_approx_eq!(arc.x_radius, 20.0);
+            assert_approx_eq!(arc.y_radius, 20.0);
+            assert_approx_eq_eps!(arc.point_at_angle(arc.angle1),
+                                  transform_point(&transform, Point2::new(10.0, 0.0)), 1e-4);
+            assert_approx_eq_eps!(arc.end_point(),
+                                  transform_point(&transform, Point2::new(0.0, 10.0)), 1e-4);
+        },
+        seg => panic!("expected an arc but got {:?}", seg),
+    }
+}
@@ -0,0 +1,173 @@
+use nalgebra::origin;
+
+use crate::painter::AsPathIter;
+use super::curve::Curve;
+use super::{Path, PathBuf, PathSegment};
+
+// Where a trimmed part of a path starts or ends, measured along the whole path. Moves have no
+// length, and a close has the length of the line back to the start of its subpath.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrimPosition {
+    // A fraction of the length of the path, from 0 at the start to 1 at the end
+    Fraction(f32),
+    // A distance from the start of the path
+    Distance(f32),
+}
+
+impl TrimPosition {
+    fn distance(&self, length: f32) -> f32 {
+        let distance = match *self {
+            TrimPosition::Fraction(fraction) => fraction * length,
+            TrimPosition::Distance(distance) => distance,
+        };
+        distance.max(0.0).min(length)
+    }
+}
+
+impl<'a> Path<'a> {
+    // Returns the part of the path between `start` and `end`, which are clamped to the path's
+    // length. The curves where they are get split there, and each subpath the part goes through
+    // is its own subpath in the returned path. A closed subpath is only still closed if all of it
+    // is in the part. If `end` isn't after `start`, the returned path is empty.
+    //
+    // Animating `end` from 0 to 1 makes a path look like it's being drawn.
+    pub fn trim(&self, start: TrimPosition, end: TrimPosition) -> PathBuf {
+        let length = self.length(..);
+        let start = start.distance(length);
+        let end = end.distance(length);
+        let mut path = PathBuf::new();
+        if start >= end {
+            return path;
+        }
+
+        // The distance along the path at the start of the current segment
+        let mut distance = 0.0;
+        // The distance where the current piece of a subpath starts, which is either after a move
+        // or after a close without a move
+        let mut contour_distance = 0.0;
+        // Whether the returned path's current point is where the current segment starts
+        let mut is_continued = false;
+        let mut current_pt = origin();
+        let mut subpath_start_pt = origin();
+        for seg in self.path_iter() {
+            let curve = match seg {
+                PathSegment::Move(pt) => {
+                    subpath_start_pt = pt;
+                    current_pt = pt;
+                    contour_distance = distance;
+                    is_continued = false;
+                    continue;
+                },
+                PathSegment::Close => Curve::Line(current_pt, subpath_start_pt),
+                _ => Curve::from_segment(current_pt, &seg).unwrap(),
+            };
+            current_pt = curve.end_point();
+            let curve_length = curve.length();
+            let curve_start = distance;
+            distance += curve_length;
+            if distance <= start || curve_start >= end {
+                is_continued = false;
+            } else {
+                let t0 = if curve_start < start {
+                    curve.t_at_distance(start - curve_start, curve_length)
+                } else {
+                    0.0
+                };
+                let t1 = if distance > end {
+                    curve.t_at_distance(end - curve_start, curve_length)
+                } else {
+                    1.0
+                };
+                let piece = if t0 > 0.0 || t1 < 1.0 { curve.subcurve(t0, t1) } else { curve };
+                if !is_continued {
+                    path.move_to(piece.point_at(0.0));
+                }
+                if seg == PathSegment::Close && is_continued && contour_distance >= start &&
+                   t1 == 1.0 {
+                    path.close();
+                } else {
+                    path.push_curve(&piece);
+                }
+                is_continued = true;
+            }
+            if seg == PathSegment::Close {
+                contour_distance = distance;
+            }
+        }
+        path
+    }
+}
+
+#[test]
+fn test_trim() {
+    use std::f32::consts::PI;
+    use nalgebra::ApproxEq;
+    use crate::{Point2, Rect};
+    use super::ArcSegment;
+
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.line_to(Point2::new(100.0, 0.0));
+    path.arc_to(Point2::new(100.0, 50.0), 50.0, 50.0, -PI * 0.5, PI * 0.5);
+    let length = 100.0 + PI * 50.0;
+
+    let trimmed = path.as_path().trim(TrimPosition::Distance(50.0),
+                                      TrimPosition::Distance(100.0 + PI * 25.0));
+    let segs: Vec<_> = trimmed.path_iter().collect();
+    assert_eq!(segs[..2], [
+        PathSegment::Move(Point2::new(50.0, 0.0)),
+        PathSegment::Line(Point2::new(100.0, 0.0)),
+    ]);
+    match segs[2] {
+        PathSegment::Arc(arc) => {
+            assert_eq!(arc, ArcSegment { angle2: arc.angle2, ..ArcSegment::new(
+                Point2::new(100.0, 50.0), 50.0, 50.0, 0.0, -PI * 0.5, 0.0) });
+            assert_approx_eq_eps!(arc.angle2, 0.0, 1e-4);
+        },
+        ref seg => panic!("expected an arc but got {:?}", seg),
+    }
+    assert_eq!(segs.len(), 3);
+    let trimmed = path.as_path().trim(TrimPosition::Fraction(0.25), TrimPosition::Fraction(0.75));
+    assert_approx_eq_eps!(trimmed.as_path().length(..), length * 0.5, 1e-3);
+    let start_pt = match trimmed.path_iter().next() {
+        Some(PathSegment::Move(pt)) => pt,
+        seg => panic!("expected a move but got {:?}", seg),
+    };
+    assert_approx_eq_eps!(start_pt, Point2::new(length * 0.25, 0.0), 1e-4);
+
+    // The whole path is the same path, and an empty range gives an empty path.
+    let trimmed = path.as_path().trim(TrimPosition::Fraction(-1.0), TrimPosition::Fraction(2.0));
+    assert_eq!(trimmed.path_iter().collect::<Vec<_>>(), path.path_iter().collect::<Vec<_>>());
+    let trimmed = path.as_path().trim(TrimPosition::Fraction(0.5), TrimPosition::Distance(10.0));
+    assert_eq!(trimmed.path_iter().count(), 0);
+
+    // A cubic curve is split where the distances are.
+    let mut path = PathBuf::new();
+    path.move_to(Point2::new(0.0, 0.0));
+    path.cubic_curve_to(Point2::new(0.0, 100.0), Point2::new(100.0, 100.0),
+                        Point2::new(100.0, 0.0));
+    let length = path.as_path().length(..);
+    let trimmed = path.as_path().trim(TrimPosition::Distance(20.0), TrimPosition::Distance(60.0));
+    assert_approx_eq_eps!(trimmed.as_path().length(..), 40.0, 1e-3);
+    let (pt, _) = path.as_path().point_at_distance(.., 60.0).unwrap();
+    assert_approx_eq_eps!(trimmed.current_point().unwrap(), pt, 1e-3);
+    let trimmed = path.as_path().trim(TrimPosition::Fraction(0.5), TrimPosition::Fraction(1.0));
+    assert_approx_eq_eps!(trimmed.as_path().length(..), length * 0.5, 1e-3);
+
+    // A closed subpath stays closed only if all of it is kept, and the part after it is in its
+    // own subpath.
+    let mut path = PathBuf::rect(Rect::new(0.0, 0.0, 10.0, 10.0));
+    path.move_to(Point2::new(20.0, 0.0));
+    path.line_to(Point2::new(30.0, 0.0));
+    let trimmed = path.as_path().trim(TrimPosition::Distance(0.0), TrimPosition::Distance(45.0));
+    let segs: Vec<_> = trimmed.path_iter().collect();
+    assert_eq!(segs.len(), 7);
+    assert_eq!(segs[4], PathSegment::Close);
+    assert_eq!(segs[5], PathSegment::Move(Point2::new(20.0, 0.0)));
+    assert_eq!(segs[6], PathSegment::Line(Point2::new(25.0, 0.0)));
+    let trimmed = path.as_path().trim(TrimPosition::Distance(5.0), TrimPosition::Distance(40.0));
+    let segs: Vec<_> = trimmed.path_iter().collect();
+    assert_eq!(segs[0], PathSegment::Move(Point2::new(5.0, 0.0)));
+    assert_eq!(segs[4], PathSegment::Line(Point2::new(0.0, 0.0)));
+    assert_eq!(segs.len(), 5);
+}
@@ -1,8 +1,10 @@
 
 use std::fmt::Debug;
 use std::ops::{Add, Div, Sub};
-use super::{LargerFloat, Point2, Vector2};
-use super::nalgebra::{ApproxEq, BaseFloat, Cast, cast, Norm};
+use super::{LargerFloat, Point2, Rect, Vector2};
+use super::cubic_bezier::{solve_cubic, solve_quadratic, split_at_ts};
+use super::nalgebra::{ApproxEq, BaseFloat, Cast, cast, Dot, Norm};
+use super::smallvec::SmallVec;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct QuadBezier<N> {
@@ -61,7 +63,8 @@ impl<N, F> QuadBezier<N> where F: BaseFloat
                                 + LargerFloat<Float = F>
                                 + Sub<Output = N>
                                 + Add<Output = N>
-                                + Div<Output = N> {
+                                + Div<Output = N>,
+                               f32: Cast<F> {
 
     pub fn tangent_at(&self, t: f32) -> Vector2<N> {
         let _1_0: N::Float = cast(1.0);
@@ -73,6 +76,30 @@ impl<N, F> QuadBezier<N> where F: BaseFloat
         cast(((p1 - p0) * (_1_0 - t) + (p2 - p1) * t).normalize())
     }
 
+    pub fn split(&self, t: f32) -> (QuadBezier<N>, QuadBezier<N>) {
+        // https://pomax.github.io/bezierinfo/#splitting
+        let t: N::Float = cast(t);
+        let (p0, p1, p2) = (cast::<Point2<N>, Point2<N::Float>>(self.p0),
+                            cast::<Point2<N>, Point2<N::Float>>(self.p1),
+                            cast::<Point2<N>, Point2<N::Float>>(self.p2));
+        let lv1p0 = p0 + (p1 - p0) * t;
+        let lv1p1 = p1 + (p2 - p1) * t;
+
+        let lv2p = lv1p0 + (lv1p1 - lv1p0) * t;
+
+        let bez0 = QuadBezier {
+            p0: p0,
+            p1: lv1p0,
+            p2: lv2p,
+        };
+        let bez1 = QuadBezier {
+            p0: lv2p,
+            p1: lv1p1,
+            p2: p2,
+        };
+        (cast(bez0), cast(bez1))
+    }
+
     pub fn point_at(&self, t: f32) -> Point2<N> {
         let _1_0: N::Float = cast(1.0);
         let _2_0: N::Float = cast(2.0);
@@ -87,6 +114,133 @@ impl<N, F> QuadBezier<N> where F: BaseFloat
         let p2 = p2.to_vector();
         cast(p0 * one_m_t * one_m_t + p1 * _2_0 * one_m_t * t + p2 * t * t)
     }
+
+    // Returns the derivative of the curve with respect to t.
+    pub fn derivative_at(&self, t: f32) -> Vector2<N> {
+        let _1_0: N::Float = cast(1.0);
+        let _2_0: N::Float = cast(2.0);
+        let t: N::Float = cast(t);
+        let (p0, p1, p2) = (cast::<Point2<N>, Point2<N::Float>>(self.p0),
+                            cast::<Point2<N>, Point2<N::Float>>(self.p1),
+                            cast::<Point2<N>, Point2<N::Float>>(self.p2));
+
+        cast(((p1 - p0) * (_1_0 - t) + (p2 - p1) * t) * _2_0)
+    }
+
+    // Returns the second derivative of the curve, which is the same at every t.
+    pub fn second_derivative(&self) -> Vector2<N> {
+        let _2_0: N::Float = cast(2.0);
+        let (p0, p1, p2) = (cast::<Point2<N>, Point2<N::Float>>(self.p0),
+                            cast::<Point2<N>, Point2<N::Float>>(self.p1),
+                            cast::<Point2<N>, Point2<N::Float>>(self.p2));
+
+        cast(((p2 - p1) - (p1 - p0)) * _2_0)
+    }
+
+    // Returns the curvature at t, with the same sign as `CubicBezier::curvature_at()`.
+    pub fn curvature_at(&self, t: f32) -> N::Float {
+        let deriv = cast::<Vector2<N>, Vector2<N::Float>>(self.derivative_at(t));
+        let second_deriv = cast::<Vector2<N>, Vector2<N::Float>>(self.second_derivative());
+        let speed = deriv.norm();
+        (deriv.x * second_deriv.y - deriv.y * second_deriv.x) / (speed * speed * speed)
+    }
+
+    // Returns the t value strictly between 0 and 1 where the x (`axis` = 0) or y (`axis` = 1)
+    // coordinate has a minimum or maximum, if there is one.
+    pub fn extrema(&self, axis: usize) -> SmallVec<[f32; 2]> {
+        let _2_0: N::Float = cast(2.0);
+        let (p0, p1, p2) = (cast::<Point2<N>, Point2<N::Float>>(self.p0),
+                            cast::<Point2<N>, Point2<N::Float>>(self.p1),
+                            cast::<Point2<N>, Point2<N::Float>>(self.p2));
+
+        let mut ts = SmallVec::new();
+        let denom: f32 = cast(p0[axis] - _2_0 * p1[axis] + p2[axis]);
+        if denom != 0.0 {
+            let t = cast::<N::Float, f32>(p0[axis] - p1[axis]) / denom;
+            if t > 0.0 && t < 1.0 {
+                ts.push(t);
+            }
+        }
+        ts
+    }
+
+    // Splits the curve where it turns around in x or y, which makes pieces that each only go one
+    // way in x and in y.
+    pub fn split_at_extrema(&self) -> SmallVec<[QuadBezier<N>; 3]> {
+        let mut ts: SmallVec<[f32; 2]> = self.extrema(0);
+        ts.extend(self.extrema(1));
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        ts.dedup();
+        split_at_ts(*self, &ts, |bez, t| bez.split(t))
+    }
+
+    // Returns the point on the curve closest to `pt`, its t, and its distance from `pt`. Other
+    // than at the ends, the closest point is where the line to `pt` is perpendicular to the curve,
+    // which is a root of the cubic equation derived in dist_to_quad_curve.tex.
+    pub fn project_point(&self, pt: Point2<N>) -> (Point2<N>, f32, N::Float) {
+        let _2_0: N::Float = cast(2.0);
+        let _3_0: N::Float = cast(3.0);
+        let (p0, p1, p2) = (cast::<Point2<N>, Point2<N::Float>>(self.p0),
+                            cast::<Point2<N>, Point2<N::Float>>(self.p1),
+                            cast::<Point2<N>, Point2<N::Float>>(self.p2));
+        let pt_f = cast::<Point2<N>, Point2<N::Float>>(pt);
+
+        let a = p1 - p0;
+        let b = (p0 - p1) + (p2 - p1);
+        let p_prime = p0 - pt_f;
+        let coef_a: f32 = cast(b.dot(&b));
+        let coef_b: f32 = cast(_3_0 * a.dot(&b));
+        let coef_c: f32 = cast(_2_0 * a.dot(&a) + p_prime.dot(&b));
+        let coef_d: f32 = cast(p_prime.dot(&a));
+        let mut ts: SmallVec<[f32; 5]> = SmallVec::new();
+        ts.push(0.0);
+        ts.push(1.0);
+        // When the control points are evenly spaced on a line, B is zero, and so is the cubic
+        // term.
+        if coef_a.abs() <= 1e-6 * (coef_b.abs() + coef_c.abs() + coef_d.abs()) {
+            ts.extend(solve_quadratic(coef_b, coef_c, coef_d));
+        } else {
+            ts.extend_from_slice(solve_cubic(coef_a, coef_b, coef_c, coef_d).get());
+        }
+
+        let mut closest = (p0, 0.0, (p0 - pt_f).norm());
+        for &t in ts.iter().filter(|&&t| t >= 0.0 && t <= 1.0) {
+            let curve_pt = cast::<Point2<N>, Point2<N::Float>>(self.point_at(t));
+            let dist = (curve_pt - pt_f).norm();
+            if dist < closest.2 {
+                closest = (curve_pt, t, dist);
+            }
+        }
+        (cast(closest.0), closest.1, closest.2)
+    }
+
+    pub fn bounding_box(&self) -> Rect<N> {
+        let _0_0: N::Float = cast(0.0);
+        let _1_0: N::Float = cast(1.0);
+        let _2_0: N::Float = cast(2.0);
+        let (p0, p1, p2) = (cast::<Point2<N>, Point2<N::Float>>(self.p0),
+                            cast::<Point2<N>, Point2<N::Float>>(self.p1),
+                            cast::<Point2<N>, Point2<N::Float>>(self.p2));
+
+        let mut min = Point2::new(p0.x.min(p2.x), p0.y.min(p2.y));
+        let mut max = Point2::new(p0.x.max(p2.x), p0.y.max(p2.y));
+        // The derivative is linear, so each coordinate has at most one extremum.
+        for axis in 0..2 {
+            let denom = p0[axis] - _2_0 * p1[axis] + p2[axis];
+            if denom == _0_0 {
+                continue;
+            }
+            let t = (p0[axis] - p1[axis]) / denom;
+            if t > _0_0 && t < _1_0 {
+                let one_m_t = _1_0 - t;
+                let value = p0[axis] * one_m_t * one_m_t + _2_0 * p1[axis] * one_m_t * t
+                          + p2[axis] * t * t;
+                min[axis] = min[axis].min(value);
+                max[axis] = max[axis].max(value);
+            }
+        }
+        Rect::new(cast(min.x), cast(min.y), cast(max.x - min.x), cast(max.y - min.y))
+    }
 }
 
 #[test]
@@ -116,3 +270,123 @@ fn test_point_at() {
     assert_approx_eq!(bez.point_at(0.5), Point2::new(113.75, 152.5));
     assert_approx_eq_eps!(bez.point_at(0.3), Point2::new(140.95, 114.1), 0.00001);
 }
+
+#[test]
+fn test_bounding_box() {
+    let bez = QuadBezier::new(Point2::new(220.0, 40.0),
+                              Point2::new(50.0, 180.0),
+                              Point2::new(135.0, 210.0));
+    // The leftmost point is at t = 170 / 255.
+    let rect = bez.bounding_box();
+    assert_approx_eq!(rect.x, bez.point_at(170.0 / 255.0).x);
+    assert_approx_eq!(rect.y, 40.0);
+    assert_approx_eq!(rect.right(), 220.0);
+    assert_approx_eq!(rect.bottom(), 210.0);
+
+    let bez = QuadBezier::new(Point2::new(0.0, 0.0), Point2::new(10.0, 20.0),
+                              Point2::new(20.0, 0.0));
+    assert_approx_eq!(bez.bounding_box(), Rect::new(0.0, 0.0, 20.0, 10.0));
+}
+
+#[test]
+fn test_project_point() {
+    let bez = QuadBezier::new(Point2::new(0.0f32, 0.0), Point2::new(10.0, 20.0),
+                              Point2::new(20.0, 0.0));
+    let (pt, t, dist) = bez.project_point(Point2::new(10.0, 30.0));
+    assert_approx_eq!(pt, Point2::new(10.0, 10.0));
+    assert_approx_eq!(t, 0.5);
+    assert_approx_eq!(dist, 20.0);
+    // before the start
+    let (pt, t, dist) = bez.project_point(Point2::new(-3.0, -4.0));
+    assert_eq!((pt, t), (bez.p0, 0.0));
+    assert_approx_eq!(dist, 5.0);
+
+    // No point along the curve is closer than the one found.
+    let bez = QuadBezier::new(Point2::new(220.0f32, 40.0),
+                              Point2::new(50.0, 180.0),
+                              Point2::new(135.0, 210.0));
+    for &(x, y) in &[(100.0, 100.0), (150.0, 150.0), (0.0, 300.0), (200.0, 200.0)] {
+        let target = Point2::new(x, y);
+        let (pt, t, dist) = bez.project_point(target);
+        assert_approx_eq!((pt - target).norm(), dist);
+        assert_approx_eq_eps!(bez.point_at(t), pt, 1e-3);
+        for i in 0..=1000 {
+            assert!((bez.point_at(i as f32 / 1000.0) - target).norm() >= dist - 1e-3);
+        }
+    }
+
+    // The cubic equation has one real root here, which the formula alone only finds roughly.
+    let bez = QuadBezier::new(Point2::new(84.0f32, 6.0), Point2::new(32.0, 32.0),
+                              Point2::new(5.0, 62.0));
+    let target = Point2::new(84.0, 21.0);
+    let (_, _, dist) = bez.project_point(target);
+    let closest_dist = (0..=10000).map(|i| (bez.point_at(i as f32 / 10000.0) - target).norm())
+                                  .fold(f32::INFINITY, f32::min);
+    assert!(dist <= closest_dist + 1e-4, "{} > {}", dist, closest_dist);
+
+    // evenly spaced control points on a line
+    let bez = QuadBezier::new(Point2::new(0.0f32, 0.0), Point2::new(10.0, 0.0),
+                              Point2::new(20.0, 0.0));
+    let (pt, t, dist) = bez.project_point(Point2::new(5.0, 3.0));
+    assert_approx_eq!(pt, Point2::new(5.0, 0.0));
+    assert_approx_eq!(t, 0.25);
+    assert_approx_eq!(dist, 3.0);
+}
+
+#[test]
+fn test_split() {
+    let bez = QuadBezier::new(Point2::new(220.0, 40.0),
+                              Point2::new(50.0, 180.0),
+                              Point2::new(135.0, 210.0));
+    let (bez0, bez1) = bez.split(0.3);
+    assert_approx_eq!(bez0.p0, bez.p0);
+    assert_approx_eq_eps!(bez0.p2, bez.point_at(0.3), 0.0001);
+    assert_approx_eq_eps!(bez1.p0, bez.point_at(0.3), 0.0001);
+    assert_approx_eq!(bez1.p2, bez.p2);
+    assert_approx_eq_eps!(bez0.point_at(0.5), bez.point_at(0.15), 0.0001);
+    assert_approx_eq_eps!(bez1.point_at(0.5), bez.point_at(0.65), 0.0001);
+}
+
+#[test]
+fn test_derivatives() {
+    let bez = QuadBezier::new(Point2::new(220.0f32, 40.0),
+                              Point2::new(50.0, 180.0),
+                              Point2::new(135.0, 210.0));
+    assert_approx_eq!(bez.derivative_at(0.0), Vector2::new(-340.0, 280.0));
+    assert_approx_eq!(bez.derivative_at(1.0), Vector2::new(170.0, 60.0));
+    assert_approx_eq!(bez.second_derivative(), Vector2::new(510.0, -220.0));
+    for &t in &[0.1, 0.3, 0.5, 0.8] {
+        let h = 1e-3;
+        let diff = (bez.point_at(t + h) - bez.point_at(t - h)) / (2.0 * h);
+        assert_approx_eq_eps!(bez.derivative_at(t), diff, 0.5);
+    }
+
+    // The curve is y = 2x - x² / 10, which at its top has a radius of 5. Going right, it turns
+    // left when y points down, so the curvature is negative, and going the other way it's
+    // positive.
+    let bez = QuadBezier::new(Point2::new(0.0f32, 0.0), Point2::new(10.0, 20.0),
+                              Point2::new(20.0, 0.0));
+    assert_approx_eq!(bez.curvature_at(0.5), -1.0 / 5.0);
+    let bez = QuadBezier::new(bez.p2, bez.p1, bez.p0);
+    assert_approx_eq!(bez.curvature_at(0.5), 1.0 / 5.0);
+}
+
+#[test]
+fn test_split_at_extrema() {
+    let bez = QuadBezier::new(Point2::new(220.0f32, 40.0),
+                              Point2::new(50.0, 180.0),
+                              Point2::new(135.0, 210.0));
+    assert_eq!(bez.extrema(0).into_vec(), vec![170.0 / 255.0]);
+    assert!(bez.extrema(1).is_empty());
+    let pieces = bez.split_at_extrema();
+    assert_eq!(pieces.len(), 2);
+    assert_eq!(pieces[0].p0, bez.p0);
+    assert_eq!(pieces[0].p2, pieces[1].p0);
+    assert_approx_eq_eps!(pieces[0].p2.x, bez.bounding_box().x, 1e-3);
+    assert_eq!(pieces[1].p2, bez.p2);
+
+    // A curve without extrema isn't split.
+    let bez = QuadBezier::new(Point2::new(0.0f32, 0.0), Point2::new(10.0, 5.0),
+                              Point2::new(20.0, 20.0));
+    assert_eq!(bez.split_at_extrema().into_vec(), vec![bez]);
+}
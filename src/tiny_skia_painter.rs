@@ -10,9 +10,9 @@ use tiny_skia::{Paint, PathBuilder, Pixmap, Shader, Stroke};
 
 use crate::color::{srgb_to_linear, linear_to_srgb};
 use crate::font::{Font, GlyphImageFormat};
-use crate::{Color, PathSegment};
+use crate::{Color, PathBuf, PathSegment};
 use crate::painter::{Brush, Error, Painter};
-use crate::path::{ArcSegment, LineCap, LineJoin, StrokeStyle};
+use crate::path::{LineCap, LineJoin, StrokeStyle};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TinySkiaPainterByteOrder {
@@ -57,7 +57,11 @@ impl TinySkiaPainter {
         }
     }
 
-    fn path_to_path(path: &mut dyn Iterator<Item=PathSegment>) -> Option<tiny_skia::Path> {
+    // Arcs are converted to cubic curves within `tolerance`.
+    fn path_to_path(
+        path: &mut dyn Iterator<Item=PathSegment>,
+        tolerance: f32,
+    ) -> Option<tiny_skia::Path> {
         let mut builder = PathBuilder::new();
         for seq in path {
             match seq {
@@ -81,8 +85,16 @@ impl TinySkiaPainter {
                     );
                 }
                 PathSegment::Arc(arc_seg) => {
-                    // I can either call builder.conic_to() or I could remove Arc from the enum
-                    todo!()
+                    for bez in arc_seg.to_cubics(tolerance) {
+                        builder.cubic_to(
+                            bez.p1.x,
+                            bez.p1.y,
+                            bez.p2.x,
+                            bez.p2.y,
+                            bez.p3.x,
+                            bez.p3.y,
+                        );
+                    }
                 },
                 PathSegment::Close => builder.close(),
 
@@ -103,20 +115,72 @@ impl TinySkiaPainter {
     //     )
     // }
 
-    fn line_cap_to_line_cap(end_cap: LineCap) -> tiny_skia::LineCap {
+    fn line_cap_to_line_cap(end_cap: LineCap) -> Option<tiny_skia::LineCap> {
         match end_cap {
-            LineCap::Flat => tiny_skia::LineCap::Butt,
-            LineCap::Square => tiny_skia::LineCap::Square,
-            LineCap::Round => tiny_skia::LineCap::Round,
+            LineCap::Flat => Some(tiny_skia::LineCap::Butt),
+            LineCap::Square => Some(tiny_skia::LineCap::Square),
+            LineCap::Round => Some(tiny_skia::LineCap::Round),
+            LineCap::Triangular => None,
         }
     }
 
-    fn line_join_to_line_join(join_style: LineJoin) -> tiny_skia::LineJoin {
+    fn line_join_to_line_join(join_style: LineJoin) -> Option<tiny_skia::LineJoin> {
         match join_style {
-            LineJoin::Round => tiny_skia::LineJoin::Round,
-            LineJoin::Bevel => tiny_skia::LineJoin::Bevel,
-            LineJoin::Miter(_) => tiny_skia::LineJoin::Miter,
+            LineJoin::Round => Some(tiny_skia::LineJoin::Round),
+            LineJoin::Bevel => Some(tiny_skia::LineJoin::Bevel),
+            LineJoin::Miter(_) | LineJoin::MiterRevert(_) => Some(tiny_skia::LineJoin::Miter),
+            LineJoin::None | LineJoin::MiterTruncate(_) => None,
+        }
+    }
+
+    // Returns `None` if tiny-skia can't stroke with the style.
+    fn style_to_stroke(style: &StrokeStyle) -> Option<Stroke> {
+        // tiny-skia uses the same cap for both ends of the path and the ends of dashes.
+        let (initial_end_cap, terminal_end_cap) = style.end_caps();
+        if terminal_end_cap != initial_end_cap {
+            return None;
         }
+        let dash = style.even_dash_pattern()
+            .and_then(|pattern| tiny_skia::StrokeDash::new(pattern, style.dash_offset));
+        if dash.is_some() && style.dash_caps() != (initial_end_cap, initial_end_cap) {
+            return None;
+        }
+        Some(Stroke {
+            width: style.width,
+            miter_limit: style.line_join.miter_limit().unwrap_or(4.0) as f32,
+            line_cap: Self::line_cap_to_line_cap(initial_end_cap)?,
+            line_join: Self::line_join_to_line_join(style.line_join)?,
+            dash,
+        })
+    }
+
+    // Returns how far curves can be from their approximations so that they are within a tenth of a
+    // pixel after being transformed. It is infinite if the transform scales everything to zero.
+    fn tolerance(&self) -> f32 {
+        let t = self.transform;
+        0.1 / (t.sx * t.sy - t.kx * t.ky).abs().sqrt()
+    }
+
+    // Strokes the path by filling its outline, for when tiny-skia can't stroke it with the style.
+    fn fill_stroke_outline(
+        &mut self,
+        path: &mut dyn Iterator<Item=PathSegment>,
+        paint: &Paint,
+        style: &StrokeStyle,
+    ) {
+        let path: PathBuf = path.collect();
+        let tolerance = self.tolerance();
+        if !tolerance.is_finite() {
+            return;
+        }
+        let outline = path.as_path().stroke_outline(style, tolerance);
+        // An outline with nothing in it is fine, since it just means there's nothing to draw.
+        let outline = match Self::path_to_path(&mut outline.path_iter(), tolerance) {
+            Some(outline) => outline,
+            None => return,
+        };
+        let mut pixmap = self.pixmap.borrow_mut();
+        pixmap.fill_path(&outline, paint, tiny_skia::FillRule::Winding, self.transform, None);
     }
 
     // fn vec2_to_point(vec: Point2<f32>) -> tiny_skia::Point {
@@ -143,34 +207,25 @@ impl<'a> Painter for TinySkiaPainter {
         brush: &Brush,
         style: &StrokeStyle,
     ) {
-        let path = match Self::path_to_path(path) {
-            Some(path) => path,
-            None => {
-                self.err.push(Error::InvalidPath(Backtrace::capture()));
-                return;
-            }
-        };
         let paint = Paint {
             shader: Self::brush_to_shader(brush, self.byte_order),
             blend_mode: tiny_skia::BlendMode::SourceOver,
             anti_alias: true,
             force_hq_pipeline: false,
         };
-        // let dash = if style.dash_pattern.is_empty() {
-        //     None
-        // } else {
-        //     tiny_skia::StrokeDash::new(
-        //         style.dash_pattern.iter().map(|&f| f as f32).collect(),
-        //         style.dash_offset as f32,
-        //     )
-        // };
-        let dash = None;
-        let stroke = Stroke {
-            width: style.width,
-            miter_limit: style.line_join.miter_limit().unwrap_or(4.0) as f32,
-            line_cap: Self::line_cap_to_line_cap(style.line_cap),
-            line_join: Self::line_join_to_line_join(style.line_join),
-            dash,
+        let stroke = match Self::style_to_stroke(style) {
+            Some(stroke) => stroke,
+            None => {
+                self.fill_stroke_outline(path, &paint, style);
+                return;
+            }
+        };
+        let path = match Self::path_to_path(path, self.tolerance()) {
+            Some(path) => path,
+            None => {
+                self.err.push(Error::InvalidPath(Backtrace::capture()));
+                return;
+            }
         };
         let mut pixmap = self.pixmap.borrow_mut();
         pixmap.stroke_path(&path, &paint, &stroke, self.transform, None);